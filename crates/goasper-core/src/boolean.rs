@@ -0,0 +1,722 @@
+//! Boolean operations on polygons with integer coordinates.
+//!
+//! Both inputs are read with the nonzero rule, each polygon counting once whatever its
+//! orientation, so overlapping polygons of one input act as their union. Edges are
+//! split where they meet, every piece is classified by the winding numbers on its two
+//! sides (an exact scanline over the pieces), and the pieces that bound the result
+//! are linked into loops. Crossings of skew edges are rounded to the nearest grid
+//! point; everything else, Manhattan and 45° geometry included, stays exact.
+//!
+//! Results are counterclockwise outlines. A hole is joined to the outline around it
+//! by a keyhole cut, so every result is a single [`Polygon`].
+
+use goasper_io::Polygon;
+use std::{cmp::Ordering, collections::HashMap};
+
+type Pt = (i64, i64);
+
+/// How the two inputs combine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    And,
+    Or,
+    Xor,
+    /// A and not B.
+    Not,
+}
+
+impl BooleanOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            BooleanOp::And => a && b,
+            BooleanOp::Or => a || b,
+            BooleanOp::Xor => a != b,
+            BooleanOp::Not => a && !b,
+        }
+    }
+}
+
+/// `a op b`, as non-overlapping polygons on `layer` (layer, datatype).
+pub fn boolean(a: &[Polygon], b: &[Polygon], op: BooleanOp, layer: (u16, u16)) -> Vec<Polygon> {
+    let mut segs = Vec::new();
+    add_edges(a, 0, &mut segs);
+    add_edges(b, 1, &mut segs);
+    let segs = merge_duplicates(split(segs));
+    let sides = classify(&segs);
+
+    let mut edges = Vec::new();
+    for (s, (left, right)) in segs.iter().zip(sides) {
+        let inside_left = op.apply(left[0] > 0, left[1] > 0);
+        let inside_right = op.apply(right[0] > 0, right[1] > 0);
+        if inside_left && !inside_right {
+            edges.push((s.p, s.q));
+        } else if inside_right && !inside_left {
+            edges.push((s.q, s.p));
+        }
+    }
+
+    let (mut outers, mut holes) = (Vec::new(), Vec::new());
+    for ring in link(&edges) {
+        match area2(&ring).cmp(&0) {
+            Ordering::Greater => outers.push(ring),
+            Ordering::Less => holes.push(ring),
+            Ordering::Equal => {}
+        }
+    }
+    let mut owned: Vec<Vec<Vec<Pt>>> = vec![Vec::new(); outers.len()];
+    for hole in holes {
+        if let Some(i) = enclosing(&outers, &hole) {
+            owned[i].push(hole);
+        }
+    }
+    outers
+        .into_iter()
+        .zip(owned)
+        .map(|(outer, holes)| Polygon {
+            layer: layer.0,
+            datatype: layer.1,
+            xy: keyhole(outer, holes)
+                .into_iter()
+                .map(|(x, y)| (to_i32(x), to_i32(y)))
+                .collect(),
+        })
+        .collect()
+}
+
+/// The union of `polys` as non-overlapping polygons on `layer` (layer, datatype),
+/// holes keyholed like every [`boolean`] result.
+pub fn merge(polys: &[Polygon], layer: (u16, u16)) -> Vec<Polygon> {
+    boolean(polys, &[], BooleanOp::Or, layer)
+}
+
+/// An edge from `p` to `q` with `p < q`, carrying how many times each input crosses
+/// it counterclockwise (negative when running from `q` to `p`).
+#[derive(Debug, Clone, Copy)]
+struct Seg {
+    p: Pt,
+    q: Pt,
+    w: [i32; 2],
+}
+
+impl Seg {
+    fn new(a: Pt, b: Pt, w: [i32; 2]) -> Self {
+        if a < b {
+            Seg { p: a, q: b, w }
+        } else {
+            Seg {
+                p: b,
+                q: a,
+                w: [-w[0], -w[1]],
+            }
+        }
+    }
+}
+
+fn add_edges(polys: &[Polygon], input: usize, out: &mut Vec<Seg>) {
+    for poly in polys {
+        let mut ring: Vec<Pt> = Vec::with_capacity(poly.xy.len());
+        for &(x, y) in &poly.xy {
+            let p = (x as i64, y as i64);
+            if ring.last() != Some(&p) {
+                ring.push(p);
+            }
+        }
+        while ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        // count every polygon counterclockwise, so the nonzero rule reads it as inside
+        let sign = match area2(&ring).cmp(&0) {
+            Ordering::Greater => 1,
+            Ordering::Less => -1,
+            Ordering::Equal => continue,
+        };
+        let mut w = [0; 2];
+        w[input] = sign;
+        for i in 0..ring.len() {
+            out.push(Seg::new(ring[i], ring[(i + 1) % ring.len()], w));
+        }
+    }
+}
+
+/// Split edges wherever they cross, touch or overlap, until no two pieces meet
+/// except at their end points. Rounded crossings can create new ones, hence the
+/// repeated passes; every cut lands on the grid and shortens both pieces, so
+/// they run out.
+fn split(mut segs: Vec<Seg>) -> Vec<Seg> {
+    loop {
+        let mut cuts: Vec<Vec<Pt>> = vec![Vec::new(); segs.len()];
+        let mut order: Vec<usize> = (0..segs.len()).collect();
+        order.sort_by_key(|&i| segs[i].p.0);
+        let mut found = false;
+        for (k, &i) in order.iter().enumerate() {
+            for &j in &order[k + 1..] {
+                if segs[j].p.0 > segs[i].q.0 {
+                    break;
+                }
+                found |= intersect(&segs[i], &segs[j], i, j, &mut cuts);
+            }
+        }
+        if !found {
+            return segs;
+        }
+        let mut next = Vec::with_capacity(segs.len());
+        for (s, mut pts) in segs.iter().zip(cuts) {
+            let d = (s.q.0 - s.p.0, s.q.1 - s.p.1);
+            pts.sort_by_key(|&c| dot((c.0 - s.p.0, c.1 - s.p.1), d));
+            pts.dedup();
+            let mut from = s.p;
+            for c in pts.into_iter().chain([s.q]) {
+                if c != from {
+                    next.push(Seg::new(from, c, s.w));
+                    from = c;
+                }
+            }
+        }
+        segs = next;
+    }
+}
+
+/// Note where `s` and `t` meet away from their own end points; true if anything
+/// was cut.
+fn intersect(s: &Seg, t: &Seg, i: usize, j: usize, cuts: &mut [Vec<Pt>]) -> bool {
+    if t.p.1.max(t.q.1) < s.p.1.min(s.q.1) || t.p.1.min(t.q.1) > s.p.1.max(s.q.1) {
+        return false;
+    }
+    let r = sub(s.q, s.p);
+    let u = sub(t.q, t.p);
+    let d1 = cross(r, sub(t.p, s.p));
+    let d2 = cross(r, sub(t.q, s.p));
+    let d3 = cross(u, sub(s.p, t.p));
+    let d4 = cross(u, sub(s.q, t.p));
+    let mut found = false;
+    let mut cut = |k: usize, p: Pt, cuts: &mut [Vec<Pt>]| {
+        cuts[k].push(p);
+        found = true;
+    };
+    // end points lying inside the other edge (T junctions and collinear overlaps)
+    if d1 == 0 && inside(s, t.p) {
+        cut(i, t.p, cuts);
+    }
+    if d2 == 0 && inside(s, t.q) {
+        cut(i, t.q, cuts);
+    }
+    if d3 == 0 && inside(t, s.p) {
+        cut(j, s.p, cuts);
+    }
+    if d4 == 0 && inside(t, s.q) {
+        cut(j, s.q, cuts);
+    }
+    // a proper crossing
+    if d1.signum() * d2.signum() < 0 && d3.signum() * d4.signum() < 0 {
+        let num = cross(sub(t.p, s.p), u);
+        let den = cross(r, u);
+        let x = s.p.0 + div_round(r.0 as i128 * num, den) as i64;
+        let y = s.p.1 + div_round(r.1 as i128 * num, den) as i64;
+        for (k, g) in [(i, s), (j, t)] {
+            if (x, y) != g.p && (x, y) != g.q {
+                cut(k, (x, y), cuts);
+            }
+        }
+    }
+    found
+}
+
+/// `c`, known to be on the line of `s`, lies strictly between its end points.
+fn inside(s: &Seg, c: Pt) -> bool {
+    let d = sub(s.q, s.p);
+    let t = dot(sub(c, s.p), d);
+    t > 0 && t < dot(d, d)
+}
+
+/// Fold coincident pieces together; pieces no input crosses any more are dropped.
+fn merge_duplicates(segs: Vec<Seg>) -> Vec<Seg> {
+    let mut sum: HashMap<(Pt, Pt), [i32; 2]> = HashMap::new();
+    for s in segs {
+        let w = sum.entry((s.p, s.q)).or_default();
+        w[0] += s.w[0];
+        w[1] += s.w[1];
+    }
+    let mut out: Vec<Seg> = sum
+        .into_iter()
+        .filter(|(_, w)| *w != [0, 0])
+        .map(|((p, q), w)| Seg { p, q, w })
+        .collect();
+    out.sort_by_key(|s| (s.p, s.q));
+    out
+}
+
+/// Winding numbers (per input) to the left and right of every piece, for pieces that
+/// only meet at end points.
+///
+/// A scanline moves over the x coordinates keeping the non-vertical pieces it cuts in
+/// bottom-to-top order; a piece's lower side has the winding of the upper side of the
+/// piece below it, since nothing crosses in between.
+fn classify(segs: &[Seg]) -> Vec<([i32; 2], [i32; 2])> {
+    let mut sides = vec![([0; 2], [0; 2]); segs.len()];
+    let mut above = vec![[0; 2]; segs.len()];
+    let mut xs: Vec<i64> = segs.iter().flat_map(|s| [s.p.0, s.q.0]).collect();
+    xs.sort_unstable();
+    xs.dedup();
+    let (mut vertical, mut sloped): (Vec<usize>, Vec<usize>) =
+        (0..segs.len()).partition(|&i| segs[i].p.0 == segs[i].q.0);
+    sloped.sort_by(|&a, &b| {
+        let (s, t) = (&segs[a], &segs[b]);
+        s.p.0
+            .cmp(&t.p.0)
+            .then_with(|| order_at(t, s, s.p.0).reverse())
+    });
+    vertical.sort_by_key(|&i| segs[i].p.0);
+
+    let mut active: Vec<usize> = Vec::new();
+    let (mut next_sloped, mut next_vertical) = (0, 0);
+    for x in xs {
+        active.retain(|&i| segs[i].q.0 != x);
+        while let Some(&s) = sloped.get(next_sloped).filter(|&&s| segs[s].p.0 == x) {
+            next_sloped += 1;
+            let pos = active.partition_point(|&t| order_at(&segs[t], &segs[s], x).is_lt());
+            let below = pos.checked_sub(1).map_or([0; 2], |k| above[active[k]]);
+            above[s] = add(below, segs[s].w);
+            // left of a piece running in +x is above it
+            sides[s] = (above[s], below);
+            active.insert(pos, s);
+        }
+        while let Some(&v) = vertical.get(next_vertical).filter(|&&v| segs[v].p.0 == x) {
+            next_vertical += 1;
+            let s = &segs[v];
+            let mid2 = s.p.1 + s.q.1;
+            let pos = active.partition_point(|&t| cmp_y2_at(&segs[t], x, mid2).is_lt());
+            let right = pos.checked_sub(1).map_or([0; 2], |k| above[active[k]]);
+            // a vertical piece runs in +y, with its left side towards -x
+            sides[v] = (add(right, s.w), right);
+        }
+    }
+    sides
+}
+
+/// Order of sloped pieces `t` and `s` just right of `x` (both cut by the line at
+/// `x`, `s` starting on it): by height at `x`, then by slope.
+fn order_at(t: &Seg, s: &Seg, x: i64) -> Ordering {
+    cmp_y2_at(t, x, 2 * s.p.1).then_with(|| {
+        let (dt, ds) = (sub(t.q, t.p), sub(s.q, s.p));
+        (dt.1 as i128 * ds.0 as i128).cmp(&(ds.1 as i128 * dt.0 as i128))
+    })
+}
+
+/// Twice the height of sloped piece `t` at `x`, compared with `y2`.
+fn cmp_y2_at(t: &Seg, x: i64, y2: i64) -> Ordering {
+    let d = sub(t.q, t.p);
+    let v = (2 * t.p.1 - y2) as i128 * d.0 as i128 + 2 * d.1 as i128 * (x - t.p.0) as i128;
+    v.cmp(&0)
+}
+
+/// Link directed boundary edges (result inside on their left) into closed rings.
+/// At a vertex with several ways on, the sharpest left turn keeps each ring around
+/// one face, so regions touching at a corner come out separately.
+fn link(edges: &[(Pt, Pt)]) -> Vec<Vec<Pt>> {
+    let mut from: HashMap<Pt, Vec<usize>> = HashMap::new();
+    for (i, e) in edges.iter().enumerate() {
+        from.entry(e.0).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let mut ring = Vec::new();
+        let mut e = start;
+        loop {
+            used[e] = true;
+            ring.push(edges[e].0);
+            let (a, v) = edges[e];
+            if v == edges[start].0 {
+                break;
+            }
+            let back = sub(a, v);
+            let next = from
+                .get(&v)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&c| !used[c])
+                .max_by(|&c, &d| ccw_cmp(back, sub(edges[c].1, v), sub(edges[d].1, v)));
+            match next {
+                Some(c) => e = c,
+                None => break,
+            }
+        }
+        let mut ring = simplify(ring);
+        // start at the lowest-left vertex so equal results print alike
+        if let Some(first) = (0..ring.len()).min_by_key(|&i| ring[i]) {
+            ring.rotate_left(first);
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
+/// Compare directions `a` and `b` by their counterclockwise angle from `r`, in
+/// [0°, 360°).
+fn ccw_cmp(r: Pt, a: Pt, b: Pt) -> Ordering {
+    let half = |d: Pt| {
+        let c = cross(r, d);
+        !(c > 0 || (c == 0 && dot(r, d) > 0))
+    };
+    half(a).cmp(&half(b)).then_with(|| 0.cmp(&cross(a, b)))
+}
+
+/// Drop vertices in the middle of straight runs.
+fn simplify(ring: Vec<Pt>) -> Vec<Pt> {
+    let mut out: Vec<Pt> = Vec::with_capacity(ring.len());
+    for p in ring {
+        while out.len() >= 2 && straight(out[out.len() - 2], out[out.len() - 1], p) {
+            out.pop();
+        }
+        out.push(p);
+    }
+    while out.len() >= 3 && straight(out[out.len() - 2], out[out.len() - 1], out[0]) {
+        out.pop();
+    }
+    while out.len() >= 3 && straight(out[out.len() - 1], out[0], out[1]) {
+        out.remove(0);
+    }
+    out
+}
+
+fn straight(a: Pt, b: Pt, c: Pt) -> bool {
+    cross(sub(b, a), sub(c, b)) == 0 && dot(sub(b, a), sub(c, b)) > 0
+}
+
+/// Index of the smallest outer ring containing `hole`.
+fn enclosing(outers: &[Vec<Pt>], hole: &[Pt]) -> Option<usize> {
+    // the midpoint of a hole edge lies on no outer ring
+    let (a, b) = (hole[0], hole[1]);
+    let mid2 = (a.0 + b.0, a.1 + b.1);
+    outers
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| contains2(o, mid2))
+        .min_by_key(|(_, o)| area2(o))
+        .map(|(i, _)| i)
+}
+
+/// Even-odd test of the point `p2 / 2` against `ring`.
+fn contains2(ring: &[Pt], p2: Pt) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let a = (2 * ring[i].0, 2 * ring[i].1);
+        let b = {
+            let b = ring[(i + 1) % ring.len()];
+            (2 * b.0, 2 * b.1)
+        };
+        if (a.1 > p2.1) != (b.1 > p2.1) {
+            // x of the edge at p2.y, compared without dividing
+            let lhs = (p2.0 - a.0) as i128 * (b.1 - a.1) as i128;
+            let rhs = (b.0 - a.0) as i128 * (p2.1 - a.1) as i128;
+            if (lhs < rhs) == (b.1 > a.1) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Join every hole to `outer` with a cut running left from the hole's leftmost
+/// vertex to the nearest boundary. Holes are taken left to right, so whatever the
+/// cut reaches is already part of the ring.
+fn keyhole(outer: Vec<Pt>, mut holes: Vec<Vec<Pt>>) -> Vec<Pt> {
+    holes.sort_by_key(|h| h.iter().copied().min());
+    let mut ring = outer;
+    for hole in holes {
+        let hi = (0..hole.len()).min_by_key(|&i| hole[i]).unwrap_or(0);
+        let h = hole[hi];
+        let looped: Vec<Pt> = (0..=hole.len())
+            .map(|k| hole[(hi + k) % hole.len()])
+            .collect();
+
+        // nearest crossing of the ray y = h.y, x <= h.x: x as num / den, with den > 0
+        let mut best: Option<(i128, i128, usize)> = None;
+        let n = ring.len();
+        for k in 0..n {
+            let (a, b) = (ring[k], ring[(k + 1) % n]);
+            let hit = if a.1 == b.1 {
+                // along the ray: its end nearest the hole, or the hole vertex itself
+                (a.1 == h.1 && a.0.min(b.0) <= h.0).then(|| (a.0.max(b.0).min(h.0) as i128, 1))
+            } else if a.1.min(b.1) <= h.1 && h.1 <= a.1.max(b.1) {
+                let den = (b.1 - a.1) as i128;
+                let num = a.0 as i128 * den + (b.0 - a.0) as i128 * (h.1 - a.1) as i128;
+                Some(if den < 0 { (-num, -den) } else { (num, den) })
+            } else {
+                None
+            };
+            let Some((num, den)) = hit else { continue };
+            if num > h.0 as i128 * den {
+                continue;
+            }
+            let closer = match best {
+                Some((bn, bd, _)) => num * bd > bn * den,
+                None => true,
+            };
+            if closer {
+                best = Some((num, den, k));
+            }
+        }
+        let Some((num, den, k)) = best else { continue };
+
+        let x = div_round(num, den) as i64;
+        let hit = (x, h.1);
+        let (a, b) = (ring[k], ring[(k + 1) % n]);
+        let mut spliced = Vec::with_capacity(n + looped.len() + 2);
+        if hit == a || hit == b {
+            // a vertex: splice at the occurrence whose interior faces the hole
+            let at = (0..n)
+                .filter(|&m| ring[m] == hit)
+                .find(|&m| faces_east(ring[(m + n - 1) % n], hit, ring[(m + 1) % n]))
+                .unwrap_or(if hit == a { k } else { (k + 1) % n });
+            spliced.extend_from_slice(&ring[..=at]);
+            spliced.extend_from_slice(&looped);
+            spliced.extend_from_slice(&ring[at..]);
+        } else {
+            spliced.extend_from_slice(&ring[..=k]);
+            spliced.push(hit);
+            spliced.extend_from_slice(&looped);
+            spliced.push(hit);
+            spliced.extend_from_slice(&ring[k + 1..]);
+        }
+        spliced.dedup();
+        ring = spliced;
+    }
+    while ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+/// The +x direction lies inside the corner `prev`-`v`-`next` of a ring with its
+/// inside on the left, i.e. counterclockwise from `next` before reaching `prev`.
+fn faces_east(prev: Pt, v: Pt, next: Pt) -> bool {
+    let out = sub(next, v);
+    let along_out = cross(out, (1, 0)) == 0 && out.0 > 0;
+    !along_out && ccw_cmp(out, (1, 0), sub(prev, v)) == Ordering::Less
+}
+
+/// Twice the signed area; positive for counterclockwise rings.
+pub(crate) fn area2(ring: &[Pt]) -> i128 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.0 as i128 * b.1 as i128 - b.0 as i128 * a.1 as i128
+        })
+        .sum()
+}
+
+fn sub(a: Pt, b: Pt) -> Pt {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn cross(a: Pt, b: Pt) -> i128 {
+    a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128
+}
+
+fn dot(a: Pt, b: Pt) -> i128 {
+    a.0 as i128 * b.0 as i128 + a.1 as i128 * b.1 as i128
+}
+
+fn add(a: [i32; 2], b: [i32; 2]) -> [i32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+/// `num / den` rounded to the nearest integer, halves away from zero.
+fn div_round(num: i128, den: i128) -> i128 {
+    let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+    if num >= 0 {
+        (2 * num + den) / (2 * den)
+    } else {
+        -((-2 * num + den) / (2 * den))
+    }
+}
+
+fn to_i32(v: i64) -> i32 {
+    v.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: i32, y0: i32, x1: i32, y1: i32) -> Polygon {
+        poly(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1)])
+    }
+
+    fn poly(xy: &[(i32, i32)]) -> Polygon {
+        Polygon {
+            layer: 1,
+            datatype: 0,
+            xy: xy.to_vec(),
+        }
+    }
+
+    /// Twice the total area of the result.
+    fn area(polys: &[Polygon]) -> i128 {
+        polys
+            .iter()
+            .map(|p| {
+                let ring: Vec<Pt> = p.xy.iter().map(|&(x, y)| (x as i64, y as i64)).collect();
+                area2(&ring)
+            })
+            .sum()
+    }
+
+    /// Even-odd containment of a point in the result (keyhole cuts cancel out).
+    fn covers(polys: &[Polygon], p: (i64, i64)) -> bool {
+        polys.iter().any(|poly| {
+            let ring: Vec<Pt> = poly.xy.iter().map(|&(x, y)| (x as i64, y as i64)).collect();
+            contains2(&ring, (2 * p.0 + 1, 2 * p.1 + 1))
+        })
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let a = [rect(0, 0, 10, 10)];
+        let b = [rect(5, 5, 15, 15)];
+        let and = boolean(&a, &b, BooleanOp::And, (7, 1));
+        assert_eq!(and.len(), 1);
+        assert_eq!((and[0].layer, and[0].datatype), (7, 1));
+        assert_eq!(and[0].xy, [(5, 5), (10, 5), (10, 10), (5, 10)]);
+
+        let or = boolean(&a, &b, BooleanOp::Or, (1, 0));
+        assert_eq!(or.len(), 1);
+        assert_eq!(or[0].xy.len(), 8);
+        assert_eq!(area(&or), 2 * 175);
+
+        let xor = boolean(&a, &b, BooleanOp::Xor, (1, 0));
+        assert_eq!(xor.len(), 2);
+        assert_eq!(area(&xor), 2 * 150);
+
+        let not = boolean(&a, &b, BooleanOp::Not, (1, 0));
+        assert_eq!(not.len(), 1);
+        assert_eq!(
+            not[0].xy,
+            [(0, 0), (10, 0), (10, 5), (5, 5), (5, 10), (0, 10)]
+        );
+        assert!(boolean(&b, &b, BooleanOp::Not, (1, 0)).is_empty());
+    }
+
+    #[test]
+    fn holes_become_keyholes() {
+        let a = [rect(0, 0, 30, 30)];
+        let b = [rect(10, 10, 20, 20), rect(22, 12, 26, 16)];
+        let ring = boolean(&a, &b, BooleanOp::Not, (1, 0));
+        assert_eq!(ring.len(), 1);
+        assert_eq!(area(&ring), 2 * (900 - 100 - 16));
+        assert!(covers(&ring, (5, 5)) && covers(&ring, (21, 15)));
+        assert!(!covers(&ring, (15, 15)) && !covers(&ring, (23, 13)));
+
+        // keyholed input reads back as the ring; an island in the hole stays apart
+        let island = boolean(&ring, &[rect(12, 12, 18, 18)], BooleanOp::Or, (1, 0));
+        assert_eq!(island.len(), 2);
+        assert_eq!(area(&island), 2 * (900 - 100 - 16 + 36));
+        assert!(!covers(&island, (11, 11)) && covers(&island, (13, 13)));
+    }
+
+    #[test]
+    fn overlaps_and_orientation_within_an_input() {
+        // clockwise and counterclockwise, overlapping: still the plain union
+        let a = [
+            rect(0, 0, 10, 10),
+            poly(&[(5, 0), (5, 10), (20, 10), (20, 0)]),
+        ];
+        let merged = boolean(&a, &[], BooleanOp::Or, (1, 0));
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].xy, [(0, 0), (20, 0), (20, 10), (0, 10)]);
+
+        // squares sharing only a corner stay apart
+        let corner = boolean(
+            &[rect(0, 0, 5, 5), rect(5, 5, 9, 9)],
+            &[],
+            BooleanOp::Or,
+            (1, 0),
+        );
+        assert_eq!(corner.len(), 2);
+    }
+
+    #[test]
+    fn merge_unions_one_input() {
+        // a frame of four overlapping bars around a hole, plus a stray duplicate
+        let bars = [
+            rect(0, 0, 30, 10),
+            rect(20, 0, 30, 30),
+            rect(0, 20, 30, 30),
+            rect(0, 0, 10, 30),
+            rect(0, 0, 30, 10),
+            rect(50, 0, 60, 10),
+        ];
+        let merged = merge(&bars, (7, 1));
+        assert_eq!(merged.len(), 2);
+        assert!(merged.iter().all(|p| (p.layer, p.datatype) == (7, 1)));
+        assert_eq!(area(&merged), 2 * (30 * 30 - 10 * 10 + 10 * 10));
+        assert!(!covers(&merged, (15, 15)) && covers(&merged, (5, 15)));
+        // merging again changes nothing
+        let again = merge(&merged, (7, 1));
+        let xy = |ps: &[Polygon]| ps.iter().map(|p| p.xy.clone()).collect::<Vec<_>>();
+        assert_eq!(xy(&again), xy(&merged));
+    }
+
+    #[test]
+    fn diagonal_edges_stay_exact() {
+        let a = [rect(0, 0, 10, 10)];
+        let diamond = [poly(&[(5, 10), (10, 5), (15, 10), (10, 15)])];
+        let and = boolean(&a, &diamond, BooleanOp::And, (1, 0));
+        assert_eq!(and.len(), 1);
+        assert_eq!(and[0].xy, [(5, 10), (10, 5), (10, 10)]);
+
+        // a skew crossing lands on the nearest grid point
+        let thin = [poly(&[(0, 0), (7, 3), (7, 4)])];
+        let cut = boolean(&thin, &[rect(3, -5, 20, 20)], BooleanOp::And, (1, 0));
+        assert_eq!(cut.len(), 1);
+        assert!(cut[0].xy.iter().all(|&(x, _)| x >= 3));
+    }
+
+    #[test]
+    fn areas_add_up_on_random_rectangles() {
+        let mut seed = 12345u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as i32
+        };
+        for _ in 0..20 {
+            let mut rects = |n| -> Vec<Polygon> {
+                (0..n)
+                    .map(|_| {
+                        let (x, y) = (next(80), next(80));
+                        rect(x, y, x + 1 + next(30), y + 1 + next(30))
+                    })
+                    .collect()
+            };
+            let (a, b) = (rects(6), rects(6));
+            let area_of = |op| area(&boolean(&a, &b, op, (1, 0)));
+            let (and, or, xor, not) = (
+                area_of(BooleanOp::And),
+                area_of(BooleanOp::Or),
+                area_of(BooleanOp::Xor),
+                area_of(BooleanOp::Not),
+            );
+            let ua = area(&boolean(&a, &[], BooleanOp::Or, (1, 0)));
+            let ub = area(&boolean(&b, &[], BooleanOp::Or, (1, 0)));
+            assert_eq!(or, ua + ub - and);
+            assert_eq!(xor, or - and);
+            assert_eq!(not, ua - and);
+            // every grid cell is classified the way the operation says
+            let result = boolean(&a, &b, BooleanOp::Xor, (1, 0));
+            for x in (0..110).step_by(3) {
+                for y in (0..110).step_by(3) {
+                    let (ia, ib) = (covers(&a, (x, y)), covers(&b, (x, y)));
+                    assert_eq!(covers(&result, (x, y)), ia != ib, "at ({x}, {y})");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Building and editing layouts in memory.
+//!
+//! Every edit keeps the cell summaries (bbox, per-layer counts) in step with the
+//! cells and drops the memoized hierarchy data. A layout opened with
+//! [`Layout::open_gds`] is decoded completely before its first edit.
+
+use crate::{cell_index, GoasperError, Layout};
+use goasper_io::{
+    CellArrayRef, CellPolygons, CellRef, CellSummary, GdsSummary, Label, Path, Polygon, Units,
+};
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Mutable access to one cell, from [`Layout::cell_mut`] or [`Layout::add_cell`].
+pub struct CellMut<'a> {
+    cell: &'a mut CellPolygons,
+    summary: &'a mut CellSummary,
+}
+
+impl Layout {
+    /// An empty library to build from scratch.
+    pub fn new_library(name: &str, units: Units) -> Self {
+        Layout {
+            summary: Some(GdsSummary {
+                libname: Some(name.to_string()),
+                units: Some(units),
+                cells: Vec::new(),
+            }),
+            polys: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Append an empty cell. Fails with [`GoasperError::DuplicateCell`] if the name is
+    /// taken.
+    pub fn add_cell(&mut self, name: &str) -> Result<CellMut<'_>, GoasperError> {
+        let (cells, summaries, index) = self.edit()?;
+        if index.contains_key(name) {
+            return Err(GoasperError::DuplicateCell(name.to_string()));
+        }
+        index.insert(name.to_string(), cells.len());
+        cells.push(CellPolygons {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        summaries.push(CellSummary {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        Ok(CellMut {
+            cell: cells.last_mut().expect("just pushed"),
+            summary: summaries.last_mut().expect("just pushed"),
+        })
+    }
+
+    /// Remove a cell and return it. Instances of it elsewhere are kept and show up in
+    /// [`CellGraph::undefined_references`](crate::CellGraph::undefined_references).
+    pub fn remove_cell(&mut self, name: &str) -> Result<CellPolygons, GoasperError> {
+        let (cells, summaries, index) = self.edit()?;
+        let i = position(index, name)?;
+        summaries.remove(i);
+        let removed = cells.remove(i);
+        *index = cell_index(cells);
+        Ok(removed)
+    }
+
+    /// Rename a cell and every SREF/AREF placing it.
+    pub fn rename_cell(&mut self, name: &str, new_name: &str) -> Result<(), GoasperError> {
+        let (cells, summaries, index) = self.edit()?;
+        let i = position(index, name)?;
+        if index.get(new_name).is_some_and(|&j| j != i) {
+            return Err(GoasperError::DuplicateCell(new_name.to_string()));
+        }
+        cells[i].name = new_name.to_string();
+        summaries[i].name = new_name.to_string();
+        for c in cells.iter_mut() {
+            let refs = c.refs.iter_mut().map(|r| &mut r.name);
+            for child in refs.chain(c.arefs.iter_mut().map(|a| &mut a.name)) {
+                if child == name {
+                    *child = new_name.to_string();
+                }
+            }
+        }
+        *index = cell_index(cells);
+        Ok(())
+    }
+
+    /// Mutable access to the cell called `name`.
+    pub fn cell_mut(&mut self, name: &str) -> Result<CellMut<'_>, GoasperError> {
+        let (cells, summaries, index) = self.edit()?;
+        let i = position(index, name)?;
+        Ok(CellMut {
+            cell: &mut cells[i],
+            summary: &mut summaries[i],
+        })
+    }
+
+    /// The cells and their summaries, index for index, ready to be changed, and the
+    /// name index over them, which the caller keeps in step.
+    fn edit(&mut self) -> Result<Edit<'_>, GoasperError> {
+        if let Some(lazy) = &self.lazy {
+            let cells = lazy.try_all()?.to_vec();
+            let summary = self.summary.get_or_insert_with(GdsSummary::default);
+            summary.cells = cells.iter().map(CellSummary::from_polygons).collect();
+            self.cell_index = cell_index(&cells);
+            self.polys = Some(cells);
+            self.lazy = None;
+        }
+        self.full_bboxes = OnceLock::new();
+        self.graph = OnceLock::new();
+        let summary = self.summary.get_or_insert_with(GdsSummary::default);
+        let cells = self.polys.get_or_insert_with(Vec::new);
+        Ok((cells, &mut summary.cells, &mut self.cell_index))
+    }
+}
+
+type Edit<'a> = (
+    &'a mut Vec<CellPolygons>,
+    &'a mut Vec<CellSummary>,
+    &'a mut HashMap<String, usize>,
+);
+
+fn position(index: &HashMap<String, usize>, name: &str) -> Result<usize, GoasperError> {
+    index
+        .get(name)
+        .copied()
+        .ok_or_else(|| GoasperError::CellNotFound(name.to_string()))
+}
+
+impl CellMut<'_> {
+    pub fn name(&self) -> &str {
+        &self.cell.name
+    }
+
+    /// The cell as it stands.
+    pub fn cell(&self) -> &CellPolygons {
+        self.cell
+    }
+
+    pub fn add_polygon(&mut self, polygon: Polygon) {
+        self.summary.add_polygon(&polygon);
+        self.cell.polys.push(polygon);
+    }
+
+    pub fn add_path(&mut self, path: Path) {
+        self.summary.add_path(&path);
+        self.cell.paths.push(path);
+    }
+
+    pub fn add_label(&mut self, label: Label) {
+        self.cell.labels.push(label);
+    }
+
+    /// Place a cell. The child need not exist yet.
+    pub fn add_ref(&mut self, cell_ref: CellRef) {
+        self.cell.refs.push(cell_ref);
+    }
+
+    pub fn add_aref(&mut self, array: CellArrayRef) {
+        self.cell.arefs.push(array);
+    }
+
+    /// Remove and return polygon `i`, if there is one.
+    pub fn remove_polygon(&mut self, i: usize) -> Option<Polygon> {
+        if i >= self.cell.polys.len() {
+            return None;
+        }
+        let p = self.cell.polys.remove(i);
+        self.resummarize();
+        Some(p)
+    }
+
+    /// Remove and return every polygon on `layer` (layer, datatype).
+    pub fn take_polygons(&mut self, layer: (u16, u16)) -> Vec<Polygon> {
+        let (taken, kept) = std::mem::take(&mut self.cell.polys)
+            .into_iter()
+            .partition(|p| (p.layer, p.datatype) == layer);
+        self.cell.polys = kept;
+        self.resummarize();
+        taken
+    }
+
+    /// Remove and return path `i`, if there is one.
+    pub fn remove_path(&mut self, i: usize) -> Option<Path> {
+        if i >= self.cell.paths.len() {
+            return None;
+        }
+        let p = self.cell.paths.remove(i);
+        self.resummarize();
+        Some(p)
+    }
+
+    /// Remove and return label `i`, if there is one.
+    pub fn remove_label(&mut self, i: usize) -> Option<Label> {
+        (i < self.cell.labels.len()).then(|| self.cell.labels.remove(i))
+    }
+
+    /// Remove and return SREF `i`, if there is one.
+    pub fn remove_ref(&mut self, i: usize) -> Option<CellRef> {
+        (i < self.cell.refs.len()).then(|| self.cell.refs.remove(i))
+    }
+
+    /// Remove and return AREF `i`, if there is one.
+    pub fn remove_aref(&mut self, i: usize) -> Option<CellArrayRef> {
+        (i < self.cell.arefs.len()).then(|| self.cell.arefs.remove(i))
+    }
+
+    /// A bbox cannot shrink incrementally, so removals recount the cell.
+    fn resummarize(&mut self) {
+        *self.summary = CellSummary::from_polygons(self.cell);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::units;
+    use goasper_io::{Anchor, BBox, Strans};
+
+    fn rect(layer: u16, x0: i32, y0: i32, x1: i32, y1: i32) -> Polygon {
+        Polygon {
+            layer,
+            datatype: 0,
+            xy: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+        }
+    }
+
+    #[test]
+    fn build_a_library_and_round_trip_it() {
+        let mut l = Layout::new_library("NEW", units());
+        {
+            let mut leaf = l.add_cell("LEAF").unwrap();
+            leaf.add_polygon(rect(1, 0, 0, 10, 10));
+            leaf.add_path(Path {
+                layer: 2,
+                datatype: 0,
+                width: 4,
+                pathtype: 2,
+                bgnextn: 0,
+                endextn: 0,
+                xy: vec![(0, 0), (20, 0)],
+            });
+            leaf.add_label(Label {
+                layer: 5,
+                texttype: 0,
+                string: "A".into(),
+                position: (1, 1),
+                anchor: Anchor::default(),
+                reflect_x: false,
+                magnification: 1.0,
+                rotation: 0.0,
+            });
+        }
+        l.add_cell("TOP").unwrap().add_ref(CellRef {
+            name: "LEAF".into(),
+            origin: (100, 0),
+            strans: Strans::default(),
+        });
+        assert!(matches!(
+            l.add_cell("TOP"),
+            Err(GoasperError::DuplicateCell(_))
+        ));
+
+        let leaf = &l.cell_summaries()[0];
+        assert_eq!((leaf.total_polys, leaf.total_paths), (1, 1));
+        assert_eq!(
+            leaf.bbox,
+            Some(BBox {
+                xmin: -2,
+                ymin: -2,
+                xmax: 22,
+                ymax: 10
+            })
+        );
+        assert_eq!(l.top_cells().unwrap(), ["TOP"]);
+        assert_eq!(l.full_bbox("TOP").map(|b| b.xmin), Some(98));
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("new.gds");
+        l.save_gds(&out).unwrap();
+        let mut back = Layout::new();
+        back.load_gds(&out).unwrap();
+        assert_eq!(back.libname(), Some("NEW"));
+        assert_eq!(back.cell_names(), ["LEAF", "TOP"]);
+        assert_eq!(back.labels_for("LEAF").unwrap().len(), 1);
+        assert_eq!(back.refs_for("TOP").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn removals_and_renames_keep_summaries_and_references() {
+        let mut l = Layout::new_library("LIB", units());
+        {
+            let mut c = l.add_cell("A").unwrap();
+            c.add_polygon(rect(1, 0, 0, 10, 10));
+            c.add_polygon(rect(1, 50, 50, 60, 60));
+            assert!(c.remove_polygon(1).is_some());
+            assert!(c.remove_polygon(5).is_none());
+        }
+        let a = &l.cell_summaries()[0];
+        assert_eq!(a.total_polys, 1);
+        assert_eq!(a.bbox.map(|b| b.xmax), Some(10));
+
+        l.add_cell("B").unwrap().add_ref(CellRef {
+            name: "A".into(),
+            origin: (0, 0),
+            strans: Strans::default(),
+        });
+        l.rename_cell("A", "LEAF").unwrap();
+        assert_eq!(l.cell_names(), ["LEAF", "B"]);
+        assert_eq!(l.cell_summaries()[0].name, "LEAF");
+        assert_eq!(l.refs_for("B").unwrap()[0].name, "LEAF");
+        assert_eq!(l.cell_graph().unwrap().children("B").unwrap(), ["LEAF"]);
+        assert!(matches!(
+            l.rename_cell("LEAF", "B"),
+            Err(GoasperError::DuplicateCell(_))
+        ));
+        l.rename_cell("LEAF", "LEAF").unwrap();
+        assert_eq!(l.cell_names(), ["LEAF", "B"]);
+        assert_eq!(l.refs_for("B").unwrap()[0].name, "LEAF");
+
+        let removed = l.remove_cell("LEAF").unwrap();
+        assert_eq!(removed.polys.len(), 1);
+        assert_eq!(l.cell_summaries().len(), 1);
+        assert_eq!(l.refs_for("B").unwrap().len(), 1, "B moved up one position");
+        assert_eq!(
+            l.cell_graph().unwrap().undefined_references(),
+            [("B", "LEAF")]
+        );
+        assert!(matches!(
+            l.cell_mut("LEAF"),
+            Err(GoasperError::CellNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn editing_an_opened_file_decodes_it_first() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut eager = Layout::new();
+        eager.load_gds(src).unwrap();
+        let mut l = Layout::new();
+        l.open_gds(src).unwrap();
+        let name = l.cell_names()[0].clone();
+        l.cell_mut(&name).unwrap().add_polygon(rect(99, 0, 0, 1, 1));
+        assert_eq!(l.cell_names(), eager.cell_names());
+        let before = eager.cell_summaries()[0].total_polys;
+        assert_eq!(l.cell_summaries()[0].total_polys, before + 1);
+        assert_eq!(l.polygons_for(&name).unwrap().last().unwrap().layer, 99);
+    }
+}
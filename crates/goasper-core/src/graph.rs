@@ -0,0 +1,276 @@
+//! Which cells place which: top cells, parents and children, and the order in which
+//! cells must be written (every cell after the cells it references).
+
+use crate::GoasperError;
+use goasper_io::CellPolygons;
+use std::collections::HashMap;
+
+/// The instance graph of a library. Edges come from SREFs and AREFs; a child placed
+/// several times is listed once. Like [`Layout::cell`](crate::Layout::cell), a
+/// duplicated cell name refers to its first definition.
+#[derive(Debug, Clone, Default)]
+pub struct CellGraph {
+    /// Defined cells in stream order.
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    children: Vec<Vec<usize>>,
+    parents: Vec<Vec<usize>>,
+    /// Referenced names that no cell defines, per cell.
+    missing: Vec<Vec<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    New,
+    Active,
+    Done,
+}
+
+impl CellGraph {
+    pub fn new(cells: &[CellPolygons]) -> Self {
+        let mut g = CellGraph::default();
+        for c in cells {
+            if !g.index.contains_key(&c.name) {
+                g.index.insert(c.name.clone(), g.names.len());
+                g.names.push(c.name.clone());
+            }
+        }
+        let n = g.names.len();
+        g.children = vec![Vec::new(); n];
+        g.parents = vec![Vec::new(); n];
+        g.missing = vec![Vec::new(); n];
+        let mut seen = vec![false; n];
+        for c in cells {
+            let i = g.index[&c.name];
+            if std::mem::replace(&mut seen[i], true) {
+                // a later duplicate definition
+                continue;
+            }
+            let refs = c.refs.iter().map(|r| &r.name);
+            for child in refs.chain(c.arefs.iter().map(|a| &a.name)) {
+                match g.index.get(child) {
+                    Some(&j) if !g.children[i].contains(&j) => {
+                        g.children[i].push(j);
+                        g.parents[j].push(i);
+                    }
+                    Some(_) => {}
+                    None if !g.missing[i].contains(child) => g.missing[i].push(child.clone()),
+                    None => {}
+                }
+            }
+        }
+        g
+    }
+
+    /// Defined cells, in stream order.
+    pub fn cells(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// Cells no other cell places. Cells on a reference cycle are never top cells.
+    pub fn top_cells(&self) -> Vec<&str> {
+        (0..self.names.len())
+            .filter(|&i| self.parents[i].is_empty())
+            .map(|i| self.name(i))
+            .collect()
+    }
+
+    /// Cells placed directly in `cell`, in order of first reference. Undefined
+    /// references are left out; see [`undefined_references`](Self::undefined_references).
+    pub fn children(&self, cell: &str) -> Result<Vec<&str>, GoasperError> {
+        let i = self.lookup(cell)?;
+        Ok(self.children[i].iter().map(|&j| self.name(j)).collect())
+    }
+
+    /// Cells that place `cell` directly, in stream order of their first reference.
+    pub fn parents(&self, cell: &str) -> Result<Vec<&str>, GoasperError> {
+        let i = self.lookup(cell)?;
+        Ok(self.parents[i].iter().map(|&j| self.name(j)).collect())
+    }
+
+    /// (cell, referenced name) for every reference to a cell the library does not
+    /// define.
+    pub fn undefined_references(&self) -> Vec<(&str, &str)> {
+        let per_cell = self.missing.iter().enumerate();
+        per_cell
+            .flat_map(|(i, m)| m.iter().map(move |child| (self.name(i), child.as_str())))
+            .collect()
+    }
+
+    /// Every cell, children before parents, as a GDS writer needs them. Independent
+    /// cells keep their stream order.
+    ///
+    /// Fails with [`GoasperError::UndefinedCell`] or [`GoasperError::ReferenceCycle`]
+    /// if the hierarchy is broken anywhere.
+    pub fn topological_order(&self) -> Result<Vec<&str>, GoasperError> {
+        let mut marks = vec![Mark::New; self.names.len()];
+        let mut order = Vec::with_capacity(self.names.len());
+        for i in 0..self.names.len() {
+            self.visit(i, &mut marks, &mut Vec::new(), &mut order)?;
+        }
+        Ok(order.into_iter().map(|i| self.name(i)).collect())
+    }
+
+    /// Number of instance levels below `cell`: 0 for a cell without instances,
+    /// otherwise one more than its deepest child. Fails like
+    /// [`topological_order`](Self::topological_order), for the part of the hierarchy
+    /// below `cell`.
+    pub fn depth(&self, cell: &str) -> Result<usize, GoasperError> {
+        let root = self.lookup(cell)?;
+        let mut marks = vec![Mark::New; self.names.len()];
+        let mut order = Vec::new();
+        self.visit(root, &mut marks, &mut Vec::new(), &mut order)?;
+        let mut depth = vec![0; self.names.len()];
+        for i in order {
+            // post-order: every child is already done
+            depth[i] = self.children[i]
+                .iter()
+                .map(|&j| depth[j] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+        Ok(depth[root])
+    }
+
+    /// Depth-first post-order from `i`; `path` holds the cells being visited.
+    fn visit(
+        &self,
+        i: usize,
+        marks: &mut [Mark],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), GoasperError> {
+        match marks[i] {
+            Mark::Done => return Ok(()),
+            Mark::Active => {
+                let start = path.iter().position(|&j| j == i).unwrap_or(0);
+                let mut cycle: Vec<String> = path[start..]
+                    .iter()
+                    .map(|&j| self.names[j].clone())
+                    .collect();
+                cycle.push(self.names[i].clone());
+                return Err(GoasperError::ReferenceCycle(cycle));
+            }
+            Mark::New => {}
+        }
+        if let Some(child) = self.missing[i].first() {
+            return Err(GoasperError::UndefinedCell {
+                cell: child.clone(),
+                parent: self.names[i].clone(),
+            });
+        }
+        marks[i] = Mark::Active;
+        path.push(i);
+        for &j in &self.children[i] {
+            self.visit(j, marks, path, order)?;
+        }
+        path.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    fn lookup(&self, cell: &str) -> Result<usize, GoasperError> {
+        self.index
+            .get(cell)
+            .copied()
+            .ok_or_else(|| GoasperError::CellNotFound(cell.to_string()))
+    }
+
+    fn name(&self, i: usize) -> &str {
+        &self.names[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goasper_io::{CellArrayRef, CellRef, Strans};
+
+    fn cell(name: &str, refs: &[&str], arefs: &[&str]) -> CellPolygons {
+        CellPolygons {
+            name: name.to_string(),
+            refs: refs
+                .iter()
+                .map(|r| CellRef {
+                    name: r.to_string(),
+                    origin: (0, 0),
+                    strans: Strans::default(),
+                })
+                .collect(),
+            arefs: arefs
+                .iter()
+                .map(|r| CellArrayRef {
+                    name: r.to_string(),
+                    cols: 2,
+                    rows: 1,
+                    origin: (0, 0),
+                    col_corner: (10, 0),
+                    row_corner: (0, 10),
+                    strans: Strans::default(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orders_children_before_parents() {
+        let g = CellGraph::new(&[
+            cell("TOP", &["A", "B", "A"], &[]),
+            cell("A", &["LEAF"], &[]),
+            cell("B", &[], &["A", "LEAF"]),
+            cell("LEAF", &[], &[]),
+            cell("SPARE", &[], &[]),
+        ]);
+        assert_eq!(g.top_cells(), ["TOP", "SPARE"]);
+        assert_eq!(g.children("TOP").unwrap(), ["A", "B"]);
+        assert_eq!(g.parents("A").unwrap(), ["TOP", "B"]);
+        assert_eq!(g.parents("LEAF").unwrap(), ["A", "B"]);
+        assert!(g.children("LEAF").unwrap().is_empty());
+        assert!(matches!(
+            g.children("missing"),
+            Err(GoasperError::CellNotFound(_))
+        ));
+
+        assert_eq!(
+            g.topological_order().unwrap(),
+            ["LEAF", "A", "B", "TOP", "SPARE"]
+        );
+        assert_eq!(g.depth("TOP").unwrap(), 3);
+        assert_eq!(g.depth("A").unwrap(), 1);
+        assert_eq!(g.depth("LEAF").unwrap(), 0);
+        assert!(g.undefined_references().is_empty());
+    }
+
+    #[test]
+    fn reports_cycles_and_undefined_cells() {
+        let g = CellGraph::new(&[
+            cell("TOP", &["A"], &[]),
+            cell("A", &["B"], &[]),
+            cell("B", &[], &["A"]),
+            cell("OK", &[], &[]),
+        ]);
+        assert_eq!(g.top_cells(), ["TOP", "OK"]);
+        match g.topological_order() {
+            Err(GoasperError::ReferenceCycle(cycle)) => assert_eq!(cycle, ["A", "B", "A"]),
+            other => panic!("expected a cycle, got {other:?}"),
+        }
+        assert!(matches!(
+            g.depth("TOP"),
+            Err(GoasperError::ReferenceCycle(_))
+        ));
+        assert_eq!(g.depth("OK").unwrap(), 0);
+
+        let g = CellGraph::new(&[cell("TOP", &["A", "GONE"], &[]), cell("A", &[], &[])]);
+        assert_eq!(g.undefined_references(), [("TOP", "GONE")]);
+        assert_eq!(g.children("TOP").unwrap(), ["A"]);
+        let err = g.topological_order().unwrap_err();
+        assert!(matches!(
+            &err,
+            GoasperError::UndefinedCell { cell, parent } if cell == "GONE" && parent == "TOP"
+        ));
+        assert_eq!(err.to_string(), "cell TOP references undefined cell GONE");
+        assert_eq!(g.depth("A").unwrap(), 0);
+    }
+}
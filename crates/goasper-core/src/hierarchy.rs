@@ -0,0 +1,228 @@
+//! Walking the instance hierarchy: bounding boxes of a cell's own geometry plus
+//! every placed child, and flattening into top-level polygons.
+
+use crate::{transform::Transform, GoasperError, Layout};
+use goasper_io::{BBox, CellPolygons, CellSummary, Path, Polygon};
+use std::collections::HashMap;
+
+/// Full bbox of every cell, each computed once. References to undefined cells and
+/// references closing a cycle add nothing.
+pub(crate) fn full_bboxes(layout: &Layout) -> HashMap<String, Option<BBox>> {
+    let cells = layout.polygons();
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    for (i, c) in cells.iter().enumerate() {
+        ids.entry(c.name.as_str()).or_insert(i);
+    }
+    let nodes: Vec<Node> = cells.iter().map(|c| Node::new(c, &ids)).collect();
+    let mut walk = BBoxWalk {
+        nodes: &nodes,
+        memo: vec![None; nodes.len()],
+        absolute: vec![None; nodes.len()],
+        active: vec![false; nodes.len()],
+    };
+    ids.into_iter()
+        .map(|(name, i)| (name.to_string(), walk.full(i)))
+        .collect()
+}
+
+/// A cell with its instances resolved to cell positions, so the walk never looks a
+/// name up. An AREF contributes the placements of its corner instances: they are
+/// translated copies, so the outer ones bound the rest.
+struct Node<'a> {
+    /// Bbox of the polygons and of the paths whose width scales with the placement.
+    local: Option<BBox>,
+    /// Paths with an absolute width, measured anew for every placement.
+    absolute_paths: Vec<&'a Path>,
+    placements: Vec<Placement>,
+}
+
+struct Placement {
+    child: usize,
+    transform: Transform,
+    /// Absolute magnification or angle.
+    absolute: bool,
+}
+
+impl<'a> Node<'a> {
+    fn new(cell: &'a CellPolygons, ids: &HashMap<&str, usize>) -> Self {
+        let mut local = CellSummary::default();
+        let mut absolute_paths = Vec::new();
+        for p in &cell.polys {
+            local.add_polygon(p);
+        }
+        for p in &cell.paths {
+            if p.width < 0 {
+                absolute_paths.push(p);
+            } else {
+                local.add_path(p);
+            }
+        }
+        let refs = cell.refs.iter().map(|r| (&r.name, r.origin, &r.strans));
+        let corners = cell.arefs.iter().flat_map(|a| {
+            a.corner_positions()
+                .into_iter()
+                .map(move |origin| (&a.name, origin, &a.strans))
+        });
+        let placements = refs
+            .chain(corners)
+            .filter_map(|(name, origin, strans)| {
+                Some(Placement {
+                    child: *ids.get(name.as_str())?,
+                    transform: Transform::placement(origin, strans),
+                    absolute: strans.abs_mag || strans.abs_angle,
+                })
+            })
+            .collect();
+        Node {
+            local: local.bbox,
+            absolute_paths,
+            placements,
+        }
+    }
+}
+
+/// Full bboxes are memoized in each cell's own frame. That only works for subtrees
+/// without absolute magnification, angle or path width: there the placement of the
+/// whole chain decides the geometry, so those subtrees are walked again for every
+/// placement, as `flatten` does.
+struct BBoxWalk<'a> {
+    nodes: &'a [Node<'a>],
+    memo: Vec<Option<Option<BBox>>>,
+    /// Whether the cell or an instance below it has an absolute magnification, angle
+    /// or path width.
+    absolute: Vec<Option<bool>>,
+    active: Vec<bool>,
+}
+
+impl BBoxWalk<'_> {
+    fn full(&mut self, i: usize) -> Option<BBox> {
+        if let Some(bb) = self.memo[i] {
+            return bb;
+        }
+        let bb = self.contents(i, &Transform::default());
+        self.memo[i] = Some(bb);
+        bb
+    }
+
+    /// Bbox of cell `i` and everything below it, placed with `t`.
+    fn placed(&mut self, i: usize, t: &Transform) -> Option<BBox> {
+        if self.absolute(i) {
+            self.contents(i, t)
+        } else {
+            self.full(i).map(|bb| t.apply_bbox(&bb))
+        }
+    }
+
+    fn contents(&mut self, i: usize, t: &Transform) -> Option<BBox> {
+        if self.active[i] {
+            return None;
+        }
+        self.active[i] = true;
+        let node = &self.nodes[i];
+        let mut bb = node.local.map(|b| t.apply_bbox(&b));
+        let mut include = |b: Option<BBox>| match (bb.as_mut(), b) {
+            (Some(acc), Some(b)) => acc.include_bbox(&b),
+            (None, b) => bb = b,
+            (_, None) => {}
+        };
+        for path in &node.absolute_paths {
+            include(unscaled(path, t).bbox().map(|b| t.apply_bbox(&b)));
+        }
+        for p in &node.placements {
+            include(self.placed(p.child, &t.then(&p.transform)));
+        }
+        self.active[i] = false;
+        bb
+    }
+
+    fn absolute(&mut self, i: usize) -> bool {
+        if let Some(abs) = self.absolute[i] {
+            return abs;
+        }
+        // a cycle adds nothing, so it cannot make the cell absolute either
+        self.absolute[i] = Some(false);
+        let mut abs = !self.nodes[i].absolute_paths.is_empty();
+        for p in &self.nodes[i].placements {
+            abs |= p.absolute || self.absolute(p.child);
+        }
+        self.absolute[i] = Some(abs);
+        abs
+    }
+}
+
+/// Polygons and path outlines of `cell` and of its instances down to `depth` levels
+/// (all levels for `None`), in the coordinates of `cell`.
+pub(crate) fn flatten(
+    layout: &Layout,
+    cell: &str,
+    depth: Option<usize>,
+) -> Result<Vec<Polygon>, GoasperError> {
+    // fail on undefined cells and cycles before walking anything
+    layout.cell_graph()?.depth(cell)?;
+    let mut out = Vec::new();
+    flatten_into(
+        layout,
+        layout.cell(cell)?,
+        &Transform::default(),
+        depth,
+        &mut out,
+    )?;
+    Ok(out)
+}
+
+fn flatten_into(
+    layout: &Layout,
+    cell: &CellPolygons,
+    t: &Transform,
+    depth: Option<usize>,
+    out: &mut Vec<Polygon>,
+) -> Result<(), GoasperError> {
+    for p in &cell.polys {
+        out.push(Polygon {
+            layer: p.layer,
+            datatype: p.datatype,
+            xy: p.xy.iter().map(|&pt| t.apply_point(pt)).collect(),
+        });
+    }
+    for path in &cell.paths {
+        let path = unscaled(path, t);
+        let xy: Vec<_> = path
+            .outline()
+            .into_iter()
+            .map(|pt| t.apply_rounded(pt))
+            .collect();
+        if !xy.is_empty() {
+            out.push(Polygon {
+                layer: path.layer,
+                datatype: path.datatype,
+                xy,
+            });
+        }
+    }
+    if depth == Some(0) {
+        return Ok(());
+    }
+    let below = depth.map(|d| d - 1);
+    for r in &cell.refs {
+        let inner = t.then(&Transform::placement(r.origin, &r.strans));
+        flatten_into(layout, layout.cell(&r.name)?, &inner, below, out)?;
+    }
+    for a in &cell.arefs {
+        let child = layout.cell(&a.name)?;
+        for origin in a.positions() {
+            let inner = t.then(&Transform::placement(origin, &a.strans));
+            flatten_into(layout, child, &inner, below, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// `path` as drawn before `t`: an absolute width is divided by the magnification `t`
+/// is about to apply, so that it comes out unchanged.
+fn unscaled(path: &Path, t: &Transform) -> Path {
+    let mut path = path.clone();
+    if path.width < 0 {
+        path.width = (path.width as f64 / t.mag).round() as i32;
+    }
+    path
+}
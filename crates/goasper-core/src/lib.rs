@@ -1,14 +1,88 @@
-use goasper_io::{read_gds_polygons, read_gds_summary, CellPolygons, CellSummary, GdsSummary};
+use goasper_io::{
+    read_gds_parallel_with, read_gds_with, read_gds_with_from, read_oas, read_oas_from, write_gds,
+    write_oas, CellPolygons, CellSummary, GdsImage, GdsIndex, GdsSummary, IoError,
+};
+use std::{collections::HashMap, sync::OnceLock};
 use thiserror::Error;
 
+pub mod boolean;
+mod edit;
+pub mod graph;
+mod hierarchy;
+pub mod sizing;
+pub mod transform;
+
+pub use boolean::{boolean, merge, BooleanOp};
+pub use edit::CellMut;
+pub use goasper_io::{
+    Anchor, BBox, CellArrayRef, CellRef, Diagnostic, DiagnosticKind, Label, Path, Polygon,
+    ReadOptions, Strans, Units,
+};
+pub use graph::CellGraph;
+pub use sizing::{size, Corner};
+pub use transform::Transform;
+
 #[derive(Debug, Error)]
 pub enum GoasperError {
+    /// The file could not be opened or read.
     #[error("I/O error: {0}")]
-    Io(String),
+    Io(#[source] IoError),
+    /// The input is not a well-formed GDSII/OASIS stream.
+    #[error("malformed file: {0}")]
+    Format(#[source] IoError),
+    #[error("cell not found: {0}")]
+    CellNotFound(String),
+    /// Valid input using a feature the readers do not implement.
+    #[error("unsupported: {0}")]
+    Unsupported(#[source] IoError),
+    /// A request the library cannot carry out.
+    #[error("unsupported request: {0}")]
+    UnsupportedRequest(String),
+    #[error("write failed: {0}")]
+    Write(#[source] IoError),
+    /// An SREF/AREF names a cell the library does not define.
+    #[error("cell {parent} references undefined cell {cell}")]
+    UndefinedCell { cell: String, parent: String },
+    /// A cell with this name already exists.
+    #[error("cell already exists: {0}")]
+    DuplicateCell(String),
+    /// Cells that place each other, first cell repeated at the end.
+    #[error("reference cycle: {}", .0.join(" -> "))]
+    ReferenceCycle(Vec<String>),
 }
-impl From<goasper_io::IoError> for GoasperError {
-    fn from(e: goasper_io::IoError) -> Self {
-        GoasperError::Io(e.to_string())
+
+impl GoasperError {
+    /// Stream offset of the offending record for format errors.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            GoasperError::Format(e) => e.offset(),
+            _ => None,
+        }
+    }
+
+    /// GDS record type of the offending record for format errors, if known.
+    pub fn rectype(&self) -> Option<u8> {
+        match self {
+            GoasperError::Format(e) => e.rectype(),
+            _ => None,
+        }
+    }
+}
+
+/// Reader errors: plain I/O stays I/O, unsupported features are reported as such and
+/// everything else is a format error.
+impl From<IoError> for GoasperError {
+    fn from(e: IoError) -> Self {
+        let mut inner = &e;
+        while let IoError::Context { source, .. } = inner {
+            inner = source;
+        }
+        match inner {
+            IoError::Io(_) => GoasperError::Io(e),
+            IoError::Unsupported { .. } => GoasperError::Unsupported(e),
+            IoError::Write(_) => GoasperError::Write(e),
+            _ => GoasperError::Format(e),
+        }
     }
 }
 
@@ -16,6 +90,141 @@ impl From<goasper_io::IoError> for GoasperError {
 pub struct Layout {
     summary: Option<GdsSummary>,
     polys: Option<Vec<CellPolygons>>,
+    /// Set by `open_gds`; cells are then decoded from here instead of `polys`.
+    lazy: Option<LazyGds>,
+    /// Position in `polys` of each cell name (the first, should names repeat).
+    cell_index: HashMap<String, usize>,
+    options: ReadOptions,
+    warnings: Vec<Diagnostic>,
+    /// Hierarchical bboxes, computed for every cell on first use.
+    full_bboxes: OnceLock<HashMap<String, Option<BBox>>>,
+    graph: OnceLock<CellGraph>,
+}
+
+/// A GDS opened with [`Layout::open_gds`]: the stream, its structure index and the
+/// cells decoded so far.
+struct LazyGds {
+    image: GdsImage,
+    index: GdsIndex,
+    /// Per index entry, filled on first access.
+    cells: Vec<OnceLock<CellPolygons>>,
+    /// Per index entry, its position in `all` if it is named.
+    slots: Vec<usize>,
+    /// Every cell, once something asked for all of them, the index entries that
+    /// failed to decode (standing in empty) and a warning for each of those.
+    all: OnceLock<(Vec<CellPolygons>, Vec<usize>, Vec<Diagnostic>)>,
+    summaries: OnceLock<Vec<CellSummary>>,
+}
+
+impl LazyGds {
+    fn new(image: GdsImage, index: GdsIndex) -> Self {
+        let mut named = 0;
+        let slots = index
+            .entries
+            .iter()
+            .map(|e| {
+                let slot = named;
+                named += usize::from(!e.name.is_empty());
+                slot
+            })
+            .collect();
+        LazyGds {
+            cells: index.entries.iter().map(|_| OnceLock::new()).collect(),
+            slots,
+            image,
+            index,
+            all: OnceLock::new(),
+            summaries: OnceLock::new(),
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.index
+            .entries
+            .iter()
+            .filter(|e| !e.name.is_empty())
+            .map(|e| e.name.clone())
+            .collect()
+    }
+
+    /// Decode index entry `i`, without caching.
+    fn decode(&self, i: usize) -> Result<CellPolygons, GoasperError> {
+        let cells = self.index.read_entry(&self.image, i)?;
+        Ok(cells.into_iter().next().unwrap_or_default())
+    }
+
+    fn cell(&self, name: &str) -> Result<&CellPolygons, GoasperError> {
+        let i = self
+            .index
+            .position(name)
+            .ok_or_else(|| GoasperError::CellNotFound(name.to_string()))?;
+        if let Some(c) = self.cells[i].get() {
+            return Ok(c);
+        }
+        if let Some((all, failed, _)) = self.all.get() {
+            if let Some(c) = all.get(self.slots[i]).filter(|c| c.name == name) {
+                if !failed.contains(&i) {
+                    return Ok(c);
+                }
+            }
+        }
+        let c = self.decode(i)?;
+        Ok(self.cells[i].get_or_init(|| c))
+    }
+
+    /// Every named cell, in index order. A cell that fails to decode stands in
+    /// empty, so names and summaries still line up; `cell` and `try_all` report
+    /// its error.
+    fn all(&self) -> &[CellPolygons] {
+        &self.decoded().0
+    }
+
+    /// Every named cell, failing on the first that does not decode.
+    fn try_all(&self) -> Result<&[CellPolygons], GoasperError> {
+        let (cells, failed, _) = self.decoded();
+        if let Some(&i) = failed.first() {
+            self.decode(i)?;
+        }
+        Ok(cells)
+    }
+
+    fn decoded(&self) -> &(Vec<CellPolygons>, Vec<usize>, Vec<Diagnostic>) {
+        self.all.get_or_init(|| {
+            let mut failed = Vec::new();
+            let mut warnings = Vec::new();
+            let named = self.index.entries.iter().enumerate();
+            let cells = named
+                .filter(|(_, e)| !e.name.is_empty())
+                .map(|(i, e)| match self.cells[i].get() {
+                    Some(c) => c.clone(),
+                    None => self.decode(i).unwrap_or_else(|err| {
+                        failed.push(i);
+                        warnings.push(Diagnostic {
+                            kind: DiagnosticKind::DroppedCell,
+                            offset: err.offset().unwrap_or(e.range.start as u64),
+                            cell: Some(e.name.clone()),
+                            message: err.to_string(),
+                        });
+                        CellPolygons {
+                            name: e.name.clone(),
+                            ..Default::default()
+                        }
+                    }),
+                })
+                .collect();
+            (cells, failed, warnings)
+        })
+    }
+
+    /// Cells that failed to decode while listing every cell; nothing before that.
+    fn warnings(&self) -> &[Diagnostic] {
+        self.all.get().map_or(&[], |(_, _, w)| w)
+    }
+
+    fn summaries(&self) -> &[CellSummary] {
+        self.summaries
+            .get_or_init(|| self.all().iter().map(CellSummary::from_polygons).collect())
+    }
 }
 
 impl Layout {
@@ -23,25 +232,319 @@ impl Layout {
         Self::default()
     }
 
-    /// load a GDS and populate the internal cell list
+    /// An empty layout whose GDS loads use `options`, e.g. lenient recovery.
+    pub fn with_options(options: ReadOptions) -> Self {
+        Layout {
+            options,
+            ..Self::default()
+        }
+    }
+
+    pub fn read_options(&self) -> ReadOptions {
+        self.options
+    }
+
+    pub fn set_read_options(&mut self, options: ReadOptions) {
+        self.options = options;
+    }
+
+    /// Problems the last GDS load worked around (dropped elements, unclosed
+    /// structures, missing ENDLIB, duplicate cell names). Empty after an OASIS load.
+    /// After `open_gds`, the cells that were listed empty by `polygons` or
+    /// `cell_summaries` because they did not decode.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        if let Some(lazy) = &self.lazy {
+            return lazy.warnings();
+        }
+        &self.warnings
+    }
+
+    fn set_loaded(
+        &mut self,
+        summary: GdsSummary,
+        polys: Vec<CellPolygons>,
+        warnings: Vec<Diagnostic>,
+    ) {
+        self.summary = Some(summary);
+        self.cell_index = cell_index(&polys);
+        self.polys = Some(polys);
+        self.lazy = None;
+        self.warnings = warnings;
+        self.full_bboxes = OnceLock::new();
+        self.graph = OnceLock::new();
+    }
+
+    /// Open a GDS without decoding it: only a structural scan runs (on a memory map
+    /// where possible), and each cell is decoded the first time it is asked for, then
+    /// cached. `polygons`, `cell_summaries` and saving decode every cell. Always
+    /// strict; errors inside a cell surface when that cell is accessed, and fail
+    /// saving and [`cell_graph`](Self::cell_graph).
+    pub fn open_gds<P: AsRef<std::path::Path>>(&mut self, p: P) -> Result<(), GoasperError> {
+        self.set_lazy(GdsImage::open(p)?)
+    }
+
+    /// `open_gds` over any reader; the decompressed stream is kept in memory.
+    pub fn open_gds_from<R: std::io::Read>(&mut self, r: R) -> Result<(), GoasperError> {
+        self.set_lazy(GdsImage::from_reader(r)?)
+    }
+
+    fn set_lazy(&mut self, image: GdsImage) -> Result<(), GoasperError> {
+        let index = GdsIndex::scan(&image)?;
+        self.summary = Some(GdsSummary {
+            libname: index.libname.clone(),
+            units: index.units,
+            cells: Vec::new(),
+        });
+        self.polys = None;
+        self.cell_index.clear();
+        self.lazy = Some(LazyGds::new(image, index));
+        self.warnings.clear();
+        self.full_bboxes = OnceLock::new();
+        self.graph = OnceLock::new();
+        Ok(())
+    }
+
+    /// load a GDS (single pass) and populate the summary and cell list
     pub fn load_gds<P: AsRef<std::path::Path>>(&mut self, p: P) -> Result<(), GoasperError> {
-        self.summary = Some(read_gds_summary(&p)?);
-        self.polys = Some(read_gds_polygons(p)?);
+        let (summary, polys, warnings) = read_gds_with(p, &self.options)?;
+        self.set_loaded(summary, polys, warnings);
         Ok(())
     }
 
-    /// TODO: stub: will implement later
-    /// exists so the Python API compiles
-    pub fn save_oas<P: AsRef<std::path::Path>>(&self, p: P) -> Result<(), GoasperError> {
-        let _ = p; // silence until implemented
+    /// load a GDS stream from any reader; compressed streams are detected and decoded
+    pub fn load_gds_from<R: std::io::Read>(&mut self, r: R) -> Result<(), GoasperError> {
+        let (summary, polys, warnings) = read_gds_with_from(r, &self.options)?;
+        self.set_loaded(summary, polys, warnings);
+        Ok(())
+    }
+
+    /// load a large GDS by memory-mapping it and decoding cells on all cores; same
+    /// result and warnings as `load_gds` with the same read options
+    pub fn load_gds_parallel<P: AsRef<std::path::Path>>(
+        &mut self,
+        p: P,
+    ) -> Result<(), GoasperError> {
+        let (summary, polys, warnings) = read_gds_parallel_with(p, &self.options)?;
+        self.set_loaded(summary, polys, warnings);
         Ok(())
     }
 
+    /// load a GDS held in memory
+    pub fn load_gds_bytes(&mut self, data: &[u8]) -> Result<(), GoasperError> {
+        self.load_gds_from(data)
+    }
+
+    /// load an OASIS file into the same summary/polygon model as `load_gds`
+    pub fn load_oas<P: AsRef<std::path::Path>>(&mut self, p: P) -> Result<(), GoasperError> {
+        let (summary, polys) = read_oas(p)?;
+        self.set_loaded(summary, polys, Vec::new());
+        Ok(())
+    }
+
+    /// load an OASIS stream from any reader
+    pub fn load_oas_from<R: std::io::Read>(&mut self, r: R) -> Result<(), GoasperError> {
+        let (summary, polys) = read_oas_from(r)?;
+        self.set_loaded(summary, polys, Vec::new());
+        Ok(())
+    }
+
+    /// load an OASIS file held in memory
+    pub fn load_oas_bytes(&mut self, data: &[u8]) -> Result<(), GoasperError> {
+        self.load_oas_from(data)
+    }
+
+    /// Write the loaded cells as GDSII, keeping the source library name and units
+    /// (defaults: `LIB`, 1 nm database unit in µm user units). A `.gz`, `.zst` or
+    /// `.bz2` extension selects compressed output.
+    pub fn save_gds<P: AsRef<std::path::Path>>(&self, p: P) -> Result<(), GoasperError> {
+        let units = self.units().unwrap_or(Units {
+            user_units_per_dbu: 1e-3,
+            meters_per_dbu: 1e-9,
+        });
+        let cells = self.try_polygons()?;
+        write_gds(p, self.libname().unwrap_or("LIB"), units, cells).map_err(GoasperError::Write)
+    }
+
+    /// Write the loaded cells as OASIS. The OASIS unit (DBU per micron) is taken
+    /// from the source GDS UNITS record, falling back to 1 nm when none was read.
+    pub fn save_oas<P: AsRef<std::path::Path>>(&self, p: P) -> Result<(), GoasperError> {
+        let cells = self.try_polygons()?;
+        write_oas(p, self.oas_unit(), cells).map_err(GoasperError::Write)
+    }
+
+    fn oas_unit(&self) -> f64 {
+        self.units()
+            .map(|u| 1e-6 / u.meters_per_dbu)
+            .filter(|u| u.is_finite() && *u > 0.0)
+            .unwrap_or(1000.0)
+    }
+
+    /// Library units as read from the file, if any.
+    pub fn units(&self) -> Option<Units> {
+        self.summary.as_ref().and_then(|s| s.units)
+    }
+
+    /// Size of one database unit in meters (e.g. 1e-9 for a 1 nm grid).
+    pub fn dbu_in_meters(&self) -> Option<f64> {
+        self.units().map(|u| u.meters_per_dbu)
+    }
+
+    /// Size of one database unit in user units (typically 1e-3 with µm user units).
+    pub fn user_units_per_dbu(&self) -> Option<f64> {
+        self.units().map(|u| u.user_units_per_dbu)
+    }
+
+    /// Bounding box of a cell's own geometry as (xmin, ymin, xmax, ymax) in microns.
+    /// `None` if the cell is unknown, empty, or the file carried no units.
+    pub fn bbox_um(&self, cell: &str) -> Option<(f64, f64, f64, f64)> {
+        let units = self.units()?;
+        Some(units.bbox_to_microns(&self.local_bbox(cell)?))
+    }
+
+    /// Bounding box of the cell's own polygons and paths (DBU), ignoring instances.
+    pub fn local_bbox(&self, cell: &str) -> Option<BBox> {
+        self.cell_summaries().iter().find(|c| c.name == cell)?.bbox
+    }
+
+    /// Bounding box of everything the cell draws (DBU): its own geometry plus every
+    /// SREF/AREF child, transformed by reflection, magnification, rotation and the
+    /// array lattice. Memoized per layout.
+    pub fn full_bbox(&self, cell: &str) -> Option<BBox> {
+        self.full_bboxes
+            .get_or_init(|| hierarchy::full_bboxes(self))
+            .get(cell)
+            .copied()
+            .flatten()
+    }
+
+    /// `full_bbox` as (xmin, ymin, xmax, ymax) in microns.
+    pub fn full_bbox_um(&self, cell: &str) -> Option<(f64, f64, f64, f64)> {
+        let units = self.units()?;
+        Some(units.bbox_to_microns(&self.full_bbox(cell)?))
+    }
+
+    /// Every polygon `cell` draws, in its coordinates: its own polygons and path
+    /// outlines plus those of the instances down to `depth` levels (`None` for all,
+    /// `Some(0)` for the cell alone), placed through each SREF/AREF's reflection,
+    /// magnification, rotation and array lattice. Fails if the hierarchy below
+    /// `cell` references an undefined cell or contains a cycle.
+    pub fn flatten(&self, cell: &str, depth: Option<usize>) -> Result<Vec<Polygon>, GoasperError> {
+        hierarchy::flatten(self, cell, depth)
+    }
+
+    /// Polygons and path outlines of `cell` itself on `layer` (layer, datatype);
+    /// instances are not descended into.
+    pub fn layer_shapes(
+        &self,
+        cell: &str,
+        layer: (u16, u16),
+    ) -> Result<Vec<Polygon>, GoasperError> {
+        let c = self.cell(cell)?;
+        let on_layer = |l: u16, d: u16| (l, d) == layer;
+        let polys = c
+            .polys
+            .iter()
+            .filter(|p| on_layer(p.layer, p.datatype))
+            .cloned();
+        let paths = c.paths.iter().filter(|p| on_layer(p.layer, p.datatype));
+        Ok(polys.chain(paths.filter_map(Path::to_polygon)).collect())
+    }
+
+    /// Derive `out_layer` in `cell` as `layer_a op layer_b` over the cell's own
+    /// polygons and paths (see [`boolean()`]). Returns the number of polygons added.
+    pub fn boolean(
+        &mut self,
+        cell: &str,
+        layer_a: (u16, u16),
+        layer_b: (u16, u16),
+        op: BooleanOp,
+        out_layer: (u16, u16),
+    ) -> Result<usize, GoasperError> {
+        let a = self.layer_shapes(cell, layer_a)?;
+        let b = self.layer_shapes(cell, layer_b)?;
+        let result = boolean(&a, &b, op, out_layer);
+        let added = result.len();
+        let mut c = self.cell_mut(cell)?;
+        for p in result {
+            c.add_polygon(p);
+        }
+        Ok(added)
+    }
+
+    /// Replace the polygons of `cell` on `layer` (layer, datatype) by their union
+    /// (see [`merge()`]), appended after the cell's other polygons. Paths are left
+    /// alone. Returns the number of polygons now on the layer.
+    pub fn merge_layer(&mut self, cell: &str, layer: (u16, u16)) -> Result<usize, GoasperError> {
+        let mut c = self.cell_mut(cell)?;
+        let merged = merge(&c.take_polygons(layer), layer);
+        let count = merged.len();
+        for p in merged {
+            c.add_polygon(p);
+        }
+        Ok(count)
+    }
+
+    /// [`merge_layer`](Self::merge_layer) for every layer with polygons in `cell`.
+    /// Returns the number of polygons in the cell afterwards.
+    pub fn merge(&mut self, cell: &str) -> Result<usize, GoasperError> {
+        let mut layers: Vec<(u16, u16)> = Vec::new();
+        for p in &self.cell(cell)?.polys {
+            if !layers.contains(&(p.layer, p.datatype)) {
+                layers.push((p.layer, p.datatype));
+            }
+        }
+        let mut count = 0;
+        for layer in layers {
+            count += self.merge_layer(cell, layer)?;
+        }
+        Ok(count)
+    }
+
+    /// Derive `out_layer` in `cell` by sizing its own polygons and paths on `layer`
+    /// by `dx`, `dy` (see [`size()`]). Returns the number of polygons added.
+    pub fn size(
+        &mut self,
+        cell: &str,
+        layer: (u16, u16),
+        (dx, dy): (i32, i32),
+        corner: Corner,
+        out_layer: (u16, u16),
+    ) -> Result<usize, GoasperError> {
+        let shapes = self.layer_shapes(cell, layer)?;
+        let result = size(&shapes, dx, dy, corner, out_layer);
+        let added = result.len();
+        let mut c = self.cell_mut(cell)?;
+        for p in result {
+            c.add_polygon(p);
+        }
+        Ok(added)
+    }
+
+    /// The instance graph: parents, children and write order of the cells. Built on
+    /// first use (decoding every cell after `open_gds`, failing if one does not
+    /// decode) and kept until the next load.
+    pub fn cell_graph(&self) -> Result<&CellGraph, GoasperError> {
+        if let Some(g) = self.graph.get() {
+            return Ok(g);
+        }
+        let g = CellGraph::new(self.try_polygons()?);
+        Ok(self.graph.get_or_init(|| g))
+    }
+
+    /// Cells not placed by any other cell.
+    pub fn top_cells(&self) -> Result<Vec<String>, GoasperError> {
+        let graph = self.cell_graph()?;
+        Ok(graph.top_cells().into_iter().map(str::to_string).collect())
+    }
+
     pub fn libname(&self) -> Option<&str> {
         self.summary.as_ref().and_then(|s| s.libname.as_deref())
     }
 
     pub fn cell_names(&self) -> Vec<String> {
+        if let Some(lazy) = &self.lazy {
+            return lazy.names();
+        }
         self.summary
             .as_ref()
             .map(|s| s.cells.iter().map(|c| c.name.clone()).collect())
@@ -49,34 +552,869 @@ impl Layout {
     }
 
     pub fn cell_summaries(&self) -> &[CellSummary] {
+        if let Some(lazy) = &self.lazy {
+            return lazy.summaries();
+        }
         self.summary
             .as_ref()
             .map(|s| s.cells.as_slice())
             .unwrap_or(&[])
     }
 
-    /// All polygons grouped per cell (DBU coordinates).
+    /// A loaded cell by name; after `open_gds` it is decoded on first access.
+    pub fn cell(&self, name: &str) -> Result<&CellPolygons, GoasperError> {
+        if let Some(lazy) = &self.lazy {
+            return lazy.cell(name);
+        }
+        self.cell_index
+            .get(name)
+            .and_then(|&i| self.polygons().get(i))
+            .ok_or_else(|| GoasperError::CellNotFound(name.to_string()))
+    }
+
+    /// All polygons grouped per cell (DBU coordinates). After `open_gds` a cell that
+    /// fails to decode is listed empty and reported in [`warnings`](Self::warnings);
+    /// [`try_polygons`](Self::try_polygons) fails instead.
     pub fn polygons(&self) -> &[CellPolygons] {
+        if let Some(lazy) = &self.lazy {
+            return lazy.all();
+        }
         self.polys.as_deref().unwrap_or(&[])
     }
 
-    /// Polygons for a single cell by name.
+    /// All polygons grouped per cell, failing if a cell does not decode (possible
+    /// only after `open_gds`).
+    pub fn try_polygons(&self) -> Result<&[CellPolygons], GoasperError> {
+        if let Some(lazy) = &self.lazy {
+            return lazy.try_all();
+        }
+        Ok(self.polys.as_deref().unwrap_or(&[]))
+    }
+
+    /// Paths for a single cell by name.
+    pub fn paths_for<'a>(&'a self, cell: &str) -> Result<&'a [goasper_io::Path], GoasperError> {
+        self.cell(cell).map(|c| c.paths.as_slice())
+    }
+
+    /// Single instances (SREF / OASIS PLACEMENT) placed in a cell.
+    pub fn refs_for<'a>(&'a self, cell: &str) -> Result<&'a [CellRef], GoasperError> {
+        self.cell(cell).map(|c| c.refs.as_slice())
+    }
+
+    /// Array instances (AREF) placed in a cell.
+    pub fn arefs_for<'a>(&'a self, cell: &str) -> Result<&'a [CellArrayRef], GoasperError> {
+        self.cell(cell).map(|c| c.arefs.as_slice())
+    }
+
+    /// Text labels (e.g. pin names) placed in a cell.
+    pub fn labels_for<'a>(&'a self, cell: &str) -> Result<&'a [Label], GoasperError> {
+        self.cell(cell).map(|c| c.labels.as_slice())
+    }
+
+    /// Polygons for a single cell by name. After `open_gds` only this cell is
+    /// decoded, once; `None` also when it fails to decode, see
+    /// [`try_polygons_for`](Self::try_polygons_for).
     pub fn polygons_for<'a>(&'a self, cell: &str) -> Option<&'a [goasper_io::Polygon]> {
-        self.polys
-            .as_ref()?
-            .iter()
-            .find(|c| c.name == cell)
-            .map(|c| c.polys.as_slice())
+        self.try_polygons_for(cell).ok()
+    }
+
+    /// Polygons for a single cell by name, failing with the reason when the cell is
+    /// unknown or does not decode.
+    pub fn try_polygons_for<'a>(
+        &'a self,
+        cell: &str,
+    ) -> Result<&'a [goasper_io::Polygon], GoasperError> {
+        self.cell(cell).map(|c| c.polys.as_slice())
     }
 }
 
+/// Name to position map over `cells`; the first cell of a name wins, like a search.
+fn cell_index(cells: &[CellPolygons]) -> HashMap<String, usize> {
+    let mut index = HashMap::new();
+    for (i, c) in cells.iter().enumerate() {
+        index.entry(c.name.clone()).or_insert(i);
+    }
+    index
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    pub(crate) fn units() -> Units {
+        Units {
+            user_units_per_dbu: 1e-3,
+            meters_per_dbu: 1e-9,
+        }
+    }
+
     #[test]
     fn empty_layout() {
         let l = Layout::new();
         assert!(l.cell_names().is_empty());
+        assert!(l.dbu_in_meters().is_none());
+    }
+
+    #[test]
+    fn load_gds_summary_matches_polygons() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut l = Layout::new();
+        l.load_gds(src).unwrap();
+        assert_eq!(l.cell_summaries().len(), l.polygons().len());
+        for (s, c) in l.cell_summaries().iter().zip(l.polygons()) {
+            assert_eq!(s.name, c.name);
+            assert_eq!(s.total_polys, c.polys.len());
+            assert_eq!(s.total_paths, c.paths.len());
+        }
+        assert_eq!(
+            l.libname(),
+            goasper_io::read_gds_summary(src)
+                .unwrap()
+                .libname
+                .as_deref()
+        );
+    }
+
+    #[test]
+    fn load_from_bytes_matches_file() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut from_file = Layout::new();
+        from_file.load_gds(src).unwrap();
+        let data = std::fs::read(src).unwrap();
+        let mut from_bytes = Layout::new();
+        from_bytes.load_gds_bytes(&data).unwrap();
+        assert_eq!(from_bytes.cell_names(), from_file.cell_names());
+        assert_eq!(from_bytes.libname(), from_file.libname());
+        for (a, b) in from_file.polygons().iter().zip(from_bytes.polygons()) {
+            assert_eq!(a.polys.len(), b.polys.len());
+        }
+
+        let mut oas = Vec::new();
+        goasper_io::oasis::write_oas_to(&mut oas, 1000.0, from_file.polygons()).unwrap();
+        let mut from_oas = Layout::new();
+        from_oas.load_oas_bytes(&oas).unwrap();
+        assert_eq!(from_oas.cell_names(), from_file.cell_names());
+
+        let e = Layout::new().load_gds_bytes(&[0, 2, 0, 0]).unwrap_err();
+        assert!(matches!(e, GoasperError::Format(_)));
+        assert_eq!((e.offset(), e.rectype()), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn error_kinds() {
+        let mut l = Layout::new();
+        let e = l.load_gds("/nonexistent/goasper.gds").unwrap_err();
+        assert!(matches!(e, GoasperError::Io(_)));
+        assert!(std::error::Error::source(&e).is_some());
+        assert!(matches!(l.cell("TOP"), Err(GoasperError::CellNotFound(_))));
+        let e = l.save_gds("/nonexistent/dir/out.gds").unwrap_err();
+        assert!(matches!(e, GoasperError::Write(_)));
+
+        // START with unit 1000, then a CBLOCK with compression type 1
+        let mut oas = goasper_io::oasis::MAGIC.to_vec();
+        oas.extend_from_slice(&[1, 3, b'1', b'.', b'0', 0, 0xE8, 0x07, 0]);
+        oas.extend_from_slice(&[0; 12]);
+        oas.extend_from_slice(&[34, 1]);
+        let e = l.load_oas_bytes(&oas).unwrap_err();
+        assert!(matches!(e, GoasperError::Unsupported(_)));
+        assert!(std::error::Error::source(&e).is_some());
+    }
+
+    #[test]
+    fn parallel_load_matches_sequential() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut seq = Layout::new();
+        seq.load_gds(src).unwrap();
+        let mut par = Layout::new();
+        par.load_gds_parallel(src).unwrap();
+        assert_eq!(par.cell_names(), seq.cell_names());
+        assert_eq!(
+            format!("{:?}", par.polygons()),
+            format!("{:?}", seq.polygons())
+        );
+    }
+
+    #[test]
+    fn open_gds_decodes_cells_on_demand() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut eager = Layout::new();
+        eager.load_gds(src).unwrap();
+        let mut l = Layout::new();
+        l.open_gds(src).unwrap();
+        assert_eq!(l.cell_names(), eager.cell_names());
+        assert_eq!(l.libname(), eager.libname());
+        assert_eq!(l.units(), eager.units());
+
+        let lazy = l.lazy.as_ref().unwrap();
+        let name = "nand2";
+        let i = lazy.index.position(name).unwrap();
+        assert!(lazy.cells.iter().all(|c| c.get().is_none()));
+        let polys = l.polygons_for(name).unwrap();
+        assert_eq!(polys.len(), eager.polygons_for(name).unwrap().len());
+        assert!(lazy.cells[i].get().is_some());
+        assert_eq!(
+            lazy.cells.iter().filter(|c| c.get().is_some()).count(),
+            1,
+            "only the requested cell is decoded"
+        );
+        // cached: the same allocation comes back
+        assert!(std::ptr::eq(polys, l.polygons_for(name).unwrap()));
+        assert!(matches!(
+            l.cell("missing"),
+            Err(GoasperError::CellNotFound(_))
+        ));
+
+        assert_eq!(
+            format!("{:?}", l.polygons()),
+            format!("{:?}", eager.polygons())
+        );
+        assert_eq!(l.cell_summaries().len(), eager.cell_summaries().len());
+
+        // loading eagerly again drops the index
+        l.load_gds(src).unwrap();
+        assert!(l.lazy.is_none());
+    }
+
+    /// LEAF: a 10x5 box. MID: LEAF rotated 90° at (100, 0) plus a 3x2 array of LEAF
+    /// on a 20x10 pitch. TOP: MID mirrored and magnified 2x. `extra` cells are added
+    /// as given.
+    fn hierarchy_bytes(extra: Vec<CellPolygons>) -> Vec<u8> {
+        let boxed = |name: &str| CellPolygons {
+            name: name.to_string(),
+            polys: vec![goasper_io::Polygon {
+                layer: 1,
+                datatype: 0,
+                xy: vec![(0, 0), (10, 0), (10, 5), (0, 5)],
+            }],
+            ..Default::default()
+        };
+        let strans = |reflect_x, mag, angle| Strans {
+            reflect_x,
+            mag,
+            angle,
+            ..Default::default()
+        };
+        let mut cells = vec![
+            boxed("LEAF"),
+            CellPolygons {
+                name: "MID".into(),
+                refs: vec![CellRef {
+                    name: "LEAF".into(),
+                    origin: (100, 0),
+                    strans: strans(false, 1.0, 90.0),
+                }],
+                arefs: vec![CellArrayRef {
+                    name: "LEAF".into(),
+                    cols: 3,
+                    rows: 2,
+                    origin: (0, 0),
+                    col_corner: (60, 0),
+                    row_corner: (0, 20),
+                    strans: Strans::default(),
+                }],
+                ..Default::default()
+            },
+            CellPolygons {
+                name: "TOP".into(),
+                refs: vec![CellRef {
+                    name: "MID".into(),
+                    origin: (0, 0),
+                    strans: strans(true, 2.0, 0.0),
+                }],
+                ..Default::default()
+            },
+        ];
+        cells.extend(extra);
+        let mut out = Vec::new();
+        goasper_io::write_gds_to(&mut out, "LIB", units(), &cells).unwrap();
+        out
+    }
+
+    #[test]
+    fn full_bbox_follows_instances() {
+        let mut l = Layout::new();
+        l.load_gds_bytes(&hierarchy_bytes(Vec::new())).unwrap();
+        let bb = |x0, y0, x1, y1| BBox {
+            xmin: x0,
+            ymin: y0,
+            xmax: x1,
+            ymax: y1,
+        };
+        assert_eq!(l.local_bbox("LEAF"), Some(bb(0, 0, 10, 5)));
+        assert_eq!(l.full_bbox("LEAF"), Some(bb(0, 0, 10, 5)));
+        assert_eq!(l.local_bbox("MID"), None);
+        assert_eq!(l.full_bbox("MID"), Some(bb(0, 0, 100, 15)));
+        assert_eq!(l.local_bbox("TOP"), None);
+        assert_eq!(l.full_bbox("TOP"), Some(bb(0, -30, 200, 0)));
+        let (x0, y0, _, _) = l.full_bbox_um("TOP").unwrap();
+        assert!((x0 - 0.0).abs() < 1e-12 && (y0 + 0.03).abs() < 1e-12);
+        assert_eq!(l.full_bbox("missing"), None);
+    }
+
+    #[test]
+    fn flatten_places_every_instance() {
+        let wire = CellPolygons {
+            name: "WIRE".into(),
+            paths: vec![goasper_io::Path {
+                layer: 2,
+                datatype: 0,
+                width: 4,
+                pathtype: 0,
+                bgnextn: 0,
+                endextn: 0,
+                xy: vec![(0, 0), (10, 0)],
+            }],
+            ..Default::default()
+        };
+        let mut fixed = wire.clone();
+        fixed.name = "FIXED".into();
+        fixed.paths[0].width = -4;
+        let host = CellPolygons {
+            name: "HOST".into(),
+            refs: ["WIRE", "FIXED"]
+                .map(|name| CellRef {
+                    name: name.into(),
+                    origin: (100, 100),
+                    strans: Strans {
+                        mag: 2.0,
+                        angle: 90.0,
+                        ..Default::default()
+                    },
+                })
+                .to_vec(),
+            ..Default::default()
+        };
+        let mut fixed_host = host.clone();
+        fixed_host.name = "FIXED_HOST".into();
+        fixed_host.refs.remove(0);
+        let mut l = Layout::new();
+        l.load_gds_bytes(&hierarchy_bytes(vec![wire, fixed, host, fixed_host]))
+            .unwrap();
+
+        let flat = l.flatten("TOP", None).unwrap();
+        assert_eq!(flat.len(), 7);
+        let mut bb = BBox {
+            xmin: 1,
+            ymin: 1,
+            xmax: 0,
+            ymax: 0,
+        };
+        for p in &flat {
+            assert_eq!((p.layer, p.datatype), (1, 0));
+            p.xy.iter().for_each(|&(x, y)| bb.include_pt(x, y));
+        }
+        assert_eq!(Some(bb), l.full_bbox("TOP"));
+        // LEAF rotated into MID at (95..100, 0..10), then mirrored and doubled
+        assert!(flat
+            .iter()
+            .any(|p| p.xy == [(200, 0), (200, -20), (190, -20), (190, 0)]));
+
+        assert!(l.flatten("TOP", Some(1)).unwrap().is_empty());
+        assert_eq!(l.flatten("MID", Some(1)).unwrap().len(), 7);
+        assert_eq!(l.flatten("TOP", Some(2)).unwrap().len(), 7);
+
+        // the path is widened by the magnification unless its width is absolute
+        let wires = l.flatten("HOST", None).unwrap();
+        assert_eq!(wires[0].xy, [(96, 100), (96, 120), (104, 120), (104, 100)]);
+        assert_eq!(wires[1].xy, [(98, 100), (98, 120), (102, 120), (102, 100)]);
+        // and the full bbox measures it the same way
+        let fixed = l.flatten("FIXED_HOST", None).unwrap();
+        assert_eq!(fixed[0].xy, wires[1].xy);
+        assert_eq!(
+            l.full_bbox("FIXED_HOST"),
+            Some(BBox {
+                xmin: 98,
+                ymin: 100,
+                xmax: 102,
+                ymax: 120
+            })
+        );
+        assert!(matches!(
+            l.flatten("missing", None),
+            Err(GoasperError::CellNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn full_bbox_honors_absolute_placements() {
+        // LEAF kept at its own size and angle inside NEST, however NEST is placed
+        let nest = CellPolygons {
+            name: "NEST".into(),
+            refs: vec![CellRef {
+                name: "LEAF".into(),
+                origin: (10, 0),
+                strans: Strans {
+                    abs_mag: true,
+                    abs_angle: true,
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        };
+        let outer = CellPolygons {
+            name: "OUTER".into(),
+            refs: vec![
+                CellRef {
+                    name: "NEST".into(),
+                    origin: (0, 0),
+                    strans: Strans {
+                        mag: 3.0,
+                        ..Default::default()
+                    },
+                },
+                CellRef {
+                    name: "NEST".into(),
+                    origin: (0, -100),
+                    strans: Strans {
+                        mag: 2.0,
+                        angle: 90.0,
+                        ..Default::default()
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+        let mut l = Layout::new();
+        l.load_gds_bytes(&hierarchy_bytes(vec![nest, outer]))
+            .unwrap();
+
+        let mut bb = BBox {
+            xmin: 1,
+            ymin: 1,
+            xmax: 0,
+            ymax: 0,
+        };
+        for p in l.flatten("OUTER", None).unwrap() {
+            p.xy.iter().for_each(|&(x, y)| bb.include_pt(x, y));
+        }
+        // (30, 0)..(40, 5) and (0, -80)..(10, -75): the LEAF box neither grown nor turned
+        let expected = BBox {
+            xmin: 0,
+            ymin: -80,
+            xmax: 40,
+            ymax: 5,
+        };
+        assert_eq!(bb, expected);
+        assert_eq!(l.full_bbox("OUTER"), Some(expected));
+        assert_eq!(l.full_bbox("NEST").map(|b| b.xmin), Some(10));
+    }
+
+    #[test]
+    fn flatten_rejects_broken_hierarchies() {
+        let loop_cell = |name: &str, child: &str| CellPolygons {
+            name: name.into(),
+            refs: vec![CellRef {
+                name: child.into(),
+                origin: (0, 0),
+                strans: Strans::default(),
+            }],
+            ..Default::default()
+        };
+        let mut l = Layout::new();
+        l.load_gds_bytes(&hierarchy_bytes(vec![
+            loop_cell("A", "B"),
+            loop_cell("B", "A"),
+            loop_cell("DANGLING", "NOWHERE"),
+        ]))
+        .unwrap();
+        assert!(matches!(
+            l.flatten("A", None),
+            Err(GoasperError::ReferenceCycle(_))
+        ));
+        assert!(matches!(
+            l.flatten("DANGLING", Some(0)),
+            Err(GoasperError::UndefinedCell { .. })
+        ));
+        assert_eq!(l.flatten("TOP", None).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn boolean_derives_a_layer_in_place() {
+        let mut l = Layout::new_library("LIB", units());
+        {
+            let mut c = l.add_cell("C").unwrap();
+            c.add_polygon(Polygon {
+                layer: 1,
+                datatype: 0,
+                xy: vec![(0, 0), (100, 0), (100, 20), (0, 20)],
+            });
+            c.add_path(goasper_io::Path {
+                layer: 2,
+                datatype: 0,
+                width: 10,
+                pathtype: 0,
+                bgnextn: 0,
+                endextn: 0,
+                xy: vec![(50, -50), (50, 50)],
+            });
+        }
+        let added = l
+            .boolean("C", (1, 0), (2, 0), BooleanOp::Not, (10, 0))
+            .unwrap();
+        assert_eq!(added, 2);
+        let summary = &l.cell_summaries()[0];
+        assert_eq!(summary.layer_poly_counts.get(&(10, 0)), Some(&2));
+        let derived = l.layer_shapes("C", (10, 0)).unwrap();
+        assert_eq!(derived[0].xy, [(0, 0), (45, 0), (45, 20), (0, 20)]);
+        assert_eq!(derived[1].xy, [(55, 0), (100, 0), (100, 20), (55, 20)]);
+
+        assert_eq!(
+            l.boolean("C", (1, 0), (2, 0), BooleanOp::And, (11, 0))
+                .unwrap(),
+            1
+        );
+        assert!(matches!(
+            l.boolean("missing", (1, 0), (2, 0), BooleanOp::Or, (12, 0)),
+            Err(GoasperError::CellNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn size_derives_a_layer_in_place() {
+        let mut l = Layout::new_library("LIB", units());
+        {
+            let mut c = l.add_cell("C").unwrap();
+            c.add_polygon(Polygon {
+                layer: 1,
+                datatype: 0,
+                xy: vec![(0, 0), (40, 0), (40, 20), (0, 20)],
+            });
+            c.add_polygon(Polygon {
+                layer: 1,
+                datatype: 0,
+                xy: vec![(44, 0), (80, 0), (80, 20), (44, 20)],
+            });
+        }
+        let added = l
+            .size("C", (1, 0), (2, 2), Corner::Square, (10, 0))
+            .unwrap();
+        assert_eq!(added, 1);
+        let grown = l.layer_shapes("C", (10, 0)).unwrap();
+        assert_eq!(grown[0].xy, [(-2, -2), (82, -2), (82, 22), (-2, 22)]);
+        assert_eq!(
+            l.size("C", (1, 0), (-5, 0), Corner::Square, (11, 0))
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            l.cell_summaries()[0].layer_poly_counts.get(&(11, 0)),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn merge_unions_every_layer_of_a_cell() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut l = Layout::new();
+        l.open_gds(src).unwrap();
+        let area = |polys: &[Polygon]| -> i128 {
+            let rings = polys.iter().map(|p| {
+                let ring: Vec<(i64, i64)> =
+                    p.xy.iter().map(|&(x, y)| (x as i64, y as i64)).collect();
+                boolean::area2(&ring).abs()
+            });
+            rings.sum()
+        };
+        let on_layer = |polys: &[Polygon], layer: (u16, u16)| -> Vec<Polygon> {
+            let polys = polys.iter().filter(|p| (p.layer, p.datatype) == layer);
+            polys.cloned().collect()
+        };
+        for (i, name) in l.cell_names().to_vec().into_iter().enumerate() {
+            let summary = &l.cell_summaries()[i];
+            let (polys, paths) = (summary.total_polys, summary.total_paths);
+            let layers: Vec<(u16, u16)> = summary.layer_poly_counts.keys().copied().collect();
+            let polygons = l.polygons_for(&name).unwrap();
+            let unions: Vec<i128> = layers
+                .iter()
+                .map(|&layer| area(&merge(&on_layer(polygons, layer), layer)))
+                .collect();
+
+            let count = l.merge(&name).unwrap();
+            let after = &l.cell_summaries()[i];
+            assert_eq!(after.total_polys, count);
+            assert!(count <= polys);
+            assert_eq!(after.total_paths, paths);
+            let polygons = l.polygons_for(&name).unwrap();
+            for (&layer, &union) in layers.iter().zip(&unions) {
+                assert_eq!(area(&on_layer(polygons, layer)), union, "{name} {layer:?}");
+            }
+        }
+
+        let mut l = Layout::new_library("LIB", units());
+        {
+            let mut c = l.add_cell("C").unwrap();
+            for x in [0, 5, 10] {
+                c.add_polygon(Polygon {
+                    layer: 1,
+                    datatype: 0,
+                    xy: vec![(x, 0), (x + 10, 0), (x + 10, 10), (x, 10)],
+                });
+            }
+            c.add_polygon(Polygon {
+                layer: 2,
+                datatype: 0,
+                xy: vec![(0, 0), (1, 0), (1, 1), (0, 1)],
+            });
+        }
+        assert_eq!(l.merge_layer("C", (1, 0)).unwrap(), 1);
+        let c = l.cell("C").unwrap();
+        assert_eq!(c.polys[0].layer, 2);
+        assert_eq!(c.polys[1].xy, [(0, 0), (20, 0), (20, 10), (0, 10)]);
+        assert_eq!(
+            l.cell_summaries()[0].layer_poly_counts.get(&(1, 0)),
+            Some(&1)
+        );
+        assert_eq!(l.merge("C").unwrap(), 2);
+        assert!(matches!(
+            l.merge("missing"),
+            Err(GoasperError::CellNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn lazy_decode_errors_are_not_swallowed() {
+        let mut bytes = hierarchy_bytes(Vec::new());
+        // break LEAF's boundary: XY (0x10) recorded as INT2 instead of INT4
+        let xy = bytes
+            .windows(2)
+            .position(|w| w == [0x10, 0x03])
+            .expect("an XY record");
+        bytes[xy + 1] = 0x02;
+        let mut l = Layout::new();
+        l.open_gds_from(&bytes[..]).unwrap();
+
+        assert_eq!(l.cell_names(), ["LEAF", "MID", "TOP"]);
+        assert!(l.warnings().is_empty());
+        let names: Vec<&str> = l.polygons().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["LEAF", "MID", "TOP"]);
+        let w = l.warnings();
+        assert_eq!(w.len(), 1);
+        assert_eq!(
+            (w[0].kind, w[0].cell.as_deref()),
+            (DiagnosticKind::DroppedCell, Some("LEAF"))
+        );
+        assert_eq!(Some(w[0].offset), l.cell("LEAF").unwrap_err().offset());
+        assert_eq!(l.cell_summaries().len(), 3);
+        assert_eq!(l.refs_for("MID").unwrap().len(), 1);
+        assert!(l.polygons_for("LEAF").is_none());
+        assert!(matches!(
+            l.try_polygons_for("LEAF"),
+            Err(GoasperError::Format(_))
+        ));
+        assert!(matches!(l.cell("LEAF"), Err(GoasperError::Format(_))));
+        assert!(matches!(l.try_polygons(), Err(GoasperError::Format(_))));
+        assert!(matches!(l.cell_graph(), Err(GoasperError::Format(_))));
+        assert!(l.top_cells().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            l.save_gds(dir.path().join("out.gds")),
+            Err(GoasperError::Format(_))
+        ));
+        assert!(l.save_oas(dir.path().join("out.oas")).is_err());
+        assert!(!dir.path().join("out.gds").exists());
+        assert!(l.cell_mut("TOP").is_err());
+    }
+
+    #[test]
+    fn cell_graph_of_loaded_library() {
+        let spare = CellPolygons {
+            name: "SPARE".into(),
+            ..Default::default()
+        };
+        let mut l = Layout::new();
+        l.load_gds_bytes(&hierarchy_bytes(vec![spare])).unwrap();
+        assert_eq!(l.top_cells().unwrap(), ["TOP", "SPARE"]);
+        let g = l.cell_graph().unwrap();
+        assert_eq!(g.children("MID").unwrap(), ["LEAF"]);
+        assert_eq!(g.parents("MID").unwrap(), ["TOP"]);
+        assert_eq!(
+            g.topological_order().unwrap(),
+            ["LEAF", "MID", "TOP", "SPARE"]
+        );
+        assert_eq!(g.depth("TOP").unwrap(), 2);
+
+        // a reload rebuilds the graph
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        l.load_gds(src).unwrap();
+        let order = l.cell_graph().unwrap().topological_order().unwrap();
+        assert_eq!(order.len(), l.cell_names().len());
+        assert!(!l.top_cells().unwrap().is_empty());
+    }
+
+    #[test]
+    fn lenient_load_keeps_what_survives_truncation() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let data = std::fs::read(src).unwrap();
+        let mut full = Layout::new();
+        full.load_gds_bytes(&data).unwrap();
+        assert!(full.warnings().is_empty());
+
+        let cut = &data[..data.len() * 2 / 3 + 1];
+        let mut l = Layout::new();
+        assert!(matches!(
+            l.load_gds_bytes(cut),
+            Err(GoasperError::Format(_))
+        ));
+
+        let mut l = Layout::with_options(ReadOptions { strict: false });
+        l.load_gds_bytes(cut).unwrap();
+        assert!(!l.cell_names().is_empty());
+        assert!(l.cell_names().len() <= full.cell_names().len());
+        let kinds: Vec<_> = l.warnings().iter().map(|d| d.kind).collect();
+        assert_eq!(kinds.first(), Some(&DiagnosticKind::BadRecord));
+        assert_eq!(kinds.last(), Some(&DiagnosticKind::MissingEndlib));
+    }
+
+    #[test]
+    fn units_and_micron_bbox() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut l = Layout::new();
+        l.load_gds(src).unwrap();
+        let m = l.dbu_in_meters().unwrap();
+        let uu = l.user_units_per_dbu().unwrap();
+        assert!(m > 0.0 && uu > 0.0);
+        let c = l
+            .cell_summaries()
+            .iter()
+            .find(|c| c.bbox.is_some())
+            .unwrap();
+        let bb = c.bbox.unwrap();
+        let (x0, y0, x1, y1) = l.bbox_um(&c.name).unwrap();
+        let um = m * 1e6;
+        assert!((x0 - bb.xmin as f64 * um).abs() < 1e-9);
+        assert!((y1 - bb.ymax as f64 * um).abs() < 1e-9);
+        assert!(x0 <= x1 && y0 <= y1);
+    }
+
+    #[test]
+    fn save_gds_roundtrips_polygons() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut l = Layout::new();
+        l.load_gds(src).unwrap();
+        let out = dir.path().join("nand2.gds");
+        l.save_gds(&out).unwrap();
+
+        let back = goasper_io::read_gds_polygons(&out).unwrap();
+        assert_eq!(back.len(), l.polygons().len());
+        for (a, b) in l.polygons().iter().zip(&back) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.polys.len(), b.polys.len());
+            for (pa, pb) in a.polys.iter().zip(&b.polys) {
+                assert_eq!((pa.layer, pa.datatype), (pb.layer, pb.datatype));
+                assert_eq!(pa.xy, pb.xy);
+            }
+        }
+        let mut reloaded = Layout::new();
+        reloaded.load_gds(&out).unwrap();
+        assert_eq!(reloaded.libname(), l.libname());
+    }
+
+    #[test]
+    fn save_oas_roundtrips_through_load_oas() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let mut l = Layout::new();
+        l.load_gds(src).unwrap();
+        let out = dir.path().join("nand2.oas");
+        l.save_oas(&out).unwrap();
+        let bytes = std::fs::read(&out).unwrap();
+        assert!(bytes.starts_with(goasper_io::oasis::MAGIC));
+        assert!(bytes.len() > 256);
+
+        let (summary, cells) = goasper_io::read_oas(&out).unwrap();
+        assert_eq!(summary.cells.len(), l.cell_names().len());
+        assert_eq!(cells.len(), l.polygons().len());
+        assert!(cells.iter().any(|c| !c.polys.is_empty()));
+        for (a, b) in l.polygons().iter().zip(&cells) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.polys.len(), b.polys.len());
+            for (pa, pb) in a.polys.iter().zip(&b.polys) {
+                assert_eq!((pa.layer, pa.datatype), (pb.layer, pb.datatype));
+                assert_eq!(pa.xy, pb.xy);
+            }
+        }
+        let mut back = Layout::new();
+        back.load_oas(&out).unwrap();
+        assert_eq!(back.cell_names(), l.cell_names());
+    }
+
+    #[test]
+    fn refs_survive_gds_and_oas_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cells = vec![
+            CellPolygons {
+                name: "LEAF".into(),
+                ..Default::default()
+            },
+            CellPolygons {
+                name: "TOP".into(),
+                refs: vec![goasper_io::CellRef {
+                    name: "LEAF".into(),
+                    origin: (100, 0),
+                    strans: goasper_io::Strans {
+                        angle: 90.0,
+                        ..Default::default()
+                    },
+                }],
+                arefs: vec![goasper_io::CellArrayRef {
+                    name: "LEAF".into(),
+                    cols: 2,
+                    rows: 2,
+                    origin: (0, 0),
+                    col_corner: (20, 0),
+                    row_corner: (0, 20),
+                    strans: Default::default(),
+                }],
+                ..Default::default()
+            },
+        ];
+        let gds = dir.path().join("refs.gds");
+        write_gds(&gds, "LIB", units(), &cells).unwrap();
+        let mut l = Layout::new();
+        l.load_gds(&gds).unwrap();
+        let oas = dir.path().join("refs.oas");
+        l.save_oas(&oas).unwrap();
+        let mut back = Layout::new();
+        back.load_oas(&oas).unwrap();
+        for layout in [&l, &back] {
+            let refs = layout.refs_for("TOP").unwrap();
+            assert_eq!(refs.len(), 1);
+            assert_eq!((refs[0].name.as_str(), refs[0].origin), ("LEAF", (100, 0)));
+            assert_eq!(refs[0].strans.angle, 90.0);
+            let arefs = layout.arefs_for("TOP").unwrap();
+            assert_eq!((arefs[0].cols, arefs[0].rows), (2, 2));
+            assert!(layout.refs_for("LEAF").unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn labels_for_cell() {
+        let dir = tempfile::tempdir().unwrap();
+        let label = Label {
+            layer: 63,
+            texttype: 0,
+            string: "Y".into(),
+            position: (40, 80),
+            anchor: Anchor::Center,
+            reflect_x: false,
+            magnification: 1.0,
+            rotation: 0.0,
+        };
+        let cells = vec![CellPolygons {
+            name: "TOP".into(),
+            labels: vec![label.clone()],
+            ..Default::default()
+        }];
+        let gds = dir.path().join("labels.gds");
+        write_gds(&gds, "LIB", units(), &cells).unwrap();
+        let mut l = Layout::new();
+        l.load_gds(&gds).unwrap();
+        assert_eq!(l.labels_for("TOP").unwrap(), &[label]);
+        assert!(matches!(
+            l.labels_for("MISSING"),
+            Err(GoasperError::CellNotFound(_))
+        ));
     }
 }
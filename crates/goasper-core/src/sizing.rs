@@ -0,0 +1,268 @@
+//! Growing and shrinking polygons (sizing, biasing).
+//!
+//! Growing is the Minkowski sum with a convex corner shape `K` centered on the
+//! origin: the polygons plus every edge swept by `K`, merged with the boolean
+//! engine. Shrinking grows the complement instead and subtracts it. With a `K` that
+//! has flat sides on the axes, axis-parallel edges move by exactly the amount given.
+
+use crate::boolean::{boolean, merge, BooleanOp};
+use goasper_io::Polygon;
+use std::f64::consts::PI;
+
+type Pt = (i64, i64);
+
+/// What becomes of convex corners when growing (and of concave ones when shrinking).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    /// Corners keep their shape; a rectangle grows into a rectangle.
+    Square,
+    /// Corners are cut at 45°.
+    Octagonal,
+    /// Corners follow a circle (an ellipse for unequal amounts) drawn with this many
+    /// segments per full turn, rounded up to a multiple of four. The polygon lies
+    /// around the circle, so no point comes out closer than the sizing amount.
+    Round(u32),
+}
+
+/// Grow (positive) or shrink (negative) `polys` by `dx` horizontally and `dy`
+/// vertically, merging whatever overlaps, as polygons on `layer` (layer, datatype).
+/// When one amount grows and the other shrinks, the two directions are sized one
+/// after the other and `corner` does not apply.
+pub fn size(
+    polys: &[Polygon],
+    dx: i32,
+    dy: i32,
+    corner: Corner,
+    layer: (u16, u16),
+) -> Vec<Polygon> {
+    if (dx > 0 && dy < 0) || (dx < 0 && dy > 0) {
+        let wide = size(polys, dx, 0, Corner::Square, layer);
+        return size(&wide, 0, dy, Corner::Square, layer);
+    }
+    let kernel = kernel(dx.unsigned_abs() as i64, dy.unsigned_abs() as i64, corner);
+    if dx >= 0 && dy >= 0 {
+        return merge(&grow(polys, &kernel), layer);
+    }
+
+    // shrink: what lies within reach of the outside is removed
+    let Some((lo, hi)) = bounds(polys) else {
+        return Vec::new();
+    };
+    let margin = (2 * dx.unsigned_abs().max(dy.unsigned_abs()) + 2) as i64;
+    let frame = rect(
+        (lo.0 - margin, lo.1 - margin),
+        (hi.0 + margin, hi.1 + margin),
+    );
+    let outside = boolean(&[frame], polys, BooleanOp::Not, layer);
+    boolean(polys, &grow(&outside, &kernel), BooleanOp::Not, layer)
+}
+
+/// The polygons themselves plus, for every edge, the hull of `K` placed at both of
+/// its ends: together they cover the Minkowski sum (unmerged).
+fn grow(polys: &[Polygon], kernel: &[Pt]) -> Vec<Polygon> {
+    let mut out = polys.to_vec();
+    for p in polys {
+        let n = p.xy.len();
+        for i in 0..n {
+            let (a, b) = (p.xy[i], p.xy[(i + 1) % n]);
+            let mut pts: Vec<Pt> = Vec::with_capacity(2 * kernel.len());
+            for &(x, y) in [a, b].iter() {
+                pts.extend(kernel.iter().map(|k| (x as i64 + k.0, y as i64 + k.1)));
+            }
+            let hull = convex_hull(pts);
+            if hull.len() >= 3 {
+                out.push(Polygon {
+                    layer: p.layer,
+                    datatype: p.datatype,
+                    xy: hull
+                        .into_iter()
+                        .map(|(x, y)| (to_i32(x), to_i32(y)))
+                        .collect(),
+                });
+            }
+        }
+    }
+    out
+}
+
+/// The corner shape for amounts `rx`, `ry` (both >= 0): a polygon around the
+/// ellipse with those radii whose flat sides touch it on the axes.
+fn kernel(rx: i64, ry: i64, corner: Corner) -> Vec<Pt> {
+    let sides = match corner {
+        Corner::Square => 4,
+        Corner::Octagonal => 8,
+        Corner::Round(n) => n.max(4).div_ceil(4) * 4,
+    };
+    // vertices halfway between the touching points, pushed out to meet the sides
+    let reach = 1.0 / (PI / sides as f64).cos();
+    (0..sides)
+        .map(|k| {
+            let a = PI * (2 * k + 1) as f64 / sides as f64;
+            let x = (rx as f64 * reach * a.cos()).round() as i64;
+            let y = (ry as f64 * reach * a.sin()).round() as i64;
+            (x.clamp(-rx, rx), y.clamp(-ry, ry))
+        })
+        .collect()
+}
+
+/// Counterclockwise convex hull (Andrew's monotone chain), collinear points dropped.
+fn convex_hull(mut pts: Vec<Pt>) -> Vec<Pt> {
+    pts.sort_unstable();
+    pts.dedup();
+    if pts.len() < 3 {
+        return pts;
+    }
+    let turn = |o: Pt, a: Pt, b: Pt| {
+        (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+    };
+    let mut hull: Vec<Pt> = Vec::with_capacity(2 * pts.len());
+    for pass in [&pts[..], &pts.iter().rev().copied().collect::<Vec<_>>()[..]] {
+        let base = hull.len();
+        for &p in pass {
+            while hull.len() >= base + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+fn bounds(polys: &[Polygon]) -> Option<(Pt, Pt)> {
+    let mut pts = polys.iter().flat_map(|p| &p.xy);
+    let &(x, y) = pts.next()?;
+    let init = ((x as i64, y as i64), (x as i64, y as i64));
+    Some(pts.fold(init, |(lo, hi), &(x, y)| {
+        let (x, y) = (x as i64, y as i64);
+        ((lo.0.min(x), lo.1.min(y)), (hi.0.max(x), hi.1.max(y)))
+    }))
+}
+
+fn rect(lo: Pt, hi: Pt) -> Polygon {
+    let (x0, y0, x1, y1) = (to_i32(lo.0), to_i32(lo.1), to_i32(hi.0), to_i32(hi.1));
+    Polygon {
+        layer: 0,
+        datatype: 0,
+        xy: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+    }
+}
+
+fn to_i32(v: i64) -> i32 {
+    v.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: i32, y0: i32, x1: i32, y1: i32) -> Polygon {
+        Polygon {
+            layer: 1,
+            datatype: 0,
+            xy: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+        }
+    }
+
+    fn bbox(polys: &[Polygon]) -> (i32, i32, i32, i32) {
+        let pts = polys.iter().flat_map(|p| &p.xy);
+        pts.fold((i32::MAX, i32::MAX, i32::MIN, i32::MIN), |b, &(x, y)| {
+            (b.0.min(x), b.1.min(y), b.2.max(x), b.3.max(y))
+        })
+    }
+
+    #[test]
+    fn square_corners_grow_and_shrink_rectangles() {
+        let r = [rect(0, 0, 10, 10)];
+        let grown = size(&r, 2, 2, Corner::Square, (5, 0));
+        assert_eq!(grown.len(), 1);
+        assert_eq!((grown[0].layer, grown[0].datatype), (5, 0));
+        assert_eq!(grown[0].xy, [(-2, -2), (12, -2), (12, 12), (-2, 12)]);
+
+        let shrunk = size(&r, -2, -2, Corner::Square, (5, 0));
+        assert_eq!(shrunk[0].xy, [(2, 2), (8, 2), (8, 8), (2, 8)]);
+        assert!(size(&r, -5, -5, Corner::Square, (5, 0)).is_empty());
+        assert_eq!(size(&r, 0, 0, Corner::Square, (5, 0))[0].xy, r[0].xy);
+    }
+
+    #[test]
+    fn amounts_per_direction() {
+        let r = [rect(0, 0, 10, 10)];
+        assert_eq!(
+            size(&r, 3, 1, Corner::Square, (1, 0))[0].xy,
+            [(-3, -1), (13, -1), (13, 11), (-3, 11)]
+        );
+        assert_eq!(
+            size(&r, 2, -2, Corner::Octagonal, (1, 0))[0].xy,
+            [(-2, 2), (12, 2), (12, 8), (-2, 8)]
+        );
+        assert_eq!(
+            size(&r, 0, -3, Corner::Square, (1, 0))[0].xy,
+            [(0, 3), (10, 3), (10, 7), (0, 7)]
+        );
+    }
+
+    #[test]
+    fn corner_modes() {
+        let r = [rect(0, 0, 100, 100)];
+        let oct = size(&r, 10, 10, Corner::Octagonal, (1, 0));
+        assert_eq!(oct.len(), 1);
+        assert_eq!(oct[0].xy.len(), 8);
+        assert_eq!(bbox(&oct), (-10, -10, 110, 110));
+        // tan(22.5°) * 10 rounds to 4
+        assert!(oct[0].xy.contains(&(-4, -10)) && oct[0].xy.contains(&(-10, -4)));
+
+        let round = size(&r, 100, 100, Corner::Round(32), (1, 0));
+        assert_eq!(round[0].xy.len(), 32);
+        assert_eq!(bbox(&round), (-100, -100, 200, 200));
+        // segment counts round up to a multiple of four
+        let odd = size(&r, 10, 10, Corner::Round(5), (1, 0));
+        assert_eq!(odd[0].xy.len(), 8);
+
+        // shrinking rounds the inside corners of an L instead
+        let l = [Polygon {
+            layer: 1,
+            datatype: 0,
+            xy: vec![(0, 0), (100, 0), (100, 40), (40, 40), (40, 100), (0, 100)],
+        }];
+        let square = size(&l, -10, -10, Corner::Square, (1, 0));
+        assert_eq!(square[0].xy.len(), 6);
+        let rounded = size(&l, -10, -10, Corner::Round(16), (1, 0));
+        assert!(rounded[0].xy.len() > 6);
+        assert_eq!(bbox(&rounded), (10, 10, 90, 90));
+    }
+
+    #[test]
+    fn results_are_merged() {
+        let two = [rect(0, 0, 10, 10), rect(12, 0, 20, 10)];
+        let grown = size(&two, 1, 1, Corner::Square, (1, 0));
+        assert_eq!(grown.len(), 1);
+        assert_eq!(grown[0].xy, [(-1, -1), (21, -1), (21, 11), (-1, 11)]);
+
+        // a thin bridge disappears when shrinking, leaving two pads
+        let dumbbell = [rect(0, 0, 10, 10), rect(10, 4, 30, 6), rect(30, 0, 40, 10)];
+        let shrunk = size(&dumbbell, -2, -2, Corner::Square, (1, 0));
+        assert_eq!(shrunk.len(), 2);
+        assert_eq!(bbox(&shrunk), (2, 2, 38, 8));
+
+        // a ring closes its hole when grown far enough
+        let ring = boolean(
+            &[rect(0, 0, 30, 30)],
+            &[rect(10, 10, 20, 20)],
+            BooleanOp::Not,
+            (1, 0),
+        );
+        let narrowed = size(&ring, 4, 4, Corner::Square, (1, 0));
+        let ring2: Vec<(i64, i64)> = narrowed[0]
+            .xy
+            .iter()
+            .map(|&(x, y)| (x as i64, y as i64))
+            .collect();
+        assert_eq!(crate::boolean::area2(&ring2), 2 * (38 * 38 - 2 * 2));
+        assert_eq!(
+            size(&ring, 5, 5, Corner::Square, (1, 0))[0].xy,
+            [(-5, -5), (35, -5), (35, 35), (-5, 35)]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Placement transforms of SREF/AREF instances.
+
+use goasper_io::{BBox, Strans};
+
+/// Maps a placed cell's coordinates into its parent's: reflect about the x axis,
+/// magnify, rotate counterclockwise, then translate (GDSII STRANS order).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub reflect_x: bool,
+    pub mag: f64,
+    /// Degrees, counterclockwise.
+    pub angle: f64,
+    pub offset: (f64, f64),
+    /// Taken from STRANS: when nesting, this transform's magnification and angle
+    /// replace the parent's instead of combining with them.
+    pub abs_mag: bool,
+    pub abs_angle: bool,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            reflect_x: false,
+            mag: 1.0,
+            angle: 0.0,
+            offset: (0.0, 0.0),
+            abs_mag: false,
+            abs_angle: false,
+        }
+    }
+}
+
+impl Transform {
+    /// The transform of an instance placed at `origin`.
+    pub fn placement(origin: (i32, i32), strans: &Strans) -> Self {
+        Transform {
+            reflect_x: strans.reflect_x,
+            mag: strans.mag,
+            angle: strans.angle,
+            offset: (origin.0 as f64, origin.1 as f64),
+            abs_mag: strans.abs_mag,
+            abs_angle: strans.abs_angle,
+        }
+    }
+
+    /// `inner` followed by `self`: for an instance placed with `inner` inside a cell
+    /// that is itself placed with `self`.
+    pub fn then(&self, inner: &Transform) -> Transform {
+        let inner_angle = if self.reflect_x {
+            -inner.angle
+        } else {
+            inner.angle
+        };
+        Transform {
+            reflect_x: self.reflect_x != inner.reflect_x,
+            mag: if inner.abs_mag {
+                inner.mag
+            } else {
+                self.mag * inner.mag
+            },
+            angle: if inner.abs_angle {
+                inner.angle
+            } else {
+                self.angle + inner_angle
+            },
+            offset: self.apply(inner.offset),
+            abs_mag: self.abs_mag,
+            abs_angle: self.abs_angle,
+        }
+    }
+
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let y = if self.reflect_x { -y } else { y };
+        let (x, y) = (x * self.mag, y * self.mag);
+        let (c, s) = cos_sin(self.angle);
+        (x * c - y * s + self.offset.0, x * s + y * c + self.offset.1)
+    }
+
+    /// Transform a DBU point, rounding to the nearest grid point.
+    pub fn apply_point(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        self.apply_rounded((x as f64, y as f64))
+    }
+
+    /// Transform an off-grid point (e.g. of a path outline) onto the DBU grid. Halves
+    /// round up, as in [`Path::to_polygon`](goasper_io::Path::to_polygon).
+    pub fn apply_rounded(&self, p: (f64, f64)) -> (i32, i32) {
+        let (x, y) = self.apply(p);
+        (to_dbu((x + 0.5).floor()), to_dbu((y + 0.5).floor()))
+    }
+
+    /// Bounding box of the transformed box; exact for multiples of 90°, enclosing
+    /// otherwise.
+    pub fn apply_bbox(&self, bb: &BBox) -> BBox {
+        let corners = [
+            (bb.xmin, bb.ymin),
+            (bb.xmax, bb.ymin),
+            (bb.xmin, bb.ymax),
+            (bb.xmax, bb.ymax),
+        ];
+        let pts = corners.map(|(x, y)| self.apply((x as f64, y as f64)));
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for (x, y) in pts {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        // absorb float noise before widening to the enclosing grid box
+        let lo = |v: f64| to_dbu((v + 1e-9).floor());
+        let hi = |v: f64| to_dbu((v - 1e-9).ceil());
+        BBox {
+            xmin: lo(x0),
+            ymin: lo(y0),
+            xmax: hi(x1),
+            ymax: hi(y1),
+        }
+    }
+}
+
+/// Exact for multiples of 90°, so orthogonal placements stay on the grid.
+fn cos_sin(deg: f64) -> (f64, f64) {
+    let d = deg.rem_euclid(360.0);
+    if d == 0.0 {
+        (1.0, 0.0)
+    } else if d == 90.0 {
+        (0.0, 1.0)
+    } else if d == 180.0 {
+        (-1.0, 0.0)
+    } else if d == 270.0 {
+        (0.0, -1.0)
+    } else {
+        let r = d.to_radians();
+        (r.cos(), r.sin())
+    }
+}
+
+fn to_dbu(v: f64) -> i32 {
+    v.clamp(i32::MIN as f64, i32::MAX as f64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strans(reflect_x: bool, mag: f64, angle: f64) -> Strans {
+        Strans {
+            reflect_x,
+            mag,
+            angle,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reflect_magnify_rotate_translate() {
+        let t = Transform::placement((100, 0), &strans(true, 2.0, 90.0));
+        // (1, 2) -> reflect (1, -2) -> mag (2, -4) -> rot90 (4, 2) -> + (100, 0)
+        assert_eq!(t.apply_point((1, 2)), (104, 2));
+        let bb = BBox {
+            xmin: 0,
+            ymin: 0,
+            xmax: 10,
+            ymax: 5,
+        };
+        assert_eq!(
+            t.apply_bbox(&bb),
+            BBox {
+                xmin: 100,
+                ymin: 0,
+                xmax: 110,
+                ymax: 20
+            }
+        );
+    }
+
+    #[test]
+    fn nesting_matches_stepwise_application() {
+        let outer = Transform::placement((10, 20), &strans(true, 1.0, 90.0));
+        let inner = Transform::placement((3, 4), &strans(false, 3.0, 180.0));
+        let p = (7, -2);
+        assert_eq!(
+            outer.then(&inner).apply_point(p),
+            outer.apply_point(inner.apply_point(p))
+        );
+
+        let mut abs = strans(false, 5.0, 0.0);
+        abs.abs_mag = true;
+        let big = Transform::placement((0, 0), &strans(false, 10.0, 0.0));
+        let nested = big.then(&Transform::placement((1, 1), &abs));
+        assert_eq!(nested.mag, 5.0);
+        assert_eq!(nested.offset, (10.0, 10.0));
+    }
+}
@@ -0,0 +1,148 @@
+//! Transparent gzip/zstd/bzip2 handling for layout streams.
+//!
+//! Readers sniff the first bytes of the input and decompress on the fly; the file
+//! writers pick a codec from the target extension (`.gz`, `.zst`, `.bz2`).
+
+use crate::IoError;
+use std::io::{BufRead, BufReader, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Identify the codec from the leading magic bytes.
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(&[0x1F, 0x8B]) {
+            Compression::Gzip
+        } else if head.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Compression::Zstd
+        } else if head.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Codec implied by the last extension of `path`, e.g. `top.gds.gz`.
+    pub fn from_extension<P: AsRef<std::path::Path>>(path: P) -> Self {
+        let ext = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("gz" | "gzip") => Compression::Gzip,
+            Some("zst" | "zstd") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Buffer `r` and, if its magic bytes announce a compressed stream, decode it.
+pub fn decompress<'a, R: Read + 'a>(r: R) -> Result<Box<dyn BufRead + 'a>, IoError> {
+    let mut r = BufReader::with_capacity(1 << 16, r);
+    let codec = Compression::detect(r.fill_buf()?);
+    Ok(match codec {
+        Compression::None => Box::new(r),
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(r))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(r)?)),
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(r))),
+    })
+}
+
+/// Writer that compresses with the chosen codec; call [`finish`](Self::finish) to
+/// flush the trailer.
+pub enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    pub fn new(w: W, codec: Compression) -> Result<Self, IoError> {
+        Ok(match codec {
+            Compression::None => CompressedWriter::Plain(w),
+            Compression::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(
+                w,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(w, 0)?),
+            Compression::Bzip2 => CompressedWriter::Bzip2(bzip2::write::BzEncoder::new(
+                w,
+                bzip2::Compression::default(),
+            )),
+        })
+    }
+
+    /// Write the codec trailer and return the underlying writer.
+    pub fn finish(self) -> Result<W, IoError> {
+        Ok(match self {
+            CompressedWriter::Plain(w) => w,
+            CompressedWriter::Gzip(e) => e.finish()?,
+            CompressedWriter::Zstd(e) => e.finish()?,
+            CompressedWriter::Bzip2(e) => e.finish()?,
+        })
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write(buf),
+            CompressedWriter::Gzip(e) => e.write(buf),
+            CompressedWriter::Zstd(e) => e.write(buf),
+            CompressedWriter::Bzip2(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.flush(),
+            CompressedWriter::Gzip(e) => e.flush(),
+            CompressedWriter::Zstd(e) => e.flush(),
+            CompressedWriter::Bzip2(e) => e.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_every_codec() {
+        let data: Vec<u8> = (0..10_000u32).flat_map(|i| i.to_be_bytes()).collect();
+        for codec in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::Bzip2,
+        ] {
+            let mut w = CompressedWriter::new(Vec::new(), codec).unwrap();
+            w.write_all(&data).unwrap();
+            let packed = w.finish().unwrap();
+            assert_eq!(Compression::detect(&packed), codec);
+            let mut back = Vec::new();
+            decompress(&packed[..])
+                .unwrap()
+                .read_to_end(&mut back)
+                .unwrap();
+            assert_eq!(back, data, "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn codec_from_extension() {
+        assert_eq!(Compression::from_extension("a.gds.gz"), Compression::Gzip);
+        assert_eq!(Compression::from_extension("a.GDS.ZST"), Compression::Zstd);
+        assert_eq!(Compression::from_extension("a.gds.bz2"), Compression::Bzip2);
+        assert_eq!(Compression::from_extension("a.gds"), Compression::None);
+    }
+}
@@ -0,0 +1,489 @@
+//! GDSII stream writer for the cell/polygon model produced by the readers.
+
+use crate::{
+    compress::{CompressedWriter, Compression},
+    CellPolygons, IoError, Strans, Units, DT_ASCII, DT_BITARRAY, DT_INT2, DT_INT4, DT_REAL8,
+    RT_ANGLE, RT_AREF, RT_BGNEXTN, RT_BGNSTR, RT_BOUNDARY, RT_COLROW, RT_DATATYPE, RT_ENDEL,
+    RT_ENDEXTN, RT_ENDLIB, RT_ENDSTR, RT_LAYER, RT_LIBNAME, RT_MAG, RT_PATH, RT_PATHTYPE,
+    RT_PRESENTATION, RT_SNAME, RT_SREF, RT_STRANS, RT_STRING, RT_STRNAME, RT_TEXT, RT_TEXTTYPE,
+    RT_UNITS, RT_WIDTH, RT_XY,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const RT_HEADER: u8 = 0x00;
+const RT_BGNLIB: u8 = 0x01;
+const DT_NONE: u8 = 0x00;
+
+/// Stream format version written into HEADER.
+const GDS_VERSION: i16 = 600;
+/// Largest XY record: (65535 - 4) / 8 points.
+const MAX_XY_POINTS: usize = 8191;
+
+/// Write cells as a GDSII file, compressed when the extension is `.gz`, `.zst` or `.bz2`.
+pub fn write_gds<P: AsRef<std::path::Path>>(
+    path: P,
+    libname: &str,
+    units: Units,
+    cells: &[CellPolygons],
+) -> Result<(), IoError> {
+    let codec = Compression::from_extension(&path);
+    let f = File::create(path)?;
+    let mut w = CompressedWriter::new(BufWriter::new(f), codec)?;
+    write_gds_to(&mut w, libname, units, cells)?;
+    w.finish()?.flush()?;
+    Ok(())
+}
+
+/// Write cells as a GDSII stream to any writer.
+/// BGNLIB/BGNSTR carry the current time; polygons are written as BOUNDARY elements
+/// with the closing point re-added, paths as PATH elements, instances as SREF/AREF and
+/// labels as TEXT. Polygons with fewer than three vertices have no BOUNDARY form and
+/// are skipped, as in the OASIS writer.
+pub fn write_gds_to<W: Write>(
+    w: &mut W,
+    libname: &str,
+    units: Units,
+    cells: &[CellPolygons],
+) -> Result<(), IoError> {
+    let stamp = timestamp(SystemTime::now());
+    let mut dates = Vec::with_capacity(24);
+    for _ in 0..2 {
+        for v in stamp {
+            dates.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+
+    record(w, RT_HEADER, DT_INT2, &GDS_VERSION.to_be_bytes())?;
+    record(w, RT_BGNLIB, DT_INT2, &dates)?;
+    record(w, RT_LIBNAME, DT_ASCII, &gds_ascii(libname))?;
+    let mut u = Vec::with_capacity(16);
+    u.extend_from_slice(&f64_to_gds_real8(units.user_units_per_dbu));
+    u.extend_from_slice(&f64_to_gds_real8(units.meters_per_dbu));
+    record(w, RT_UNITS, DT_REAL8, &u)?;
+
+    let mut xy = Vec::new();
+    for c in cells {
+        record(w, RT_BGNSTR, DT_INT2, &dates)?;
+        record(w, RT_STRNAME, DT_ASCII, &gds_ascii(&c.name))?;
+        for p in &c.polys {
+            // BOUNDARY needs at least four points with the closing one
+            if p.xy.len() < 3 {
+                continue;
+            }
+            if p.xy.len() + 1 > MAX_XY_POINTS {
+                return Err(IoError::Write(format!(
+                    "polygon with {} points in cell {} exceeds the GDSII XY record limit",
+                    p.xy.len(),
+                    c.name
+                )));
+            }
+            record(w, RT_BOUNDARY, DT_NONE, &[])?;
+            record(w, RT_LAYER, DT_INT2, &p.layer.to_be_bytes())?;
+            record(w, RT_DATATYPE, DT_INT2, &p.datatype.to_be_bytes())?;
+            xy.clear();
+            for &(x, y) in p.xy.iter().chain(p.xy.first()) {
+                xy.extend_from_slice(&x.to_be_bytes());
+                xy.extend_from_slice(&y.to_be_bytes());
+            }
+            record(w, RT_XY, DT_INT4, &xy)?;
+            record(w, RT_ENDEL, DT_NONE, &[])?;
+        }
+        for p in &c.paths {
+            if p.xy.len() > MAX_XY_POINTS {
+                return Err(IoError::Write(format!(
+                    "path with {} points in cell {} exceeds the GDSII XY record limit",
+                    p.xy.len(),
+                    c.name
+                )));
+            }
+            record(w, RT_PATH, DT_NONE, &[])?;
+            record(w, RT_LAYER, DT_INT2, &p.layer.to_be_bytes())?;
+            record(w, RT_DATATYPE, DT_INT2, &p.datatype.to_be_bytes())?;
+            record(w, RT_PATHTYPE, DT_INT2, &p.pathtype.to_be_bytes())?;
+            record(w, RT_WIDTH, DT_INT4, &p.width.to_be_bytes())?;
+            if p.pathtype == 4 {
+                record(w, RT_BGNEXTN, DT_INT4, &p.bgnextn.to_be_bytes())?;
+                record(w, RT_ENDEXTN, DT_INT4, &p.endextn.to_be_bytes())?;
+            }
+            xy.clear();
+            for &(x, y) in &p.xy {
+                xy.extend_from_slice(&x.to_be_bytes());
+                xy.extend_from_slice(&y.to_be_bytes());
+            }
+            record(w, RT_XY, DT_INT4, &xy)?;
+            record(w, RT_ENDEL, DT_NONE, &[])?;
+        }
+        for r in &c.refs {
+            record(w, RT_SREF, DT_NONE, &[])?;
+            record(w, RT_SNAME, DT_ASCII, &gds_ascii(&r.name))?;
+            strans(w, &r.strans)?;
+            record(w, RT_XY, DT_INT4, &xy_bytes(&[r.origin]))?;
+            record(w, RT_ENDEL, DT_NONE, &[])?;
+        }
+        for a in &c.arefs {
+            record(w, RT_AREF, DT_NONE, &[])?;
+            record(w, RT_SNAME, DT_ASCII, &gds_ascii(&a.name))?;
+            strans(w, &a.strans)?;
+            let mut colrow = a.cols.to_be_bytes().to_vec();
+            colrow.extend_from_slice(&a.rows.to_be_bytes());
+            record(w, RT_COLROW, DT_INT2, &colrow)?;
+            record(
+                w,
+                RT_XY,
+                DT_INT4,
+                &xy_bytes(&[a.origin, a.col_corner, a.row_corner]),
+            )?;
+            record(w, RT_ENDEL, DT_NONE, &[])?;
+        }
+        for l in &c.labels {
+            record(w, RT_TEXT, DT_NONE, &[])?;
+            record(w, RT_LAYER, DT_INT2, &l.layer.to_be_bytes())?;
+            record(w, RT_TEXTTYPE, DT_INT2, &l.texttype.to_be_bytes())?;
+            let presentation = l.anchor.presentation();
+            if presentation != 0 {
+                record(w, RT_PRESENTATION, DT_BITARRAY, &presentation.to_be_bytes())?;
+            }
+            let t = Strans {
+                reflect_x: l.reflect_x,
+                mag: l.magnification,
+                angle: l.rotation,
+                ..Default::default()
+            };
+            strans(w, &t)?;
+            record(w, RT_XY, DT_INT4, &xy_bytes(&[l.position]))?;
+            record(w, RT_STRING, DT_ASCII, &gds_ascii(&l.string))?;
+            record(w, RT_ENDEL, DT_NONE, &[])?;
+        }
+        record(w, RT_ENDSTR, DT_NONE, &[])?;
+    }
+    record(w, RT_ENDLIB, DT_NONE, &[])?;
+    Ok(())
+}
+
+fn record<W: Write>(w: &mut W, rectype: u8, dtype: u8, data: &[u8]) -> Result<(), IoError> {
+    let len = u16::try_from(4 + data.len()).map_err(|_| {
+        IoError::Write(format!(
+            "record {:#04x} payload of {} bytes is too long",
+            rectype,
+            data.len()
+        ))
+    })?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(&[rectype, dtype])?;
+    w.write_all(data)?;
+    Ok(())
+}
+
+/// STRANS (plus MAG/ANGLE when not default); nothing for the identity.
+fn strans<W: Write>(w: &mut W, t: &Strans) -> Result<(), IoError> {
+    if t.is_identity() && !t.abs_mag && !t.abs_angle {
+        return Ok(());
+    }
+    let bits: u16 =
+        (t.reflect_x as u16) << 15 | (t.abs_mag as u16) << 2 | (t.abs_angle as u16) << 1;
+    record(w, RT_STRANS, DT_BITARRAY, &bits.to_be_bytes())?;
+    if t.mag != 1.0 {
+        record(w, RT_MAG, DT_REAL8, &f64_to_gds_real8(t.mag))?;
+    }
+    if t.angle != 0.0 {
+        record(w, RT_ANGLE, DT_REAL8, &f64_to_gds_real8(t.angle))?;
+    }
+    Ok(())
+}
+
+fn xy_bytes(pts: &[(i32, i32)]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(pts.len() * 8);
+    for &(x, y) in pts {
+        v.extend_from_slice(&x.to_be_bytes());
+        v.extend_from_slice(&y.to_be_bytes());
+    }
+    v
+}
+
+/// ASCII payloads are NUL-padded to an even length.
+fn gds_ascii(s: &str) -> Vec<u8> {
+    let mut v = s.as_bytes().to_vec();
+    if !v.len().is_multiple_of(2) {
+        v.push(0);
+    }
+    v
+}
+
+/// Encode an 8-byte GDS real (excess-64, base-16 exponent, 56-bit mantissa).
+pub fn f64_to_gds_real8(v: f64) -> [u8; 8] {
+    if v == 0.0 || !v.is_finite() {
+        return [0; 8];
+    }
+    let mut m = v.abs();
+    let mut exp: i32 = 64;
+    while m >= 1.0 {
+        m /= 16.0;
+        exp += 1;
+    }
+    while m < 1.0 / 16.0 {
+        m *= 16.0;
+        exp -= 1;
+    }
+    let mut mant = (m * (1u64 << 56) as f64).round() as u64;
+    if mant >= 1u64 << 56 {
+        mant >>= 4;
+        exp += 1;
+    }
+    let mut b = mant.to_be_bytes();
+    b[0] = ((v < 0.0) as u8) << 7 | exp.clamp(0, 127) as u8;
+    b
+}
+
+/// (year, month, day, hour, minute, second) in UTC, as stored in BGNLIB/BGNSTR.
+fn timestamp(t: SystemTime) -> [i16; 6] {
+    let secs = t
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // civil-from-days (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    [
+        year as i16,
+        month as i16,
+        day as i16,
+        (rem / 3600) as i16,
+        (rem % 3600 / 60) as i16,
+        (rem % 60) as i16,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gds_real8_to_f64, read_gds_polygons, read_gds_summary, Anchor, CellArrayRef, CellRef,
+        Label, Path, Polygon,
+    };
+    use std::time::Duration;
+    use tempfile::NamedTempFile;
+
+    fn units() -> Units {
+        Units {
+            user_units_per_dbu: 1e-3,
+            meters_per_dbu: 1e-9,
+        }
+    }
+
+    /// One cell, TOP, holding a 10x5 rectangle on layer 1/0.
+    fn rect_cell() -> Vec<CellPolygons> {
+        vec![CellPolygons {
+            name: "TOP".into(),
+            polys: vec![Polygon {
+                layer: 1,
+                datatype: 0,
+                xy: vec![(0, 0), (10, 0), (10, 5), (0, 5)],
+            }],
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn real8_roundtrip() {
+        for v in [1e-3, 1e-9, 0.5, 1.0, 16.0, -2.5, 1e-6, 0.25e-9] {
+            let back = gds_real8_to_f64(f64_to_gds_real8(v));
+            assert!((back - v).abs() <= v.abs() * 1e-15, "{} -> {}", v, back);
+        }
+        assert_eq!(f64_to_gds_real8(0.0), [0; 8]);
+        // 1.0 is 0x41 0x10 0 ...
+        assert_eq!(f64_to_gds_real8(1.0), [0x41, 0x10, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn timestamp_civil_date() {
+        // 2024-02-29T12:34:56Z
+        let t = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(timestamp(t), [2024, 2, 29, 12, 34, 56]);
+    }
+
+    #[test]
+    fn polys_roundtrip_through_writer() {
+        let tmp = NamedTempFile::new().unwrap();
+        let cells = rect_cell();
+        write_gds(tmp.path(), "LIB", units(), &cells).unwrap();
+
+        let back = read_gds_polygons(tmp.path()).unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].name, "TOP");
+        assert_eq!(back[0].polys.len(), 1);
+        assert_eq!(back[0].polys[0].layer, 1);
+        assert_eq!(back[0].polys[0].datatype, 0);
+        assert_eq!(back[0].polys[0].xy, vec![(0, 0), (10, 0), (10, 5), (0, 5)]);
+
+        let s = read_gds_summary(tmp.path()).unwrap();
+        assert_eq!(s.libname.as_deref(), Some("LIB"));
+        let u = s.units.unwrap();
+        assert!((u.user_units_per_dbu - 1e-3).abs() < 1e-18);
+        assert!((u.meters_per_dbu - 1e-9).abs() < 1e-24);
+    }
+
+    #[test]
+    fn degenerate_polygons_are_skipped() {
+        let tmp = NamedTempFile::new().unwrap();
+        let mut cells = rect_cell();
+        for xy in [vec![(1, 1)], vec![(0, 0), (7, 7)]] {
+            cells[0].polys.push(Polygon {
+                layer: 2,
+                datatype: 0,
+                xy,
+            });
+        }
+        write_gds(tmp.path(), "LIB", units(), &cells).unwrap();
+        let back = read_gds_polygons(tmp.path()).unwrap();
+        assert_eq!(back[0].polys.len(), 1);
+        assert_eq!(back[0].polys[0].layer, 1);
+    }
+
+    #[test]
+    fn paths_roundtrip_through_writer() {
+        let tmp = NamedTempFile::new().unwrap();
+        let cells = vec![CellPolygons {
+            name: "WIRE".into(),
+            paths: vec![Path {
+                layer: 3,
+                datatype: 1,
+                width: 10,
+                pathtype: 4,
+                bgnextn: 2,
+                endextn: 7,
+                xy: vec![(0, 0), (100, 0), (100, 50)],
+            }],
+            ..Default::default()
+        }];
+        write_gds(tmp.path(), "LIB", units(), &cells).unwrap();
+
+        let back = read_gds_polygons(tmp.path()).unwrap();
+        let p = &back[0].paths[0];
+        assert_eq!((p.layer, p.datatype, p.width, p.pathtype), (3, 1, 10, 4));
+        assert_eq!((p.bgnextn, p.endextn), (2, 7));
+        assert_eq!(p.xy, vec![(0, 0), (100, 0), (100, 50)]);
+    }
+
+    #[test]
+    fn refs_roundtrip_through_writer() {
+        let tmp = NamedTempFile::new().unwrap();
+        let strans = Strans {
+            reflect_x: true,
+            abs_mag: false,
+            abs_angle: true,
+            mag: 2.0,
+            angle: 90.0,
+        };
+        let cells = vec![
+            CellPolygons {
+                name: "LEAF".into(),
+                ..Default::default()
+            },
+            CellPolygons {
+                name: "TOP".into(),
+                refs: vec![CellRef {
+                    name: "LEAF".into(),
+                    origin: (5, -7),
+                    strans,
+                }],
+                arefs: vec![CellArrayRef {
+                    name: "LEAF".into(),
+                    cols: 3,
+                    rows: 2,
+                    origin: (0, 0),
+                    col_corner: (30, 0),
+                    row_corner: (0, 40),
+                    strans: Strans::default(),
+                }],
+                ..Default::default()
+            },
+        ];
+        write_gds(tmp.path(), "LIB", units(), &cells).unwrap();
+
+        let back = read_gds_polygons(tmp.path()).unwrap();
+        let top = &back[1];
+        assert_eq!(top.refs.len(), 1);
+        assert_eq!(top.refs[0].name, "LEAF");
+        assert_eq!(top.refs[0].origin, (5, -7));
+        assert_eq!(top.refs[0].strans, strans);
+        let a = &top.arefs[0];
+        assert_eq!((a.cols, a.rows), (3, 2));
+        assert_eq!(
+            (a.origin, a.col_corner, a.row_corner),
+            ((0, 0), (30, 0), (0, 40))
+        );
+        assert!(a.strans.is_identity());
+        let pos: Vec<_> = a.positions().collect();
+        assert_eq!(
+            pos,
+            vec![(0, 0), (10, 0), (20, 0), (0, 20), (10, 20), (20, 20)]
+        );
+    }
+
+    #[test]
+    fn labels_roundtrip_through_writer() {
+        let tmp = NamedTempFile::new().unwrap();
+        let labels = vec![
+            Label {
+                layer: 10,
+                texttype: 2,
+                string: "VDD".into(),
+                position: (150, -20),
+                anchor: Anchor::BottomRight,
+                reflect_x: false,
+                magnification: 0.5,
+                rotation: 90.0,
+            },
+            Label {
+                layer: 10,
+                texttype: 0,
+                string: "A".into(),
+                position: (0, 0),
+                anchor: Anchor::TopLeft,
+                reflect_x: false,
+                magnification: 1.0,
+                rotation: 0.0,
+            },
+        ];
+        let cells = vec![CellPolygons {
+            name: "TOP".into(),
+            labels: labels.clone(),
+            ..Default::default()
+        }];
+        write_gds(tmp.path(), "LIB", units(), &cells).unwrap();
+
+        let back = read_gds_polygons(tmp.path()).unwrap();
+        assert_eq!(back[0].labels, labels);
+    }
+
+    #[test]
+    fn compressed_output_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let cells = rect_cell();
+        for (name, codec) in [
+            ("top.gds.gz", Compression::Gzip),
+            ("top.gds.zst", Compression::Zstd),
+            ("top.gds.bz2", Compression::Bzip2),
+        ] {
+            let path = dir.path().join(name);
+            write_gds(&path, "LIB", units(), &cells).unwrap();
+            let bytes = std::fs::read(&path).unwrap();
+            assert_eq!(Compression::detect(&bytes), codec);
+            let back = read_gds_polygons(&path).unwrap();
+            assert_eq!(back[0].polys[0].xy, cells[0].polys[0].xy);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Byte-range index of the structures in a GDSII stream, for decoding cells on demand.
+//!
+//! [`GdsIndex::scan`] walks the record headers only (payloads are skipped, apart from
+//! LIBNAME, UNITS and STRNAME) and notes where each BGNSTR..ENDSTR block lies; any
+//! block can then be decoded on its own with the regular record parser.
+
+use crate::{
+    compress, parse_records, visitor::CellCollector, CellPolygons, Compression, Diagnostic,
+    GdsRecordReader, GdsSummary, IoError, ReadOptions, Units, RT_BGNSTR, RT_ENDLIB, RT_ENDSTR,
+    RT_LIBNAME, RT_STRNAME, RT_UNITS,
+};
+use memmap2::Mmap;
+use std::{collections::HashMap, fs::File, io::Read, ops::Range};
+
+/// An uncompressed GDSII stream held in memory: memory-mapped from a file, or
+/// decompressed into a buffer.
+pub enum GdsImage {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl GdsImage {
+    /// Map the file; compressed files are decompressed into memory instead.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, IoError> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read. As with any mmap-based reader, the file must
+        // not be truncated while the image is alive.
+        let map = unsafe { Mmap::map(&file)? };
+        if Compression::detect(&map) == Compression::None {
+            Ok(GdsImage::Mapped(map))
+        } else {
+            Self::from_reader(&map[..])
+        }
+    }
+
+    /// Read `r` to the end, decompressing it if needed.
+    pub fn from_reader<R: Read>(r: R) -> Result<Self, IoError> {
+        let mut data = Vec::new();
+        compress::decompress(r)?.read_to_end(&mut data)?;
+        Ok(GdsImage::Owned(data))
+    }
+}
+
+impl std::ops::Deref for GdsImage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            GdsImage::Mapped(m) => m,
+            GdsImage::Owned(v) => v,
+        }
+    }
+}
+
+/// One BGNSTR..ENDSTR block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Empty if the block has no STRNAME.
+    pub name: String,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct GdsIndex {
+    pub libname: Option<String>,
+    pub units: Option<Units>,
+    /// Blocks in stream order.
+    pub entries: Vec<IndexEntry>,
+    /// First entry of each structure name, filled by `scan`.
+    names: HashMap<String, usize>,
+    /// Whether the scan reached ENDLIB.
+    pub(crate) endlib: bool,
+}
+
+impl GdsIndex {
+    /// Index an uncompressed GDSII stream.
+    ///
+    /// A structure without ENDSTR ends where the next one begins. At the first header
+    /// that cannot be framed the scan stops and gives the rest of the input to a last
+    /// entry, so decoding that entry reports the error with its cell and element.
+    pub fn scan(data: &[u8]) -> Result<Self, IoError> {
+        let mut summary = GdsSummary::default();
+        let mut entries = Vec::new();
+        let mut open: Option<IndexEntry> = None;
+        let mut endlib = false;
+        let mut pos = 0;
+        while pos < data.len() {
+            let len = match data.get(pos..pos + 4) {
+                Some(h) => u16::from_be_bytes([h[0], h[1]]) as usize,
+                None => 0,
+            };
+            if len < 4 || !len.is_multiple_of(2) || pos + len > data.len() {
+                let mut last = open.take().unwrap_or(IndexEntry {
+                    name: String::new(),
+                    range: pos..pos,
+                });
+                last.range.end = data.len();
+                entries.push(last);
+                pos = data.len();
+                break;
+            }
+            match data[pos + 2] {
+                RT_BGNSTR => {
+                    if let Some(mut e) = open.take() {
+                        e.range.end = pos;
+                        entries.push(e);
+                    }
+                    open = Some(IndexEntry {
+                        name: String::new(),
+                        range: pos..pos,
+                    });
+                }
+                RT_ENDSTR => {
+                    if let Some(mut e) = open.take() {
+                        e.range.end = pos + len;
+                        entries.push(e);
+                    }
+                }
+                RT_STRNAME => {
+                    // like the parser, the first STRNAME names the structure
+                    if let Some(e) = open.as_mut().filter(|e| e.name.is_empty()) {
+                        let rec = read_one(data, pos, len)?;
+                        e.name = rec.payload.ascii().unwrap_or_default().to_string();
+                    }
+                }
+                RT_LIBNAME | RT_UNITS => {
+                    let rec = read_one(data, pos, len)?;
+                    summary.apply(rec.rectype, &rec.payload);
+                }
+                RT_ENDLIB => {
+                    endlib = true;
+                    pos += len;
+                    break;
+                }
+                _ => {}
+            }
+            pos += len;
+        }
+        if let Some(mut e) = open {
+            e.range.end = pos;
+            entries.push(e);
+        }
+        let mut names = HashMap::new();
+        for (i, e) in entries.iter().enumerate() {
+            names.entry(e.name.clone()).or_insert(i);
+        }
+        Ok(GdsIndex {
+            libname: summary.libname,
+            units: summary.units,
+            entries,
+            names,
+            endlib,
+        })
+    }
+
+    /// Position of the first entry named `name`.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Decode the cells of entry `i` (one, unless the block is unnamed) from the
+    /// stream the index was built on.
+    pub fn read_entry(&self, data: &[u8], i: usize) -> Result<Vec<CellPolygons>, IoError> {
+        Ok(self.read_entry_with(data, i, &ReadOptions::default())?.0)
+    }
+
+    /// [`read_entry`](Self::read_entry) with `opts`, also returning what the parser
+    /// worked around. The entry is parsed as a stream of its own, so the diagnostics
+    /// always include a missing ENDLIB.
+    pub fn read_entry_with(
+        &self,
+        data: &[u8],
+        i: usize,
+        opts: &ReadOptions,
+    ) -> Result<(Vec<CellPolygons>, Vec<Diagnostic>), IoError> {
+        let range = self.entries[i].range.clone();
+        let records = GdsRecordReader::new(&data[range.clone()]).starting_at(range.start as u64);
+        let mut cells = CellCollector::default();
+        let diags = parse_records(records, opts, &mut cells)?;
+        Ok((cells.cells, diags))
+    }
+
+    /// Whether two entries share a structure name.
+    pub(crate) fn has_duplicates(&self) -> bool {
+        self.names.len() < self.entries.len()
+    }
+}
+
+/// Decode the record at `pos`, whose header the scan has already framed.
+fn read_one(data: &[u8], pos: usize, len: usize) -> Result<crate::Record, IoError> {
+    let mut records = GdsRecordReader::new(&data[pos..pos + len]).starting_at(pos as u64);
+    records
+        .next()
+        .expect("a framed record always yields an item")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_gds;
+
+    #[test]
+    fn entries_decode_to_the_same_cells() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let (summary, cells) = read_gds(src).unwrap();
+        let image = GdsImage::open(src).unwrap();
+        let index = GdsIndex::scan(&image).unwrap();
+        assert_eq!(index.libname, summary.libname);
+        assert_eq!(index.units, summary.units);
+        let names: Vec<_> = index.entries.iter().map(|e| e.name.as_str()).collect();
+        let expected: Vec<_> = cells.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, expected);
+
+        let last = cells.last().unwrap();
+        let i = index.position(&last.name).unwrap();
+        let decoded = index.read_entry(&image, i).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", [last]));
+    }
+}
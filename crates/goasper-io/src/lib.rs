@@ -1,4 +1,10 @@
-//! Minimal GDSII reader: extracts cell names by scanning BGNSTR..ENDSTR and STRNAME.
+//! Layout I/O: reads GDSII into cells with their polygons, paths, instances and
+//! labels, plus a per-cell summary and the library units. All GDS readers are built
+//! on the [`record`] tokenizer. [`gds_writer`] writes the cell/polygon model back as
+//! GDSII; the [`oasis`] module reads and writes the same model as OASIS.
+//! [`parallel`] reads large uncompressed GDS files memory-mapped, decoding cells on
+//! all cores; [`index`] locates cells for decoding on demand, and [`visitor`] streams
+//! elements one at a time.
 
 // GDS record types
 const RT_BGNSTR: u8 = 0x05;
@@ -6,25 +12,60 @@ const RT_STRNAME: u8 = 0x06;
 const RT_ENDSTR: u8 = 0x07;
 const RT_ENDLIB: u8 = 0x04;
 const RT_LIBNAME: u8 = 0x02;
+const RT_UNITS: u8 = 0x03;
 const RT_BOUNDARY: u8 = 0x08;
+const RT_PATH: u8 = 0x09;
 const RT_LAYER: u8 = 0x0D;
 const RT_DATATYPE: u8 = 0x0E;
 const RT_XY: u8 = 0x10;
 const RT_ENDEL: u8 = 0x11;
+const RT_WIDTH: u8 = 0x0F;
+const RT_PATHTYPE: u8 = 0x21;
+const RT_BGNEXTN: u8 = 0x30;
+const RT_ENDEXTN: u8 = 0x31;
+const RT_SREF: u8 = 0x0A;
+const RT_AREF: u8 = 0x0B;
+const RT_SNAME: u8 = 0x12;
+const RT_COLROW: u8 = 0x13;
+const RT_STRANS: u8 = 0x1A;
+const RT_MAG: u8 = 0x1B;
+const RT_ANGLE: u8 = 0x1C;
+const RT_TEXT: u8 = 0x0C;
+const RT_TEXTTYPE: u8 = 0x16;
+const RT_PRESENTATION: u8 = 0x17;
+const RT_STRING: u8 = 0x19;
 
 // datatypes
+const DT_BITARRAY: u8 = 0x01;
 const DT_INT2: u8 = 0x02;
 const DT_INT4: u8 = 0x03;
+const DT_REAL8: u8 = 0x05;
 const DT_ASCII: u8 = 0x06;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
-    io::{BufReader, Read},
-    path::Path,
+    io::Read,
 };
 use thiserror::Error;
 
+pub mod compress;
+pub mod gds_writer;
+pub mod index;
+pub mod oasis;
+mod outline;
+pub mod parallel;
+pub mod record;
+pub mod visitor;
+
+pub use compress::Compression;
+pub use gds_writer::{write_gds, write_gds_to};
+pub use index::{GdsImage, GdsIndex, IndexEntry};
+pub use oasis::{read_oas, read_oas_from, write_oas};
+pub use parallel::{read_gds_parallel, read_gds_parallel_with, read_gds_polygons_parallel};
+pub use record::{GdsRecordReader, Record, RecordData};
+pub use visitor::{visit_gds, visit_gds_from, LayoutVisitor, SummaryVisitor};
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct BBox {
     pub xmin: i32,
@@ -61,14 +102,139 @@ pub struct CellSummary {
     pub bbox: Option<BBox>, // DBU coordinates
     pub layer_poly_counts: HashMap<(u16, u16), usize>,
     pub total_polys: usize,
+    pub layer_path_counts: HashMap<(u16, u16), usize>,
+    pub total_paths: usize,
+}
+
+/// Library units from the GDS UNITS record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    /// Size of one database unit in user units (typically 1e-3, i.e. µm → nm).
+    pub user_units_per_dbu: f64,
+    /// Size of one database unit in meters (typically 1e-9).
+    pub meters_per_dbu: f64,
+}
+
+impl Units {
+    /// Size of one database unit in microns.
+    pub fn microns_per_dbu(&self) -> f64 {
+        self.meters_per_dbu * 1e6
+    }
+
+    /// Convert a DBU length or coordinate to microns.
+    pub fn to_microns(&self, dbu: i64) -> f64 {
+        dbu as f64 * self.microns_per_dbu()
+    }
+
+    /// `bbox` as (xmin, ymin, xmax, ymax) in microns.
+    pub fn bbox_to_microns(&self, bbox: &BBox) -> (f64, f64, f64, f64) {
+        (
+            self.to_microns(bbox.xmin as i64),
+            self.to_microns(bbox.ymin as i64),
+            self.to_microns(bbox.xmax as i64),
+            self.to_microns(bbox.ymax as i64),
+        )
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct GdsSummary {
     pub libname: Option<String>,
+    pub units: Option<Units>,
     pub cells: Vec<CellSummary>,
 }
 
+impl GdsSummary {
+    /// Take a LIBNAME or UNITS record into the summary.
+    fn apply(&mut self, rectype: u8, data: &RecordData) {
+        match (rectype, data) {
+            (RT_LIBNAME, RecordData::Ascii(name)) => self.libname = Some(name.clone()),
+            (RT_UNITS, RecordData::Real8(v)) if v.len() >= 2 => {
+                self.units = Some(Units {
+                    user_units_per_dbu: v[0],
+                    meters_per_dbu: v[1],
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// How the GDS readers treat damaged input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Fail on the first corrupt record or element (the default). When false, broken
+    /// elements are dropped, reading resumes at the next valid record header and each
+    /// repair is reported as a [`Diagnostic`].
+    pub strict: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions { strict: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A corrupt record was skipped (lenient mode only).
+    BadRecord,
+    /// An element was discarded because it was corrupt or never closed.
+    DroppedElement,
+    /// A structure had no ENDSTR; its elements are kept.
+    UnclosedStructure,
+    /// The stream ended without ENDLIB.
+    MissingEndlib,
+    /// A structure name was defined more than once; every definition is kept.
+    DuplicateCell,
+    /// A cell of a lazily opened file did not decode and is listed empty.
+    DroppedCell,
+}
+
+impl DiagnosticKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticKind::BadRecord => "bad_record",
+            DiagnosticKind::DroppedElement => "dropped_element",
+            DiagnosticKind::UnclosedStructure => "unclosed_structure",
+            DiagnosticKind::MissingEndlib => "missing_endlib",
+            DiagnosticKind::DuplicateCell => "duplicate_cell",
+            DiagnosticKind::DroppedCell => "dropped_cell",
+        }
+    }
+}
+
+/// A problem the reader worked around instead of failing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// Byte offset of the record that triggered it (end of input for MissingEndlib).
+    pub offset: u64,
+    pub cell: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, offset: u64, cell: Option<&str>, message: String) -> Self {
+        Diagnostic {
+            kind,
+            offset,
+            cell: cell.filter(|c| !c.is_empty()).map(str::to_string),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "offset {}", self.offset)?;
+        if let Some(cell) = &self.cell {
+            write!(f, ", cell {}", cell)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum IoError {
     #[error("I/O: {0}")]
@@ -80,8 +246,89 @@ pub enum IoError {
         rectype: u8,
         dtype: u8,
     },
-    #[error("Unexpected EOF")]
-    Eof,
+    /// The stream ended inside a record; `rectype` is `None` when the GDS header
+    /// itself was cut short, and for OASIS.
+    #[error("Truncated record at offset {offset}{}: needs {needed} bytes, only {available} left", rectype_suffix(.rectype))]
+    Truncated {
+        offset: u64,
+        rectype: Option<u8>,
+        needed: u64,
+        available: u64,
+    },
+    /// GDSII records always have an even length.
+    #[error("Odd-length GDS record at offset {offset} (len={len}, rectype={rectype:#04x}, dtype={dtype:#04x})")]
+    OddLength {
+        offset: u64,
+        len: u16,
+        rectype: u8,
+        dtype: u8,
+    },
+    /// XY must hold whole INT4 (x, y) pairs.
+    #[error("XY record at offset {offset} has a {len}-byte payload (dtype={dtype:#04x}), not a multiple of 8")]
+    BadXy { offset: u64, len: u16, dtype: u8 },
+    #[error("Malformed OASIS at offset {offset}: {msg}")]
+    Oasis { offset: u64, msg: String },
+    /// Well-formed input using a feature this crate does not implement.
+    #[error("Unsupported at offset {offset}: {what}")]
+    Unsupported { offset: u64, what: String },
+    #[error("Cannot write: {0}")]
+    Write(String),
+    /// Any of the above, annotated with where in the library it happened.
+    #[error("{source} (cell {}, element {})", .cell.as_deref().unwrap_or("<none>"), .element.unwrap_or("<none>"))]
+    Context {
+        cell: Option<String>,
+        element: Option<&'static str>,
+        #[source]
+        source: Box<IoError>,
+    },
+}
+
+fn rectype_suffix(rectype: &Option<u8>) -> String {
+    match rectype {
+        Some(r) => format!(" (rectype={:#04x})", r),
+        None => String::new(),
+    }
+}
+
+impl IoError {
+    /// Byte offset of the offending record, if the error is tied to one.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            IoError::Malformed { offset, .. }
+            | IoError::Truncated { offset, .. }
+            | IoError::OddLength { offset, .. }
+            | IoError::BadXy { offset, .. }
+            | IoError::Oasis { offset, .. }
+            | IoError::Unsupported { offset, .. } => Some(*offset),
+            IoError::Context { source, .. } => source.offset(),
+            IoError::Io(_) | IoError::Write(_) => None,
+        }
+    }
+
+    /// GDS record type of the offending record, if known.
+    pub fn rectype(&self) -> Option<u8> {
+        match self {
+            IoError::Malformed { rectype, .. } | IoError::OddLength { rectype, .. } => {
+                Some(*rectype)
+            }
+            IoError::Truncated { rectype, .. } => *rectype,
+            IoError::BadXy { .. } => Some(RT_XY),
+            IoError::Context { source, .. } => source.rectype(),
+            _ => None,
+        }
+    }
+
+    /// Annotate with the cell and element being parsed.
+    fn in_element(self, cell: Option<&str>, element: Option<&'static str>) -> Self {
+        match self {
+            IoError::Context { .. } => self,
+            e => IoError::Context {
+                cell: cell.filter(|c| !c.is_empty()).map(str::to_string),
+                element,
+                source: Box::new(e),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -92,71 +339,354 @@ pub struct Polygon {
     pub xy: Vec<(i32, i32)>,
 }
 
-#[derive(Debug, Default)]
+/// PATH element: a centerline with a width and end style.
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub layer: u16,
+    pub datatype: u16,
+    /// Full width in DBU; negative means absolute (not scaled by references).
+    pub width: i32,
+    /// 0 = flush, 1 = round, 2 = half-width extension, 4 = custom (BGNEXTN/ENDEXTN).
+    pub pathtype: u16,
+    pub bgnextn: i32,
+    pub endextn: i32,
+    /// Centerline points in DBU.
+    pub xy: Vec<(i32, i32)>,
+}
+
+impl Path {
+    pub fn half_width(&self) -> f64 {
+        self.width.unsigned_abs() as f64 / 2.0
+    }
+
+    /// Effective (begin, end) extensions beyond the end points. Round ends (type 1)
+    /// report their radius, which bounds the cap.
+    pub fn extensions(&self) -> (f64, f64) {
+        match self.pathtype {
+            1 | 2 => (self.half_width(), self.half_width()),
+            4 => (self.bgnextn as f64, self.endextn as f64),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Bounding box of the path outline including width and extensions. Exact for
+    /// Manhattan paths; otherwise it encloses [`outline`](Self::outline) (and the
+    /// true circle of round ends).
+    pub fn bbox(&self) -> Option<BBox> {
+        let first = *self.xy.first()?;
+        let hw = self.half_width();
+        let (bext, eext) = self.extensions();
+        let pts: Vec<(f64, f64)> = self.xy.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+        let n = pts.len();
+        let dir = |a: (f64, f64), b: (f64, f64)| {
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let l = (dx * dx + dy * dy).sqrt();
+            if l == 0.0 {
+                (0.0, 0.0)
+            } else {
+                (dx / l, dy / l)
+            }
+        };
+        let mut bb = BBox {
+            xmin: first.0,
+            ymin: first.1,
+            xmax: first.0,
+            ymax: first.1,
+        };
+        let clamp = |v: f64| v.clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+        let mut include = |x: f64, y: f64| {
+            bb.include_pt(clamp(x.floor()), clamp(y.floor()));
+            bb.include_pt(clamp(x.ceil()), clamp(y.ceil()));
+        };
+        if n < 2 {
+            include(pts[0].0 - hw, pts[0].1 - hw);
+            include(pts[0].0 + hw, pts[0].1 + hw);
+            return Some(bb);
+        }
+        // end caps: the extended end point offset perpendicular to its segment
+        for (p, d, ext) in [
+            (pts[0], dir(pts[1], pts[0]), bext),
+            (pts[n - 1], dir(pts[n - 2], pts[n - 1]), eext),
+        ] {
+            let e = (p.0 + d.0 * ext, p.1 + d.1 * ext);
+            include(e.0 - d.1 * hw, e.1 + d.0 * hw);
+            include(e.0 + d.1 * hw, e.1 - d.0 * hw);
+        }
+        // interior joins stay within half-width of the vertex for right angles;
+        // other miters and cut-off sharp turns reach further
+        for &(x, y) in &pts[1..n - 1] {
+            include(x - hw, y - hw);
+            include(x + hw, y + hw);
+        }
+        for (x, y) in self.outline() {
+            include(x, y);
+        }
+        Some(bb)
+    }
+}
+
+/// Instance transformation (STRANS/MAG/ANGLE): reflect about the x axis, then
+/// magnify, then rotate counterclockwise, then translate to the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strans {
+    pub reflect_x: bool,
+    /// Magnification is absolute, i.e. not multiplied by parent magnifications.
+    pub abs_mag: bool,
+    /// Angle is absolute, i.e. not added to parent rotations.
+    pub abs_angle: bool,
+    pub mag: f64,
+    /// Rotation in degrees, counterclockwise.
+    pub angle: f64,
+}
+
+impl Default for Strans {
+    fn default() -> Self {
+        Strans {
+            reflect_x: false,
+            abs_mag: false,
+            abs_angle: false,
+            mag: 1.0,
+            angle: 0.0,
+        }
+    }
+}
+
+impl Strans {
+    pub fn is_identity(&self) -> bool {
+        !self.reflect_x && self.mag == 1.0 && self.angle == 0.0
+    }
+
+    fn apply(&mut self, rectype: u8, data: &RecordData) {
+        match (rectype, data.bits(), data.real8()) {
+            (RT_STRANS, Some(bits), _) => {
+                self.reflect_x = bits & 0x8000 != 0;
+                self.abs_mag = bits & 0x0004 != 0;
+                self.abs_angle = bits & 0x0002 != 0;
+            }
+            (RT_MAG, _, Some(v)) => self.mag = v,
+            (RT_ANGLE, _, Some(v)) => self.angle = v,
+            _ => {}
+        }
+    }
+}
+
+/// SREF: a single placement of another cell.
+#[derive(Debug, Clone)]
+pub struct CellRef {
+    pub name: String,
+    pub origin: (i32, i32),
+    pub strans: Strans,
+}
+
+/// AREF: a cols x rows lattice of placements. The lattice is given by its three GDS
+/// points: the origin, `col_corner` = origin + cols * column pitch and
+/// `row_corner` = origin + rows * row pitch.
+#[derive(Debug, Clone)]
+pub struct CellArrayRef {
+    pub name: String,
+    pub cols: u16,
+    pub rows: u16,
+    pub origin: (i32, i32),
+    pub col_corner: (i32, i32),
+    pub row_corner: (i32, i32),
+    pub strans: Strans,
+}
+
+impl CellArrayRef {
+    /// Origin of every instance, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let (cols, rows) = (self.cols.max(1), self.rows.max(1));
+        (0..rows).flat_map(move |r| (0..cols).map(move |c| self.position(c, r)))
+    }
+
+    /// Origins of the first and last instance of the first and last row; every
+    /// other instance lies within their hull.
+    pub fn corner_positions(&self) -> [(i32, i32); 4] {
+        let (c, r) = (self.cols.max(1) - 1, self.rows.max(1) - 1);
+        [
+            self.position(0, 0),
+            self.position(c, 0),
+            self.position(0, r),
+            self.position(c, r),
+        ]
+    }
+
+    fn position(&self, col: u16, row: u16) -> (i32, i32) {
+        let (cols, rows) = (self.cols.max(1) as i64, self.rows.max(1) as i64);
+        let o = (self.origin.0 as i64, self.origin.1 as i64);
+        let step = |corner: (i32, i32), n: i64, i: i64| {
+            (
+                (corner.0 as i64 - o.0) * i / n,
+                (corner.1 as i64 - o.1) * i / n,
+            )
+        };
+        let (cx, cy) = step(self.col_corner, cols, col as i64);
+        let (rx, ry) = step(self.row_corner, rows, row as i64);
+        ((o.0 + cx + rx) as i32, (o.1 + cy + ry) as i32)
+    }
+}
+
+/// Text justification relative to the label position (PRESENTATION bits 0-3).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    TopCenter,
+    TopRight,
+    MiddleLeft,
+    Center,
+    MiddleRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl Anchor {
+    const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::TopCenter,
+        Anchor::TopRight,
+        Anchor::MiddleLeft,
+        Anchor::Center,
+        Anchor::MiddleRight,
+        Anchor::BottomLeft,
+        Anchor::BottomCenter,
+        Anchor::BottomRight,
+    ];
+
+    /// Horizontal justification is bits 0-1 (left/center/right), vertical is bits 2-3
+    /// (top/middle/bottom); the reserved value 3 falls back to left/top.
+    pub fn from_presentation(bits: u16) -> Self {
+        let h = (bits & 0x3) as usize % 3;
+        let v = ((bits >> 2) & 0x3) as usize % 3;
+        Self::ALL[v * 3 + h]
+    }
+
+    /// PRESENTATION bits for this anchor (font 0).
+    pub fn presentation(self) -> u16 {
+        let i = self as u16;
+        ((i / 3) << 2) | (i % 3)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top_left",
+            Anchor::TopCenter => "top_center",
+            Anchor::TopRight => "top_right",
+            Anchor::MiddleLeft => "middle_left",
+            Anchor::Center => "center",
+            Anchor::MiddleRight => "middle_right",
+            Anchor::BottomLeft => "bottom_left",
+            Anchor::BottomCenter => "bottom_center",
+            Anchor::BottomRight => "bottom_right",
+        }
+    }
+}
+
+/// TEXT element: a string placed at a point, e.g. a pin label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub layer: u16,
+    pub texttype: u16,
+    pub string: String,
+    pub position: (i32, i32),
+    pub anchor: Anchor,
+    pub reflect_x: bool,
+    pub magnification: f64,
+    /// Rotation in degrees, counterclockwise.
+    pub rotation: f64,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct CellPolygons {
     pub name: String,
     pub polys: Vec<Polygon>,
+    pub paths: Vec<Path>,
+    pub refs: Vec<CellRef>,
+    pub arefs: Vec<CellArrayRef>,
+    pub labels: Vec<Label>,
 }
 
-/// Read cell (structure) names from a GDSII file by scanning records.
-/// This is tolerant to extra/unknown records and only relies on BGNSTR/STRNAME/ENDSTR.
-pub fn read_gds_cell_names<P: AsRef<Path>>(path: P) -> Result<Vec<String>, IoError> {
-    let f = File::open(path)?;
-    let mut r = BufReader::new(f);
-    let mut buf = Vec::with_capacity(1 << 16);
+impl CellSummary {
+    /// Derive bbox and per-layer counts from already decoded polygons and paths.
+    pub fn from_polygons(c: &CellPolygons) -> Self {
+        let mut s = CellSummary {
+            name: c.name.clone(),
+            ..Default::default()
+        };
+        for p in &c.polys {
+            s.add_polygon(p);
+        }
+        for p in &c.paths {
+            s.add_path(p);
+        }
+        s
+    }
 
-    let mut offset: u64 = 0;
-    let mut in_struct = false;
-    let mut cells = Vec::new();
-
-    loop {
-        // Header: 2 bytes length (BE), 1 byte rectype, 1 byte dtype
-        let mut hdr = [0u8; 4];
-        if let Err(e) = r.read_exact(&mut hdr) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                break; // clean EOF
+    /// Count a polygon and grow the bbox by its points.
+    pub fn add_polygon(&mut self, p: &Polygon) {
+        *self
+            .layer_poly_counts
+            .entry((p.layer, p.datatype))
+            .or_insert(0) += 1;
+        self.total_polys += 1;
+        for &(x, y) in &p.xy {
+            self.bbox
+                .get_or_insert(BBox {
+                    xmin: x,
+                    ymin: y,
+                    xmax: x,
+                    ymax: y,
+                })
+                .include_pt(x, y);
+        }
+    }
+
+    /// Count a path and grow the bbox by its outline (width and extensions included).
+    pub fn add_path(&mut self, p: &Path) {
+        *self
+            .layer_path_counts
+            .entry((p.layer, p.datatype))
+            .or_insert(0) += 1;
+        self.total_paths += 1;
+        self.include_path(p);
+    }
+
+    fn include_path(&mut self, p: &Path) {
+        if let Some(pb) = p.bbox() {
+            if let Some(cb) = self.bbox.as_mut() {
+                cb.include_bbox(&pb);
             } else {
-                return Err(IoError::Io(e));
+                self.bbox = Some(pb);
             }
         }
-        offset += 4;
-        let len = u16::from_be_bytes([hdr[0], hdr[1]]);
-        let rectype = hdr[2];
-        let dtype = hdr[3];
+    }
+}
 
-        if len < 4 {
-            return Err(IoError::Malformed {
-                offset,
-                len,
-                rectype,
-                dtype,
-            });
-        }
-        let pay = (len - 4) as usize;
-        buf.resize(pay, 0);
-        if pay > 0 {
-            r.read_exact(&mut buf)?;
-            offset += pay as u64;
-        }
+/// Read cell (structure) names from a GDSII file by scanning records.
+/// This is tolerant to extra/unknown records and only relies on BGNSTR/STRNAME/ENDSTR.
+pub fn read_gds_cell_names<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<String>, IoError> {
+    read_gds_cell_names_from(File::open(path)?)
+}
 
-        match rectype {
-            RT_BGNSTR => {
+/// [`read_gds_cell_names`] over any reader.
+pub fn read_gds_cell_names_from<R: Read>(r: R) -> Result<Vec<String>, IoError> {
+    let mut in_struct = false;
+    let mut cells: Vec<String> = Vec::new();
+
+    for rec in GdsRecordReader::new(compress::decompress(r)?) {
+        let cell = if in_struct { cells.last() } else { None };
+        let rec = rec.map_err(|e| e.in_element(cell.map(String::as_str), None))?;
+        match (rec.rectype, &rec.payload) {
+            (RT_BGNSTR, _) => {
                 in_struct = true;
             }
-            RT_STRNAME if in_struct && dtype == DT_ASCII => {
-                // Strings are even-length, null-padded ASCII.
-                let s = trim_gds_ascii(&buf);
-                if !s.is_empty() {
-                    cells.push(s.to_string());
-                }
+            (RT_STRNAME, RecordData::Ascii(s)) if in_struct && !s.is_empty() => {
+                cells.push(s.clone());
             }
-            RT_ENDSTR => {
+            (RT_ENDSTR, _) => {
                 in_struct = false;
             }
-            RT_ENDLIB => {
-                // Stop early when ENDLIB is found.
-                break;
-            }
             _ => {
                 // ignore other records
             }
@@ -166,266 +696,455 @@ pub fn read_gds_cell_names<P: AsRef<Path>>(path: P) -> Result<Vec<String>, IoErr
     Ok(cells)
 }
 
-/// Read polygons (BOUNDARY only) grouped per cell.
-/// Returns Vec<CellPolygons> in the order cells appear in the stream.
-pub fn read_gds_polygons<P: AsRef<Path>>(path: P) -> Result<Vec<CellPolygons>, IoError> {
-    let f = File::open(path)?;
-    let mut r = BufReader::new(f);
-    let mut buf = Vec::with_capacity(1 << 16);
+/// Read polygons (BOUNDARY), paths (PATH), instances (SREF/AREF) and labels (TEXT)
+/// grouped per cell. Returns Vec<CellPolygons> in the order cells appear in the stream.
+pub fn read_gds_polygons<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<CellPolygons>, IoError> {
+    Ok(read_gds(path)?.1)
+}
+
+/// [`read_gds_polygons`] over any reader.
+pub fn read_gds_polygons_from<R: Read>(r: R) -> Result<Vec<CellPolygons>, IoError> {
+    Ok(read_gds_from(r)?.1)
+}
+
+/// Read the whole library in a single pass: LIBNAME/UNITS plus every cell's elements.
+/// The per-cell summaries are derived from the decoded geometry.
+pub fn read_gds<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    read_gds_from(File::open(path)?)
+}
+
+/// [`read_gds`] over any reader, e.g. an in-memory `&[u8]` or an archive member.
+/// The input is buffered internally and gzip/zstd/bzip2 streams are decompressed
+/// on the fly (detected by their magic bytes).
+pub fn read_gds_from<R: Read>(r: R) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    let (summary, cells, _) = read_gds_with_from(r, &ReadOptions::default())?;
+    Ok((summary, cells))
+}
+
+/// [`read_gds`] with explicit [`ReadOptions`], also returning the warnings collected
+/// along the way. Unclosed structures, a missing ENDLIB and duplicate cell names are
+/// reported in either mode; in lenient mode only I/O failures are errors.
+pub fn read_gds_with<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: &ReadOptions,
+) -> Result<(GdsSummary, Vec<CellPolygons>, Vec<Diagnostic>), IoError> {
+    read_gds_with_from(File::open(path)?, opts)
+}
+
+/// [`read_gds_with`] over any reader.
+pub fn read_gds_with_from<R: Read>(
+    r: R,
+    opts: &ReadOptions,
+) -> Result<(GdsSummary, Vec<CellPolygons>, Vec<Diagnostic>), IoError> {
+    let mut cells = visitor::CellCollector::default();
+    let diags = visit_gds_from(r, opts, &mut cells)?;
+    let (summary, cells) = cells.finish();
+    Ok((summary, cells, diags))
+}
 
-    let mut out: Vec<CellPolygons> = Vec::new();
+/// The record loop behind every GDS reader: decodes each element and hands it to `v`.
+///
+/// A cell begins at the first STRNAME of a structure; elements of unnamed structures
+/// are not reported. LIBNAME/UNITS are reported once, before the first cell.
+fn parse_records<R: Read, V: LayoutVisitor>(
+    mut records: GdsRecordReader<R>,
+    opts: &ReadOptions,
+    v: &mut V,
+) -> Result<Vec<Diagnostic>, IoError> {
+    let mut library = GdsSummary::default();
+    let mut library_sent = false;
+    let mut diags: Vec<Diagnostic> = Vec::new();
+    let mut defined: HashSet<String> = HashSet::new();
+    let mut saw_endlib = false;
 
     // parser state
     let mut in_struct = false;
-    let mut cur_cell: Option<CellPolygons> = None;
+    let mut cur_cell: Option<String> = None;
 
     let mut in_boundary = false;
+    let mut in_path = false;
     let mut cur_layer: u16 = 0;
     let mut cur_dtype: u16 = 0;
     let mut cur_xy: Vec<(i32, i32)> = Vec::new();
+    let mut cur_path = PathAttrs::default();
 
-    loop {
-        let mut hdr = [0u8; 4];
-        if let Err(e) = r.read_exact(&mut hdr) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                break;
-            } else {
-                return Err(IoError::Io(e));
+    let mut in_sref = false;
+    let mut in_aref = false;
+    let mut cur_sname = String::new();
+    let mut cur_strans = Strans::default();
+    let mut cur_colrow: (u16, u16) = (1, 1);
+
+    let mut in_text = false;
+    let mut cur_presentation: u16 = 0;
+    let mut cur_string = String::new();
+
+    while let Some(rec) = records.next() {
+        let element = if in_boundary {
+            Some("BOUNDARY")
+        } else if in_path {
+            Some("PATH")
+        } else if in_text {
+            Some("TEXT")
+        } else if in_sref {
+            Some("SREF")
+        } else if in_aref {
+            Some("AREF")
+        } else {
+            None
+        };
+        let cell = cur_cell.as_deref();
+        let rec = match rec {
+            Ok(rec) => rec,
+            Err(e @ IoError::Io(_)) => return Err(e.in_element(cell, element)),
+            Err(e) if !opts.strict => {
+                let offset = e.offset().unwrap_or(records.offset());
+                diags.push(Diagnostic::new(
+                    DiagnosticKind::BadRecord,
+                    offset,
+                    cell,
+                    e.to_string(),
+                ));
+                if let Some(el) = element {
+                    diags.push(Diagnostic::new(
+                        DiagnosticKind::DroppedElement,
+                        offset,
+                        cell,
+                        format!("{} dropped after a corrupt record", el),
+                    ));
+                    (in_boundary, in_path, in_text, in_sref, in_aref) = Default::default();
+                }
+                continue;
             }
-        }
-        let len = u16::from_be_bytes([hdr[0], hdr[1]]);
-        let rectype = hdr[2];
-        let dtype = hdr[3];
-        if len < 4 {
-            return Err(IoError::Malformed {
-                offset: 0,
-                len,
-                rectype,
-                dtype,
-            });
-        }
-        let pay = (len - 4) as usize;
-        buf.resize(pay, 0);
-        if pay > 0 {
-            r.read_exact(&mut buf)?;
+            Err(e) => return Err(e.in_element(cell, element)),
+        };
+        let rectype = rec.rectype;
+        let data = &rec.payload;
+        // a new element, ENDSTR or ENDLIB before ENDEL loses the open element
+        if let (
+            Some(el),
+            RT_BOUNDARY | RT_PATH | RT_TEXT | RT_SREF | RT_AREF | RT_ENDSTR | RT_ENDLIB,
+        ) = (element, rectype)
+        {
+            diags.push(Diagnostic::new(
+                DiagnosticKind::DroppedElement,
+                rec.offset,
+                cell,
+                format!("{} has no ENDEL", el),
+            ));
+            (in_boundary, in_path, in_text, in_sref, in_aref) = Default::default();
         }
 
         match rectype {
-            RT_BGNSTR => { in_struct = true; cur_cell = Some(CellPolygons::default()); }
-            RT_STRNAME if in_struct && dtype == DT_ASCII => {
-                if let Some(c) = cur_cell.as_mut() {
-                    c.name = trim_gds_ascii(&buf).to_string();
+            RT_LIBNAME | RT_UNITS => library.apply(rectype, data),
+            RT_ENDLIB => saw_endlib = true,
+            RT_BGNSTR => {
+                if let Some(name) = cur_cell.take() {
+                    diags.push(Diagnostic::new(
+                        DiagnosticKind::UnclosedStructure,
+                        rec.offset,
+                        Some(&name),
+                        "BGNSTR before ENDSTR".to_string(),
+                    ));
+                    finish_cell(v, name, rec.offset, &mut defined, &mut diags);
                 }
+                in_struct = true;
             }
-            RT_BOUNDARY => {
-                in_boundary = true;
+            RT_STRNAME if in_struct && cur_cell.is_none() => {
+                if let Some(name) = data.ascii().filter(|n| !n.is_empty()) {
+                    if !library_sent {
+                        v.on_library(library.libname.as_deref(), library.units);
+                        library_sent = true;
+                    }
+                    v.on_cell_begin(name);
+                    cur_cell = Some(name.to_string());
+                }
+            }
+            RT_BOUNDARY | RT_PATH => {
+                in_boundary = rectype == RT_BOUNDARY;
+                in_path = rectype == RT_PATH;
                 cur_layer = 0;
                 cur_dtype = 0;
                 cur_xy.clear();
+                cur_path = PathAttrs::default();
             }
             // layer/datatype (optional in some files, default 0)
-            0x0D /* RT_LAYER */ if in_boundary && dtype == DT_INT2 => {
-                if buf.len() >= 2 { cur_layer = u16::from_be_bytes([buf[0], buf[1]]); }
-            }
-            0x0E /* RT_DATATYPE */ if in_boundary && dtype == DT_INT2 => {
-                if buf.len() >= 2 { cur_dtype = u16::from_be_bytes([buf[0], buf[1]]); }
-            }
-            0x10 /* RT_XY */ if in_boundary && dtype == 0x03 /* DT_INT4 */ => {
-                if buf.len() % 8 == 0 {
-                    cur_xy.clear();
-                    let n = buf.len() / 8;
-                    // Decode all pairs, drop the duplicate closing point if present
-                    let x0 = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
-                    let y0 = i32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
-                    for i in 0..n {
-                        let x = i32::from_be_bytes([buf[8*i], buf[8*i+1], buf[8*i+2], buf[8*i+3]]);
-                        let y = i32::from_be_bytes([buf[8*i+4], buf[8*i+5], buf[8*i+6], buf[8*i+7]]);
-                        if i + 1 == n && n >= 2 && x == x0 && y == y0 {
-                            break; // drop duplicate close
-                        }
-                        cur_xy.push((x, y));
-                    }
+            RT_LAYER if in_boundary || in_path || in_text => {
+                if let Some(v) = data.int2() {
+                    cur_layer = v as u16;
                 }
             }
-            0x11 /* RT_ENDEL */ if in_boundary => {
-                if let Some(c) = cur_cell.as_mut() {
-                    if !cur_xy.is_empty() {
-                        c.polys.push(Polygon { layer: cur_layer, datatype: cur_dtype, xy: cur_xy.clone() });
-                    }
+            // texttype shares the datatype slot while inside TEXT
+            RT_DATATYPE | RT_TEXTTYPE if in_boundary || in_path || in_text => {
+                if let Some(v) = data.int2() {
+                    cur_dtype = v as u16;
                 }
-                in_boundary = false;
-                cur_xy.clear();
             }
-            RT_ENDSTR => {
-                in_struct = false;
-                if let Some(c) = cur_cell.take() {
-                    if !c.name.is_empty() {
-                        out.push(c);
-                    }
-                }
+            RT_WIDTH | RT_PATHTYPE | RT_BGNEXTN | RT_ENDEXTN if in_path => {
+                cur_path.apply(rectype, data);
             }
-            RT_ENDLIB => break,
-            _ => {}
-        }
-    }
-
-    Ok(out)
-}
-
-fn trim_gds_ascii(bytes: &[u8]) -> &str {
-    // Strip trailing 0x00 padding and any trailing spaces.
-    let mut end = bytes.len();
-    while end > 0 && (bytes[end - 1] == 0 || bytes[end - 1] == b' ') {
-        end -= 1;
-    }
-    std::str::from_utf8(&bytes[..end]).unwrap_or("")
-}
-
-/// Stream a GDS and summarize: libname, per-cell bbox, per-layer polygon counts
-/// Coordinates are raw DBU (GDS integer units); Only BOUNDARY is counted in this MVP
-pub fn read_gds_summary<P: AsRef<Path>>(path: P) -> Result<GdsSummary, IoError> {
-    let f = File::open(path)?;
-    let mut r = BufReader::new(f);
-    let mut buf = Vec::with_capacity(1 << 16);
-
-    let mut offset: u64 = 0;
-    let mut s = GdsSummary::default();
-
-    // parser state
-    let mut in_struct = false;
-    let mut cur: Option<CellSummary> = None;
-
-    let mut in_boundary = false;
-    let mut cur_layer: Option<u16> = None;
-    let mut cur_dtype: Option<u16> = None;
-    let mut cur_poly_bbox: Option<BBox> = None;
-
-    loop {
-        let mut hdr = [0u8; 4];
-        if let Err(e) = r.read_exact(&mut hdr) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                break;
-            } else {
-                return Err(IoError::Io(e));
-            }
-        }
-        offset += 4;
-        let len = u16::from_be_bytes([hdr[0], hdr[1]]);
-        let rectype = hdr[2];
-        let dtype = hdr[3];
-        if len < 4 {
-            return Err(IoError::Malformed {
-                offset,
-                len,
-                rectype,
-                dtype,
-            });
-        }
-
-        let pay = (len - 4) as usize;
-        buf.resize(pay, 0);
-        if pay > 0 {
-            r.read_exact(&mut buf)?;
-            offset += pay as u64;
-        }
-
-        match rectype {
-            RT_LIBNAME if dtype == DT_ASCII => {
-                s.libname = Some(trim_gds_ascii(&buf).to_string());
+            RT_TEXT => {
+                in_text = true;
+                cur_layer = 0;
+                cur_dtype = 0;
+                cur_presentation = 0;
+                cur_string.clear();
+                cur_strans = Strans::default();
+                cur_xy.clear();
             }
-            RT_BGNSTR => {
-                in_struct = true;
-                cur = Some(CellSummary::default());
+            RT_PRESENTATION if in_text => {
+                if let Some(bits) = data.bits() {
+                    cur_presentation = bits;
+                }
             }
-            RT_STRNAME if in_struct && dtype == DT_ASCII => {
-                if let Some(c) = cur.as_mut() {
-                    c.name = trim_gds_ascii(&buf).to_string();
+            RT_STRING if in_text => {
+                if let Some(s) = data.ascii() {
+                    cur_string = s.to_string();
                 }
             }
-            RT_BOUNDARY => {
-                in_boundary = true;
-                cur_layer = None;
-                cur_dtype = None;
-                cur_poly_bbox = None;
+            RT_SREF | RT_AREF => {
+                in_sref = rectype == RT_SREF;
+                in_aref = rectype == RT_AREF;
+                cur_sname.clear();
+                cur_strans = Strans::default();
+                cur_colrow = (1, 1);
+                cur_xy.clear();
             }
-            RT_LAYER if in_boundary && dtype == DT_INT2 => {
-                if buf.len() >= 2 {
-                    cur_layer = Some(u16::from_be_bytes([buf[0], buf[1]]));
+            RT_SNAME if in_sref || in_aref => {
+                if let Some(s) = data.ascii() {
+                    cur_sname = s.to_string();
                 }
             }
-            RT_DATATYPE if in_boundary && dtype == DT_INT2 => {
-                if buf.len() >= 2 {
-                    cur_dtype = Some(u16::from_be_bytes([buf[0], buf[1]]));
+            RT_STRANS | RT_MAG | RT_ANGLE if in_text || in_sref || in_aref => {
+                cur_strans.apply(rectype, data);
+            }
+            RT_COLROW if in_aref => {
+                if let RecordData::Int2(v) = data {
+                    if v.len() >= 2 {
+                        cur_colrow = (v[0] as u16, v[1] as u16);
+                    }
                 }
             }
-            RT_XY if in_boundary && dtype == DT_INT4 => {
-                // parse i32 pairs; last point duplicates first → skip it
-                if buf.len() % 8 == 0 && !buf.is_empty() {
-                    let n = buf.len() / 8;
-                    let x0 = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
-                    let y0 = i32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
-                    let mut bb = BBox {
-                        xmin: i32::MAX,
-                        ymin: i32::MAX,
-                        xmax: i32::MIN,
-                        ymax: i32::MIN,
+            // boundaries drop the duplicate closing point, everything else keeps every point
+            RT_XY if element.is_some() => {
+                let decoded = decode_xy(data, in_boundary, &mut cur_xy);
+                if !decoded {
+                    let e = IoError::BadXy {
+                        offset: rec.offset,
+                        len: rec.len - 4,
+                        dtype: rec.dtype,
                     };
-                    for i in 0..n {
-                        let x = i32::from_be_bytes([
-                            buf[8 * i],
-                            buf[8 * i + 1],
-                            buf[8 * i + 2],
-                            buf[8 * i + 3],
-                        ]);
-                        let y = i32::from_be_bytes([
-                            buf[8 * i + 4],
-                            buf[8 * i + 5],
-                            buf[8 * i + 6],
-                            buf[8 * i + 7],
-                        ]);
-                        if i + 1 == n && n >= 2 && x == x0 && y == y0 {
-                            break;
-                        }
-                        bb.include_pt(x, y);
+                    let cell = cur_cell.as_deref();
+                    if opts.strict {
+                        return Err(e.in_element(cell, element));
                     }
-                    if bb.is_valid() {
-                        cur_poly_bbox = Some(bb);
+                    diags.push(Diagnostic::new(
+                        DiagnosticKind::DroppedElement,
+                        rec.offset,
+                        cell,
+                        format!("{} dropped: {}", element.unwrap_or_default(), e),
+                    ));
+                    (in_boundary, in_path, in_text, in_sref, in_aref) = Default::default();
+                }
+            }
+            RT_ENDEL if in_text => {
+                if let (Some(_), Some(&position)) = (&cur_cell, cur_xy.first()) {
+                    v.on_text(Label {
+                        layer: cur_layer,
+                        texttype: cur_dtype,
+                        string: std::mem::take(&mut cur_string),
+                        position,
+                        anchor: Anchor::from_presentation(cur_presentation),
+                        reflect_x: cur_strans.reflect_x,
+                        magnification: cur_strans.mag,
+                        rotation: cur_strans.angle,
+                    });
+                }
+                in_text = false;
+                cur_xy.clear();
+            }
+            RT_ENDEL if in_sref || in_aref => {
+                if cur_cell.is_some() {
+                    if in_sref && !cur_xy.is_empty() {
+                        v.on_ref(CellRef {
+                            name: std::mem::take(&mut cur_sname),
+                            origin: cur_xy[0],
+                            strans: cur_strans,
+                        });
+                    } else if in_aref && cur_xy.len() >= 3 {
+                        v.on_aref(CellArrayRef {
+                            name: std::mem::take(&mut cur_sname),
+                            cols: cur_colrow.0,
+                            rows: cur_colrow.1,
+                            origin: cur_xy[0],
+                            col_corner: cur_xy[1],
+                            row_corner: cur_xy[2],
+                            strans: cur_strans,
+                        });
                     }
                 }
+                in_sref = false;
+                in_aref = false;
+                cur_xy.clear();
             }
-            RT_ENDEL if in_boundary => {
-                if let Some(c) = cur.as_mut() {
-                    let lay = cur_layer.unwrap_or(0);
-                    let dt = cur_dtype.unwrap_or(0);
-                    *c.layer_poly_counts.entry((lay, dt)).or_insert(0) += 1;
-                    c.total_polys += 1;
-                    if let Some(pb) = cur_poly_bbox {
-                        if let Some(cb) = c.bbox.as_mut() {
-                            cb.include_bbox(&pb);
-                        } else {
-                            c.bbox = Some(pb);
-                        }
+            RT_ENDEL if in_boundary || in_path => {
+                if cur_cell.is_some() && !cur_xy.is_empty() {
+                    let xy = std::mem::take(&mut cur_xy);
+                    if in_boundary {
+                        v.on_polygon(Polygon {
+                            layer: cur_layer,
+                            datatype: cur_dtype,
+                            xy,
+                        });
+                    } else {
+                        v.on_path(cur_path.build(cur_layer, cur_dtype, xy));
                     }
                 }
                 in_boundary = false;
-                cur_layer = None;
-                cur_dtype = None;
-                cur_poly_bbox = None;
+                in_path = false;
+                cur_xy.clear();
             }
             RT_ENDSTR => {
                 in_struct = false;
-                if let Some(c) = cur.take() {
-                    if !c.name.is_empty() {
-                        s.cells.push(c);
-                    }
+                if let Some(name) = cur_cell.take() {
+                    finish_cell(v, name, rec.offset, &mut defined, &mut diags);
                 }
             }
-            RT_ENDLIB => break,
             _ => {}
         }
     }
 
-    Ok(s)
+    let end = records.offset();
+    if let Some(name) = cur_cell.take() {
+        diags.push(Diagnostic::new(
+            DiagnosticKind::UnclosedStructure,
+            end,
+            Some(&name),
+            "input ends before ENDSTR".to_string(),
+        ));
+        finish_cell(v, name, end, &mut defined, &mut diags);
+    }
+    if !library_sent {
+        v.on_library(library.libname.as_deref(), library.units);
+    }
+    if !saw_endlib {
+        diags.push(Diagnostic::new(
+            DiagnosticKind::MissingEndlib,
+            end,
+            None,
+            "input ends without ENDLIB".to_string(),
+        ));
+    }
+
+    Ok(diags)
+}
+
+/// End the current cell, warning when its name was already taken.
+fn finish_cell<V: LayoutVisitor>(
+    v: &mut V,
+    name: String,
+    offset: u64,
+    defined: &mut HashSet<String>,
+    diags: &mut Vec<Diagnostic>,
+) {
+    v.on_cell_end();
+    if defined.contains(&name) {
+        diags.push(Diagnostic::new(
+            DiagnosticKind::DuplicateCell,
+            offset,
+            Some(&name),
+            format!("cell {} is defined more than once", name),
+        ));
+    } else {
+        defined.insert(name);
+    }
+}
+
+/// PATH-only attributes collected between PATH and ENDEL.
+#[derive(Default)]
+struct PathAttrs {
+    width: i32,
+    pathtype: u16,
+    bgnextn: i32,
+    endextn: i32,
+}
+
+impl PathAttrs {
+    fn apply(&mut self, rectype: u8, data: &RecordData) {
+        match (rectype, data.int2(), data.int4()) {
+            (RT_PATHTYPE, Some(v), _) => self.pathtype = v as u16,
+            (RT_WIDTH, _, Some(v)) => self.width = v,
+            (RT_BGNEXTN, _, Some(v)) => self.bgnextn = v,
+            (RT_ENDEXTN, _, Some(v)) => self.endextn = v,
+            _ => {}
+        }
+    }
+
+    fn build(&self, layer: u16, datatype: u16, xy: Vec<(i32, i32)>) -> Path {
+        Path {
+            layer,
+            datatype,
+            width: self.width,
+            pathtype: self.pathtype,
+            bgnextn: self.bgnextn,
+            endextn: self.endextn,
+            xy,
+        }
+    }
+}
+
+/// Decode XY pairs into `out`; `closed` drops a trailing point equal to the first.
+/// Returns false if the payload is not a non-empty list of INT4 pairs.
+fn decode_xy(data: &RecordData, closed: bool, out: &mut Vec<(i32, i32)>) -> bool {
+    out.clear();
+    if matches!(data, RecordData::Int4(v) if v.is_empty()) {
+        return true;
+    }
+    let Some(pts) = data.points() else {
+        return false;
+    };
+    out.extend(pts);
+    if closed && out.len() >= 2 && out.first() == out.last() {
+        out.pop(); // drop duplicate close
+    }
+    true
+}
+
+fn trim_gds_ascii(bytes: &[u8]) -> &str {
+    // Strip trailing 0x00 padding and any trailing spaces.
+    let mut end = bytes.len();
+    while end > 0 && (bytes[end - 1] == 0 || bytes[end - 1] == b' ') {
+        end -= 1;
+    }
+    std::str::from_utf8(&bytes[..end]).unwrap_or("")
+}
+
+/// Decode an 8-byte GDS real (excess-64, base-16 exponent, 56-bit mantissa).
+pub fn gds_real8_to_f64(b: [u8; 8]) -> f64 {
+    let neg = b[0] & 0x80 != 0;
+    let exp = (b[0] & 0x7F) as i32 - 64;
+    let mant = u64::from_be_bytes([0, b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+    let v = mant as f64 / (1u64 << 56) as f64 * 16f64.powi(exp);
+    if neg {
+        -v
+    } else {
+        v
+    }
+}
+
+/// Stream a GDS and summarize: libname, units, per-cell bbox, per-layer polygon and path
+/// counts. Coordinates are raw DBU (GDS integer units); path width/extensions are part
+/// of the cell bbox.
+pub fn read_gds_summary<P: AsRef<std::path::Path>>(path: P) -> Result<GdsSummary, IoError> {
+    read_gds_summary_from(File::open(path)?)
+}
+
+/// [`read_gds_summary`] over any reader.
+pub fn read_gds_summary_from<R: Read>(r: R) -> Result<GdsSummary, IoError> {
+    let mut v = SummaryVisitor::default();
+    visit_gds_from(r, &ReadOptions::default(), &mut v)?;
+    Ok(v.finish())
 }
 
 #[cfg(test)]
@@ -452,7 +1171,7 @@ mod tests {
         let mut bytes = Vec::new();
         bytes.extend(rec(RT_BGNSTR, 0x02, &[0; 24])); // BGNSTR with dummy dates (12 u16)
         let mut nm = b"TOP".to_vec();
-        if nm.len() % 2 != 0 {
+        if !nm.len().is_multiple_of(2) {
             nm.push(0);
         }
         bytes.extend(rec(RT_STRNAME, DT_ASCII, &nm));
@@ -485,13 +1204,13 @@ mod tests {
         // craft a tiny stream: LIBNAME, one struct "TOP" with a rectangle on (1,0)
         let mut bytes = Vec::new();
         let mut lib = b"LIB".to_vec();
-        if lib.len() % 2 != 0 {
+        if !lib.len().is_multiple_of(2) {
             lib.push(0);
         }
         bytes.extend(rec(RT_LIBNAME, DT_ASCII, &lib));
         bytes.extend(rec(RT_BGNSTR, 0x02, &[0; 24]));
         let mut nm = b"TOP".to_vec();
-        if nm.len() % 2 != 0 {
+        if !nm.len().is_multiple_of(2) {
             nm.push(0);
         }
         bytes.extend(rec(RT_STRNAME, DT_ASCII, &nm));
@@ -550,7 +1269,7 @@ mod tests {
         // one struct TOP with one rectangle on layer 1/0
         bytes.extend(rec(RT_BGNSTR, 0x02, &[0; 24]));
         let mut nm = b"TOP".to_vec();
-        if nm.len() % 2 != 0 {
+        if !nm.len().is_multiple_of(2) {
             nm.push(0)
         };
         bytes.extend(rec(RT_STRNAME, DT_ASCII, &nm));
@@ -576,4 +1295,176 @@ mod tests {
         assert_eq!(cells[0].polys[0].datatype, 0);
         assert_eq!(cells[0].polys[0].xy, vec![(0, 0), (10, 0), (10, 5), (0, 5)]);
     }
+
+    #[test]
+    fn path_parsed_and_bbox_includes_width() {
+        use std::io::Write;
+        let mut tmp = NamedTempFile::new().unwrap();
+        fn be_i32(x: i32) -> [u8; 4] {
+            x.to_be_bytes()
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend(rec(RT_BGNSTR, DT_INT2, &[0; 24]));
+        bytes.extend(rec(RT_STRNAME, DT_ASCII, b"WIRE"));
+        bytes.extend(rec(RT_PATH, 0, &[]));
+        bytes.extend(rec(RT_LAYER, DT_INT2, &2u16.to_be_bytes()));
+        bytes.extend(rec(RT_DATATYPE, DT_INT2, &0u16.to_be_bytes()));
+        bytes.extend(rec(RT_PATHTYPE, DT_INT2, &2u16.to_be_bytes()));
+        bytes.extend(rec(RT_WIDTH, DT_INT4, &be_i32(10)));
+        let mut xy = Vec::new();
+        for (x, y) in &[(0, 0), (100, 0), (100, 50)] {
+            xy.extend_from_slice(&be_i32(*x));
+            xy.extend_from_slice(&be_i32(*y));
+        }
+        bytes.extend(rec(RT_XY, DT_INT4, &xy));
+        bytes.extend(rec(RT_ENDEL, 0, &[]));
+        bytes.extend(rec(RT_ENDSTR, 0, &[]));
+        bytes.extend(rec(RT_ENDLIB, 0, &[]));
+        tmp.write_all(&bytes).unwrap();
+
+        let cells = read_gds_polygons(tmp.path()).unwrap();
+        assert!(cells[0].polys.is_empty());
+        let p = &cells[0].paths[0];
+        assert_eq!((p.layer, p.datatype, p.width, p.pathtype), (2, 0, 10, 2));
+        assert_eq!(p.xy, vec![(0, 0), (100, 0), (100, 50)]);
+
+        let s = read_gds_summary(tmp.path()).unwrap();
+        let c = &s.cells[0];
+        assert_eq!(c.total_polys, 0);
+        assert_eq!(c.total_paths, 1);
+        assert_eq!(c.layer_path_counts.get(&(2, 0)).copied(), Some(1));
+        // half-width 5 on every side plus the half-width end extensions
+        let expected = BBox {
+            xmin: -5,
+            ymin: -5,
+            xmax: 105,
+            ymax: 55,
+        };
+        assert_eq!(c.bbox, Some(expected));
+        assert_eq!(CellSummary::from_polygons(&cells[0]).bbox, Some(expected));
+    }
+
+    #[test]
+    fn errors_carry_offset_cell_and_element() {
+        let mut bytes = Vec::new();
+        bytes.extend(rec(RT_BGNSTR, 0x02, &[0; 24]));
+        bytes.extend(rec(RT_STRNAME, DT_ASCII, b"TOP\0"));
+        bytes.extend(rec(RT_BOUNDARY, 0, &[]));
+        let xy_at = bytes.len() as u64;
+        // three INT4 values: not whole (x, y) pairs
+        bytes.extend(rec(RT_XY, DT_INT4, &[0; 12]));
+        bytes.extend(rec(RT_ENDEL, 0, &[]));
+        let e = read_gds_from(&bytes[..]).unwrap_err();
+        match &e {
+            IoError::Context {
+                cell,
+                element,
+                source,
+            } => {
+                assert_eq!(cell.as_deref(), Some("TOP"));
+                assert_eq!(*element, Some("BOUNDARY"));
+                assert!(matches!(**source, IoError::BadXy { len: 12, .. }));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(e.offset(), Some(xy_at));
+        assert_eq!(e.rectype(), Some(RT_XY));
+        let msg = e.to_string();
+        assert!(msg.contains("TOP") && msg.contains("BOUNDARY"), "{}", msg);
+
+        // cut inside the PATH's XY payload
+        let mut bytes = Vec::new();
+        bytes.extend(rec(RT_BGNSTR, 0x02, &[0; 24]));
+        bytes.extend(rec(RT_STRNAME, DT_ASCII, b"WIRE"));
+        bytes.extend(rec(RT_PATH, 0, &[]));
+        let xy_at = bytes.len() as u64;
+        let xy = rec(RT_XY, DT_INT4, &[0; 16]);
+        bytes.extend(&xy[..10]);
+        let e = read_gds_from(&bytes[..]).unwrap_err();
+        assert_eq!(e.offset(), Some(xy_at));
+        assert!(matches!(
+            e,
+            IoError::Context { ref cell, element: Some("PATH"), ref source }
+                if cell.as_deref() == Some("WIRE")
+                    && matches!(**source, IoError::Truncated { needed: 20, available: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn lenient_mode_drops_damage_and_reports_it() {
+        fn be_i32(x: i32) -> [u8; 4] {
+            x.to_be_bytes()
+        }
+        fn square(bytes: &mut Vec<u8>) {
+            let xy: Vec<u8> = [0, 0, 10, 0, 10, 10, 0, 0]
+                .iter()
+                .flat_map(|&v| be_i32(v))
+                .collect();
+            bytes.extend(rec(RT_BOUNDARY, 0, &[]));
+            bytes.extend(rec(RT_XY, DT_INT4, &xy));
+            bytes.extend(rec(RT_ENDEL, 0, &[]));
+        }
+        let mut bytes = Vec::new();
+        bytes.extend(rec(0x00, DT_INT2, &[0x02, 0x58]));
+        bytes.extend(rec(RT_LIBNAME, DT_ASCII, b"LIB\0"));
+        bytes.extend(rec(RT_BGNSTR, DT_INT2, &[0; 24]));
+        bytes.extend(rec(RT_STRNAME, DT_ASCII, b"A\0"));
+        bytes.extend(rec(RT_BOUNDARY, 0, &[]));
+        let bad_xy = bytes.len() as u64;
+        bytes.extend(rec(RT_XY, DT_INT4, &[0; 12]));
+        bytes.extend(rec(RT_ENDEL, 0, &[]));
+        square(&mut bytes);
+        bytes.extend(rec(RT_ENDSTR, 0, &[]));
+        // a header with length 3, then bytes that do not form a record
+        let junk = bytes.len() as u64;
+        bytes.extend([0, 3, 0xFF, 0xFF, 1, 2]);
+        bytes.extend(rec(RT_BGNSTR, DT_INT2, &[0; 24]));
+        bytes.extend(rec(RT_STRNAME, DT_ASCII, b"A\0"));
+        square(&mut bytes);
+        // neither ENDSTR nor ENDLIB
+
+        assert!(read_gds_from(&bytes[..]).is_err());
+
+        let (summary, cells, diags) =
+            read_gds_with_from(&bytes[..], &ReadOptions { strict: false }).unwrap();
+        assert_eq!(summary.libname.as_deref(), Some("LIB"));
+        assert_eq!(cells.len(), 2);
+        assert!(cells.iter().all(|c| c.name == "A" && c.polys.len() == 1));
+        let kinds: Vec<_> = diags.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiagnosticKind::DroppedElement,
+                DiagnosticKind::BadRecord,
+                DiagnosticKind::UnclosedStructure,
+                DiagnosticKind::DuplicateCell,
+                DiagnosticKind::MissingEndlib,
+            ]
+        );
+        assert_eq!(diags[0].offset, bad_xy);
+        assert_eq!(diags[0].cell.as_deref(), Some("A"));
+        assert_eq!(diags[1].offset, junk);
+        assert_eq!(diags[4].offset, bytes.len() as u64);
+        assert!(diags[1]
+            .to_string()
+            .starts_with(&format!("offset {}", junk)));
+    }
+
+    #[test]
+    fn strict_mode_still_reports_warnings() {
+        let mut bytes = Vec::new();
+        for _ in 0..2 {
+            bytes.extend(rec(RT_BGNSTR, DT_INT2, &[0; 24]));
+            bytes.extend(rec(RT_STRNAME, DT_ASCII, b"A\0"));
+            bytes.extend(rec(RT_ENDSTR, 0, &[]));
+        }
+        let (_, cells, diags) = read_gds_with_from(&bytes[..], &ReadOptions::default()).unwrap();
+        assert_eq!(cells.len(), 2);
+        let kinds: Vec<_> = diags.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![DiagnosticKind::DuplicateCell, DiagnosticKind::MissingEndlib]
+        );
+    }
 }
@@ -0,0 +1,135 @@
+//! OASIS (SEMI P39) support: record ids, primitive encodings, reader and writer.
+
+mod reader;
+mod writer;
+
+pub use reader::{read_oas, read_oas_from};
+pub use writer::{write_oas, write_oas_to};
+
+/// Magic bytes every OASIS file starts with.
+pub const MAGIC: &[u8; 13] = b"%SEMI-OASIS\r\n";
+
+// OASIS record ids
+const RID_PAD: u8 = 0;
+const RID_START: u8 = 1;
+const RID_END: u8 = 2;
+const RID_CELLNAME: u8 = 3;
+const RID_CELLNAME_REF: u8 = 4;
+const RID_TEXTSTRING: u8 = 5;
+const RID_TEXTSTRING_REF: u8 = 6;
+const RID_PROPNAME: u8 = 7;
+const RID_PROPNAME_REF: u8 = 8;
+const RID_PROPSTRING: u8 = 9;
+const RID_PROPSTRING_REF: u8 = 10;
+const RID_LAYERNAME: u8 = 11;
+const RID_LAYERNAME_TEXT: u8 = 12;
+const RID_CELL_REF: u8 = 13;
+const RID_CELL_NAME: u8 = 14;
+const RID_XYABSOLUTE: u8 = 15;
+const RID_XYRELATIVE: u8 = 16;
+const RID_PLACEMENT: u8 = 17;
+const RID_PLACEMENT_MAG: u8 = 18;
+const RID_TEXT: u8 = 19;
+const RID_RECTANGLE: u8 = 20;
+const RID_POLYGON: u8 = 21;
+const RID_PATH: u8 = 22;
+const RID_TRAPEZOID: u8 = 23;
+const RID_TRAPEZOID_A: u8 = 24;
+const RID_TRAPEZOID_B: u8 = 25;
+const RID_CTRAPEZOID: u8 = 26;
+const RID_CIRCLE: u8 = 27;
+const RID_PROPERTY: u8 = 28;
+const RID_PROPERTY_REPEAT: u8 = 29;
+const RID_XNAME: u8 = 30;
+const RID_XNAME_REF: u8 = 31;
+const RID_XELEMENT: u8 = 32;
+const RID_XGEOMETRY: u8 = 33;
+const RID_CBLOCK: u8 = 34;
+
+/// The END record is always padded to exactly this many bytes.
+const END_RECORD_LEN: usize = 256;
+
+fn put_uint(out: &mut Vec<u8>, mut v: u64) {
+    loop {
+        let b = (v & 0x7F) as u8;
+        v >>= 7;
+        if v == 0 {
+            out.push(b);
+            return;
+        }
+        out.push(b | 0x80);
+    }
+}
+
+fn put_sint(out: &mut Vec<u8>, v: i64) {
+    // sign lives in bit 0, magnitude in the remaining bits
+    let mag = v.unsigned_abs();
+    put_uint(out, (mag << 1) | (v < 0) as u64);
+}
+
+fn put_real(out: &mut Vec<u8>, v: f64) {
+    if v.fract() == 0.0 && v.abs() < u64::MAX as f64 {
+        // type 0/1: positive/negative whole number
+        put_uint(out, (v < 0.0) as u64);
+        put_uint(out, v.abs() as u64);
+    } else {
+        // type 7: IEEE double, little endian
+        put_uint(out, 7);
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+fn put_string(out: &mut Vec<u8>, s: &[u8]) {
+    put_uint(out, s.len() as u64);
+    out.extend_from_slice(s);
+}
+
+/// g-delta (form 2): arbitrary-angle displacement.
+fn put_gdelta(out: &mut Vec<u8>, dx: i64, dy: i64) {
+    put_uint(out, (dx.unsigned_abs() << 2) | (((dx < 0) as u64) << 1) | 1);
+    put_sint(out, dy);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uint_varint() {
+        let mut v = Vec::new();
+        put_uint(&mut v, 0);
+        put_uint(&mut v, 127);
+        put_uint(&mut v, 128);
+        put_uint(&mut v, 16384);
+        assert_eq!(v, [0x00, 0x7F, 0x80, 0x01, 0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn sint_sign_bit() {
+        let mut v = Vec::new();
+        put_sint(&mut v, 0);
+        put_sint(&mut v, 1);
+        put_sint(&mut v, -1);
+        put_sint(&mut v, -64);
+        assert_eq!(v, [0x00, 0x02, 0x03, 0x81, 0x01]);
+    }
+
+    #[test]
+    fn real_forms() {
+        let mut v = Vec::new();
+        put_real(&mut v, 1000.0);
+        assert_eq!(v, [0x00, 0xE8, 0x07]);
+        v.clear();
+        put_real(&mut v, 0.5);
+        assert_eq!(v[0], 7);
+        assert_eq!(f64::from_le_bytes(v[1..9].try_into().unwrap()), 0.5);
+    }
+
+    #[test]
+    fn gdelta_form2() {
+        let mut v = Vec::new();
+        put_gdelta(&mut v, -3, 5);
+        // |dx|=3 → 3<<2 | sign(1)<<1 | 1 = 15; dy=5 → 10
+        assert_eq!(v, [15, 10]);
+    }
+}
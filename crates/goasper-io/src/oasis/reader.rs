@@ -0,0 +1,1736 @@
+use super::*;
+use crate::{
+    Anchor, CellArrayRef, CellPolygons, CellRef, CellSummary, GdsSummary, IoError, Label, Path,
+    Polygon, Strans, Units,
+};
+use std::{collections::HashMap, io::Read};
+
+/// Vertex count used to approximate CIRCLE records as polygons.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Most displacements a single repetition may describe; larger counts are taken for
+/// a corrupt file rather than expanded.
+const MAX_REPETITION: u64 = 1 << 24;
+
+/// Most extra elements the repetitions of one file may expand into together, so that
+/// a few bytes per record cannot add up to gigabytes.
+const MAX_EXPANDED: u64 = 1 << 26;
+
+/// Read an OASIS file into the same summary/polygon model `read_gds_summary` and
+/// `read_gds_polygons` produce. OASIS carries no library name, so `libname` is `None`;
+/// the unit (grid steps per micron) is converted into [`Units`].
+///
+/// RECTANGLE, POLYGON, TRAPEZOID, CTRAPEZOID and CIRCLE become polygons (circles are
+/// approximated), PATHs become [`Path`]s, repetitions are expanded (up to a limit on
+/// the elements they add per file). PLACEMENTs become
+/// [`CellRef`]s, or [`CellArrayRef`]s when their repetition is a regular lattice.
+/// TEXTs become [`Label`]s with the default anchor and no transformation.
+pub fn read_oas<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    read_oas_from(std::fs::File::open(path)?)
+}
+
+/// [`read_oas`] over any reader. OASIS name tables may follow their uses, so the
+/// whole (decompressed, if gzip/zstd/bzip2) stream is read into memory first.
+pub fn read_oas_from<R: Read>(r: R) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    let mut data = Vec::new();
+    crate::compress::decompress(r)?.read_to_end(&mut data)?;
+    parse(&data)
+}
+
+fn parse(data: &[u8]) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    parse_with_budget(data, MAX_EXPANDED)
+}
+
+/// `parse`, letting repetitions add at most `budget` elements.
+fn parse_with_budget(data: &[u8], budget: u64) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    if !data.starts_with(MAGIC) {
+        return Err(IoError::Oasis {
+            offset: 0,
+            msg: "missing %SEMI-OASIS magic".into(),
+        });
+    }
+    let mut st = State {
+        budget,
+        ..State::default()
+    };
+    let mut c = Cursor {
+        buf: data,
+        pos: MAGIC.len(),
+        base: 0,
+    };
+    st.run(&mut c)?;
+    if !st.ended {
+        return Err(c.err("missing END record"));
+    }
+
+    for &(ci, is_array, idx, n) in &st.pending_refs {
+        let name = st.cellnames.get(&n).cloned().ok_or(IoError::Oasis {
+            offset: 0,
+            msg: format!("PLACEMENT refers to undefined CELLNAME {}", n),
+        })?;
+        let cell = &mut st.cells[ci].1;
+        if is_array {
+            cell.arefs[idx].name = name;
+        } else {
+            cell.refs[idx].name = name;
+        }
+    }
+
+    for &(ci, idx, n) in &st.pending_labels {
+        let text = st.textstrings.get(&n).cloned().ok_or(IoError::Oasis {
+            offset: 0,
+            msg: format!("TEXT refers to undefined TEXTSTRING {}", n),
+        })?;
+        st.cells[ci].1.labels[idx].string = text;
+    }
+
+    let mut cells = Vec::with_capacity(st.cells.len());
+    for (key, mut cell) in st.cells {
+        if let CellKey::Ref(n) = key {
+            cell.name = st.cellnames.get(&n).cloned().ok_or(IoError::Oasis {
+                offset: 0,
+                msg: format!("CELL refers to undefined CELLNAME {}", n),
+            })?;
+        }
+        cells.push(cell);
+    }
+    let summary = GdsSummary {
+        libname: None,
+        units: st.unit.map(|u| Units {
+            user_units_per_dbu: 1.0 / u,
+            meters_per_dbu: 1e-6 / u,
+        }),
+        cells: cells.iter().map(CellSummary::from_polygons).collect(),
+    };
+    Ok((summary, cells))
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    /// Stream offset of `buf[0]` (differs from 0 inside CBLOCKs).
+    base: u64,
+}
+
+impl Cursor<'_> {
+    fn err(&self, msg: impl Into<String>) -> IoError {
+        IoError::Oasis {
+            offset: self.base + self.pos as u64,
+            msg: msg.into(),
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn truncated(&self, needed: usize) -> IoError {
+        IoError::Truncated {
+            offset: self.base + self.pos as u64,
+            rectype: None,
+            needed: needed as u64,
+            available: self.buf.len().saturating_sub(self.pos) as u64,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, IoError> {
+        let b = *self.buf.get(self.pos).ok_or_else(|| self.truncated(1))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&[u8], IoError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&e| e <= self.buf.len())
+            .ok_or_else(|| self.truncated(n))?;
+        let s = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(s)
+    }
+
+    fn uint(&mut self) -> Result<u64, IoError> {
+        let mut v: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            // the tenth byte has room for bit 63 only
+            if shift >= 64 || (shift == 63 && b & 0x7E != 0) {
+                return Err(self.err("unsigned-integer overflows 64 bits"));
+            }
+            v |= ((b & 0x7F) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+            shift += 7;
+        }
+    }
+
+    fn sint(&mut self) -> Result<i64, IoError> {
+        let u = self.uint()?;
+        let mag = (u >> 1) as i64;
+        Ok(if u & 1 != 0 { -mag } else { mag })
+    }
+
+    fn real(&mut self) -> Result<f64, IoError> {
+        let t = self.uint()?;
+        self.real_body(t)
+    }
+
+    fn real_body(&mut self, t: u64) -> Result<f64, IoError> {
+        Ok(match t {
+            0 => self.uint()? as f64,
+            1 => -(self.uint()? as f64),
+            2 => 1.0 / self.uint()? as f64,
+            3 => -1.0 / self.uint()? as f64,
+            4 => self.uint()? as f64 / self.uint()? as f64,
+            5 => -(self.uint()? as f64 / self.uint()? as f64),
+            6 => f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()) as f64,
+            7 => f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()),
+            _ => return Err(self.err(format!("invalid real type {}", t))),
+        })
+    }
+
+    fn string(&mut self) -> Result<String, IoError> {
+        let n = self.uint()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(n)?).into_owned())
+    }
+
+    /// 2-/3-delta and octangular g-delta directions: E N W S NE NW SW SE.
+    fn octangular(dir: u64, m: i64) -> (i64, i64) {
+        match dir {
+            0 => (m, 0),
+            1 => (0, m),
+            2 => (-m, 0),
+            3 => (0, -m),
+            4 => (m, m),
+            5 => (-m, m),
+            6 => (-m, -m),
+            _ => (m, -m),
+        }
+    }
+
+    fn gdelta(&mut self) -> Result<(i64, i64), IoError> {
+        let u = self.uint()?;
+        if u & 1 == 0 {
+            Ok(Self::octangular((u >> 1) & 7, (u >> 4) as i64))
+        } else {
+            let mag = (u >> 2) as i64;
+            let dx = if u & 2 != 0 { -mag } else { mag };
+            Ok((dx, self.sint()?))
+        }
+    }
+
+    /// Decode a point list into vertices relative to the start point (which is included
+    /// as the first entry). `polygon` adds the implicit vertex of Manhattan types 0/1.
+    fn point_list(&mut self, polygon: bool) -> Result<Vec<(i64, i64)>, IoError> {
+        let t = self.uint()?;
+        let n = self.uint()? as usize;
+        let mut pts = Vec::with_capacity(n.min(1 << 16) + 2);
+        let (mut x, mut y) = (0i64, 0i64);
+        pts.push((x, y));
+        match t {
+            0 | 1 => {
+                for i in 0..n {
+                    let d = self.sint()?;
+                    if (i % 2 == 0) == (t == 0) {
+                        x += d;
+                    } else {
+                        y += d;
+                    }
+                    pts.push((x, y));
+                }
+                if polygon {
+                    if n.is_multiple_of(2) == (t == 0) {
+                        pts.push((0, y));
+                    } else {
+                        pts.push((x, 0));
+                    }
+                }
+            }
+            2 | 3 => {
+                for _ in 0..n {
+                    let u = self.uint()?;
+                    let (dx, dy) = if t == 2 {
+                        Self::octangular(u & 3, (u >> 2) as i64)
+                    } else {
+                        Self::octangular(u & 7, (u >> 3) as i64)
+                    };
+                    x += dx;
+                    y += dy;
+                    pts.push((x, y));
+                }
+            }
+            4 | 5 => {
+                let (mut ddx, mut ddy) = (0i64, 0i64);
+                for _ in 0..n {
+                    let (dx, dy) = self.gdelta()?;
+                    if t == 5 {
+                        ddx += dx;
+                        ddy += dy;
+                    } else {
+                        (ddx, ddy) = (dx, dy);
+                    }
+                    x += ddx;
+                    y += ddy;
+                    pts.push((x, y));
+                }
+            }
+            _ => return Err(self.err(format!("invalid point-list type {}", t))),
+        }
+        Ok(pts)
+    }
+
+    /// Decode a repetition. Type 0 ("reuse previous") is resolved by the caller.
+    fn repetition(&mut self) -> Result<Option<Repetition>, IoError> {
+        let t = self.uint()?;
+        let rep = match t {
+            0 => return Ok(None),
+            1 => {
+                let (cols, rows) = (self.count(2)?, self.count(2)?);
+                let (sx, sy) = (self.uint()? as i64, self.uint()? as i64);
+                self.lattice(cols, rows, (sx, 0), (0, sy))?
+            }
+            2 | 3 => {
+                let n = self.count(2)?;
+                let s = self.uint()? as i64;
+                if t == 2 {
+                    self.lattice(n, 1, (s, 0), (0, 0))?
+                } else {
+                    self.lattice(1, n, (0, 0), (0, s))?
+                }
+            }
+            4..=7 => {
+                let n = self.count(1)?;
+                let grid = if t == 5 || t == 7 {
+                    self.uint()? as i64
+                } else {
+                    1
+                };
+                let mut acc = 0i64;
+                let mut out = vec![(0, 0)];
+                for _ in 0..n {
+                    acc = acc.saturating_add((self.uint()? as i64).saturating_mul(grid));
+                    out.push(if t <= 5 { (acc, 0) } else { (0, acc) });
+                }
+                Repetition::Explicit(out)
+            }
+            8 => {
+                let (n, m) = (self.count(2)?, self.count(2)?);
+                let (a, b) = (self.gdelta()?, self.gdelta()?);
+                self.lattice(n, m, a, b)?
+            }
+            9 => {
+                let n = self.count(2)?;
+                let d = self.gdelta()?;
+                self.lattice(n, 1, d, (0, 0))?
+            }
+            10 | 11 => {
+                let n = self.count(1)?;
+                let grid = if t == 11 { self.uint()? as i64 } else { 1 };
+                let (mut x, mut y) = (0i64, 0i64);
+                let mut out = vec![(0, 0)];
+                for _ in 0..n {
+                    let (dx, dy) = self.gdelta()?;
+                    x = x.saturating_add(dx.saturating_mul(grid));
+                    y = y.saturating_add(dy.saturating_mul(grid));
+                    out.push((x, y));
+                }
+                Repetition::Explicit(out)
+            }
+            _ => return Err(self.err(format!("invalid repetition type {}", t))),
+        };
+        Ok(Some(rep))
+    }
+
+    /// A repetition count stored as `count - plus`.
+    fn count(&mut self, plus: u64) -> Result<u64, IoError> {
+        self.uint()?
+            .checked_add(plus)
+            .filter(|&n| n <= MAX_REPETITION)
+            .ok_or_else(|| self.err("repetition count out of range"))
+    }
+
+    fn lattice(
+        &self,
+        cols: u64,
+        rows: u64,
+        col: (i64, i64),
+        row: (i64, i64),
+    ) -> Result<Repetition, IoError> {
+        if cols * rows > MAX_REPETITION {
+            return Err(self.err("repetition count out of range"));
+        }
+        Ok(Repetition::Lattice {
+            cols,
+            rows,
+            col,
+            row,
+        })
+    }
+
+    fn interval(&mut self) -> Result<(), IoError> {
+        match self.uint()? {
+            0 => {}
+            1..=3 => {
+                self.uint()?;
+            }
+            4 => {
+                self.uint()?;
+                self.uint()?;
+            }
+            t => return Err(self.err(format!("invalid interval type {}", t))),
+        }
+        Ok(())
+    }
+}
+
+enum CellKey {
+    Ref(u64),
+    Name,
+}
+
+/// A decoded repetition. The regular lattice types keep only their shape, so a large
+/// array costs nothing until its displacements are walked.
+#[derive(Clone)]
+enum Repetition {
+    /// `cols` x `rows` displacements `i * col + j * row`.
+    Lattice {
+        cols: u64,
+        rows: u64,
+        col: (i64, i64),
+        row: (i64, i64),
+    },
+    Explicit(Vec<(i64, i64)>),
+}
+
+impl Repetition {
+    fn single() -> Self {
+        Repetition::Explicit(vec![(0, 0)])
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            Repetition::Lattice { cols, rows, .. } => cols * rows,
+            Repetition::Explicit(v) => v.len() as u64,
+        }
+    }
+
+    /// Every displacement, row by row for a lattice.
+    fn offsets(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        match *self {
+            Repetition::Lattice {
+                cols,
+                rows,
+                col,
+                row,
+            } => Box::new((0..rows as i64).flat_map(move |j| {
+                (0..cols as i64).map(move |i| {
+                    let at =
+                        |c: i64, r: i64| i.saturating_mul(c).saturating_add(j.saturating_mul(r));
+                    (at(col.0, row.0), at(col.1, row.1))
+                })
+            })),
+            Repetition::Explicit(ref v) => Box::new(v.iter().copied()),
+        }
+    }
+}
+
+/// PLACEMENT cell or TEXT string, given by reference number (resolved at the end) or inline.
+#[derive(Clone)]
+enum NameRef {
+    Ref(u64),
+    Name(String),
+}
+
+/// Modal variables (spec section 10); reset at every CELL record.
+#[derive(Default)]
+struct Modal {
+    xy_relative: bool,
+    placement_x: i64,
+    placement_y: i64,
+    geometry_x: i64,
+    geometry_y: i64,
+    text_x: i64,
+    text_y: i64,
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text_string: Option<NameRef>,
+    placement_cell: Option<NameRef>,
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<(i64, i64)>>,
+    path_halfwidth: Option<u64>,
+    path_points: Option<Vec<(i64, i64)>>,
+    path_start_ext: Option<i64>,
+    path_end_ext: Option<i64>,
+    ctrapezoid_type: Option<u64>,
+    circle_radius: Option<u64>,
+    repetition: Option<Repetition>,
+}
+
+#[derive(Default)]
+struct State {
+    unit: Option<f64>,
+    offset_flag: u64,
+    ended: bool,
+    cellnames: HashMap<u64, String>,
+    next_cellname: u64,
+    textstrings: HashMap<u64, String>,
+    next_textstring: u64,
+    cells: Vec<(CellKey, CellPolygons)>,
+    /// Instances whose cell is a CELLNAME refnum: (cell index, is array, index, refnum).
+    pending_refs: Vec<(usize, bool, usize, u64)>,
+    /// Labels whose string is a TEXTSTRING refnum: (cell index, label index, refnum).
+    pending_labels: Vec<(usize, usize, u64)>,
+    modal: Modal,
+    /// Elements repetitions may still add, see [`MAX_EXPANDED`].
+    budget: u64,
+}
+
+fn undefined(c: &Cursor, what: &str) -> IoError {
+    c.err(format!("modal variable {} is undefined", what))
+}
+
+impl State {
+    fn run(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        while !c.at_end() && !self.ended {
+            let rid = c.byte()?;
+            self.record(rid, c)?;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, rid: u8, c: &mut Cursor) -> Result<(), IoError> {
+        match rid {
+            RID_PAD => {}
+            RID_START => {
+                let _version = c.string()?;
+                self.unit = Some(c.real()?);
+                self.offset_flag = c.uint()?;
+                if self.offset_flag == 0 {
+                    for _ in 0..12 {
+                        c.uint()?;
+                    }
+                }
+            }
+            RID_END => {
+                if self.offset_flag != 0 {
+                    for _ in 0..12 {
+                        c.uint()?;
+                    }
+                }
+                let _padding = c.string()?;
+                if c.uint()? != 0 {
+                    c.bytes(4)?; // validation signature
+                }
+                self.ended = true;
+            }
+            RID_CELLNAME => {
+                let name = c.string()?;
+                self.cellnames.insert(self.next_cellname, name);
+                self.next_cellname += 1;
+            }
+            RID_CELLNAME_REF => {
+                let name = c.string()?;
+                let n = c.uint()?;
+                self.cellnames.insert(n, name);
+            }
+            RID_TEXTSTRING => {
+                let s = c.string()?;
+                self.textstrings.insert(self.next_textstring, s);
+                self.next_textstring += 1;
+            }
+            RID_TEXTSTRING_REF => {
+                let s = c.string()?;
+                let n = c.uint()?;
+                self.textstrings.insert(n, s);
+            }
+            RID_PROPNAME | RID_PROPSTRING => {
+                c.string()?;
+            }
+            RID_PROPNAME_REF | RID_PROPSTRING_REF => {
+                c.string()?;
+                c.uint()?;
+            }
+            RID_LAYERNAME | RID_LAYERNAME_TEXT => {
+                c.string()?;
+                c.interval()?;
+                c.interval()?;
+            }
+            RID_CELL_REF | RID_CELL_NAME => {
+                let (key, name) = if rid == RID_CELL_REF {
+                    (CellKey::Ref(c.uint()?), String::new())
+                } else {
+                    (CellKey::Name, c.string()?)
+                };
+                self.cells.push((
+                    key,
+                    CellPolygons {
+                        name,
+                        ..Default::default()
+                    },
+                ));
+                self.modal = Modal::default();
+            }
+            RID_XYABSOLUTE => self.modal.xy_relative = false,
+            RID_XYRELATIVE => self.modal.xy_relative = true,
+            RID_PLACEMENT | RID_PLACEMENT_MAG => self.placement(rid, c)?,
+            RID_TEXT => self.text(c)?,
+            RID_RECTANGLE => self.rectangle(c)?,
+            RID_POLYGON => self.polygon(c)?,
+            RID_PATH => self.path(c)?,
+            RID_TRAPEZOID | RID_TRAPEZOID_A | RID_TRAPEZOID_B => self.trapezoid(rid, c)?,
+            RID_CTRAPEZOID => self.ctrapezoid(c)?,
+            RID_CIRCLE => self.circle(c)?,
+            RID_PROPERTY => self.property(c)?,
+            RID_PROPERTY_REPEAT => {}
+            RID_XNAME | RID_XELEMENT => {
+                c.uint()?;
+                c.string()?;
+            }
+            RID_XNAME_REF => {
+                c.uint()?;
+                c.string()?;
+                c.uint()?;
+            }
+            RID_XGEOMETRY => {
+                // info, attribute, [layer], [datatype], fdata, [x], [y], [repetition]
+                let info = c.byte()?;
+                c.uint()?;
+                self.layer_datatype(info, c)?;
+                c.string()?;
+                self.geometry_xy(info, c)?;
+                self.repetition(info & 0x04 != 0, c)?;
+            }
+            RID_CBLOCK => {
+                let comp_type = c.uint()?;
+                if comp_type != 0 {
+                    return Err(IoError::Unsupported {
+                        offset: c.base + c.pos as u64,
+                        what: format!("CBLOCK compression type {}", comp_type),
+                    });
+                }
+                let raw_len = c.uint()?;
+                let comp_len = c.uint()? as usize;
+                let base = c.base + c.pos as u64;
+                let comp = c.bytes(comp_len)?;
+                // the header's size is not trusted with an allocation; one byte past
+                // it is enough to tell that the block is longer
+                let mut raw = Vec::new();
+                flate2::read::DeflateDecoder::new(comp)
+                    .take(raw_len.saturating_add(1))
+                    .read_to_end(&mut raw)?;
+                if raw.len() as u64 != raw_len {
+                    return Err(c.err("CBLOCK size does not match its header"));
+                }
+                let mut inner = Cursor {
+                    buf: &raw,
+                    pos: 0,
+                    base,
+                };
+                self.run(&mut inner)?;
+            }
+            _ => return Err(c.err(format!("unknown record id {}", rid))),
+        }
+        Ok(())
+    }
+
+    fn cur_cell(&mut self, c: &Cursor) -> Result<&mut CellPolygons, IoError> {
+        match self.cells.last_mut() {
+            Some((_, cell)) => Ok(cell),
+            None => Err(c.err("element outside of CELL")),
+        }
+    }
+
+    /// Apply an x or y field according to the current xy-mode.
+    fn coord(relative: bool, modal: &mut i64, c: &mut Cursor) -> Result<(), IoError> {
+        let v = c.sint()?;
+        if relative {
+            *modal += v;
+        } else {
+            *modal = v;
+        }
+        Ok(())
+    }
+
+    fn geometry_xy(&mut self, info: u8, c: &mut Cursor) -> Result<(i64, i64), IoError> {
+        let m = &mut self.modal;
+        if info & 0x10 != 0 {
+            Self::coord(m.xy_relative, &mut m.geometry_x, c)?;
+        }
+        if info & 0x08 != 0 {
+            Self::coord(m.xy_relative, &mut m.geometry_y, c)?;
+        }
+        Ok((m.geometry_x, m.geometry_y))
+    }
+
+    fn layer_datatype(&mut self, info: u8, c: &mut Cursor) -> Result<(u16, u16), IoError> {
+        if info & 0x01 != 0 {
+            self.modal.layer = Some(c.uint()?);
+        }
+        if info & 0x02 != 0 {
+            self.modal.datatype = Some(c.uint()?);
+        }
+        let layer = self.modal.layer.ok_or_else(|| undefined(c, "layer"))?;
+        let datatype = self
+            .modal
+            .datatype
+            .ok_or_else(|| undefined(c, "datatype"))?;
+        let to_u16 = |v: u64| {
+            u16::try_from(v).map_err(|_| c.err(format!("layer/datatype {} exceeds 16 bits", v)))
+        };
+        Ok((to_u16(layer)?, to_u16(datatype)?))
+    }
+
+    /// Read the repetition if its info bit is set; a type-0 repetition reuses the modal one.
+    fn repetition(&mut self, present: bool, c: &mut Cursor) -> Result<Repetition, IoError> {
+        if !present {
+            return Ok(Repetition::single());
+        }
+        if let Some(rep) = c.repetition()? {
+            self.modal.repetition = Some(rep);
+        }
+        self.modal
+            .repetition
+            .clone()
+            .ok_or_else(|| undefined(c, "repetition"))
+    }
+
+    /// Take the copies `reps` adds beyond the first out of the budget, before any
+    /// are made.
+    fn expand(&mut self, c: &Cursor, reps: &Repetition) -> Result<(), IoError> {
+        let extra = reps.len().saturating_sub(1);
+        self.budget = self
+            .budget
+            .checked_sub(extra)
+            .ok_or_else(|| c.err("repetitions expand past the per-file element limit"))?;
+        Ok(())
+    }
+
+    /// Push `outline` (relative to `origin`) once per repetition displacement.
+    fn emit(
+        &mut self,
+        c: &Cursor,
+        layer: u16,
+        datatype: u16,
+        origin: (i64, i64),
+        outline: &[(i64, i64)],
+        reps: &Repetition,
+    ) -> Result<(), IoError> {
+        let mut pts = outline.to_vec();
+        if pts.len() >= 2 && pts.first() == pts.last() {
+            pts.pop();
+        }
+        if pts.is_empty() {
+            return Ok(());
+        }
+        self.expand(c, reps)?;
+        let cell = self.cur_cell(c)?;
+        for (rx, ry) in reps.offsets() {
+            let xy = to_i32_points(c, offset(origin, (rx, ry)), &pts)?;
+            cell.polys.push(Polygon {
+                layer,
+                datatype,
+                xy,
+            });
+        }
+        Ok(())
+    }
+
+    fn placement(&mut self, rid: u8, c: &mut Cursor) -> Result<(), IoError> {
+        // 17: C N X Y R A A F, 18: C N X Y R M A F
+        let info = c.byte()?;
+        if info & 0x80 != 0 {
+            self.modal.placement_cell = Some(if info & 0x40 != 0 {
+                NameRef::Ref(c.uint()?)
+            } else {
+                NameRef::Name(c.string()?)
+            });
+        }
+        let cell = self
+            .modal
+            .placement_cell
+            .clone()
+            .ok_or_else(|| undefined(c, "placement-cell"))?;
+        let mut strans = Strans {
+            reflect_x: info & 0x01 != 0,
+            ..Default::default()
+        };
+        if rid == RID_PLACEMENT_MAG {
+            if info & 0x04 != 0 {
+                strans.mag = c.real()?;
+            }
+            if info & 0x02 != 0 {
+                strans.angle = c.real()?;
+            }
+        } else {
+            strans.angle = 90.0 * ((info >> 1) & 3) as f64;
+        }
+        let m = &mut self.modal;
+        if info & 0x20 != 0 {
+            Self::coord(m.xy_relative, &mut m.placement_x, c)?;
+        }
+        if info & 0x10 != 0 {
+            Self::coord(m.xy_relative, &mut m.placement_y, c)?;
+        }
+        let origin = (m.placement_x, m.placement_y);
+        let rep = self.repetition(info & 0x08 != 0, c)?;
+
+        let (name, refnum) = match cell {
+            NameRef::Ref(n) => (String::new(), Some(n)),
+            NameRef::Name(s) => (s, None),
+        };
+        let cell_idx = self.cells.len().wrapping_sub(1);
+        let to_i32 = |(x, y): (i64, i64)| match (i32::try_from(x), i32::try_from(y)) {
+            (Ok(x), Ok(y)) => Ok((x, y)),
+            _ => Err(c.err(format!("coordinate ({}, {}) exceeds 32 bits", x, y))),
+        };
+        let lattice = match rep {
+            Repetition::Lattice {
+                cols,
+                rows,
+                col,
+                row,
+            } => u16::try_from(cols)
+                .ok()
+                .zip(u16::try_from(rows).ok())
+                .map(|(n, m)| (n, m, col, row)),
+            Repetition::Explicit(_) => None,
+        };
+        if let Some((n, m, a, b)) = lattice {
+            let corner = |k: u16, d: (i64, i64)| {
+                let k = k as i64;
+                offset(origin, (k.saturating_mul(d.0), k.saturating_mul(d.1)))
+            };
+            let aref = CellArrayRef {
+                name,
+                cols: n,
+                rows: m,
+                origin: to_i32(origin)?,
+                col_corner: to_i32(corner(n, a))?,
+                row_corner: to_i32(corner(m, b))?,
+                strans,
+            };
+            let arefs = &mut self.cur_cell(c)?.arefs;
+            arefs.push(aref);
+            if let Some(n) = refnum {
+                let idx = arefs.len() - 1;
+                self.pending_refs.push((cell_idx, true, idx, n));
+            }
+        } else {
+            self.expand(c, &rep)?;
+            for d in rep.offsets() {
+                let r = CellRef {
+                    name: name.clone(),
+                    origin: to_i32(offset(origin, d))?,
+                    strans,
+                };
+                let refs = &mut self.cur_cell(c)?.refs;
+                refs.push(r);
+                if let Some(n) = refnum {
+                    let idx = refs.len() - 1;
+                    self.pending_refs.push((cell_idx, false, idx, n));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // 0 C N X Y R T L
+        let info = c.byte()?;
+        if info & 0x40 != 0 {
+            self.modal.text_string = Some(if info & 0x20 != 0 {
+                NameRef::Ref(c.uint()?)
+            } else {
+                NameRef::Name(c.string()?)
+            });
+        }
+        let string = self
+            .modal
+            .text_string
+            .clone()
+            .ok_or_else(|| undefined(c, "text-string"))?;
+        if info & 0x01 != 0 {
+            self.modal.textlayer = Some(c.uint()?);
+        }
+        if info & 0x02 != 0 {
+            self.modal.texttype = Some(c.uint()?);
+        }
+        let layer = self
+            .modal
+            .textlayer
+            .ok_or_else(|| undefined(c, "textlayer"))?;
+        let texttype = self
+            .modal
+            .texttype
+            .ok_or_else(|| undefined(c, "texttype"))?;
+        let to_u16 = |v: u64| {
+            u16::try_from(v).map_err(|_| c.err(format!("textlayer/texttype {} exceeds 16 bits", v)))
+        };
+        let (layer, texttype) = (to_u16(layer)?, to_u16(texttype)?);
+        let m = &mut self.modal;
+        if info & 0x10 != 0 {
+            Self::coord(m.xy_relative, &mut m.text_x, c)?;
+        }
+        if info & 0x08 != 0 {
+            Self::coord(m.xy_relative, &mut m.text_y, c)?;
+        }
+        let (x, y) = (m.text_x, m.text_y);
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+
+        let (text, refnum) = match string {
+            NameRef::Ref(n) => (String::new(), Some(n)),
+            NameRef::Name(s) => (s, None),
+        };
+        let cell_idx = self.cells.len().wrapping_sub(1);
+        self.expand(c, &reps)?;
+        for (rx, ry) in reps.offsets() {
+            let position = to_i32_points(c, (x, y), &[(rx, ry)])?[0];
+            let label = Label {
+                layer,
+                texttype,
+                string: text.clone(),
+                position,
+                anchor: Anchor::default(),
+                reflect_x: false,
+                magnification: 1.0,
+                rotation: 0.0,
+            };
+            let labels = &mut self.cur_cell(c)?.labels;
+            labels.push(label);
+            if let Some(n) = refnum {
+                let idx = labels.len() - 1;
+                self.pending_labels.push((cell_idx, idx, n));
+            }
+        }
+        Ok(())
+    }
+
+    fn rectangle(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // S W H X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(c.uint()?);
+        }
+        if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(c.uint()?);
+        }
+        let w = self
+            .modal
+            .geometry_w
+            .ok_or_else(|| undefined(c, "geometry-w"))? as i64;
+        let h = if info & 0x80 != 0 {
+            self.modal.geometry_h = Some(w as u64);
+            w
+        } else {
+            self.modal
+                .geometry_h
+                .ok_or_else(|| undefined(c, "geometry-h"))? as i64
+        };
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        let outline = [(0, 0), (w, 0), (w, h), (0, h)];
+        self.emit(c, layer, datatype, origin, &outline, &reps)
+    }
+
+    fn polygon(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // 0 0 P X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x20 != 0 {
+            self.modal.polygon_points = Some(c.point_list(true)?);
+        }
+        let pts = self
+            .modal
+            .polygon_points
+            .clone()
+            .ok_or_else(|| undefined(c, "polygon-point-list"))?;
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        self.emit(c, layer, datatype, origin, &pts, &reps)
+    }
+
+    fn path(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // E W P X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x40 != 0 {
+            self.modal.path_halfwidth = Some(c.uint()?);
+        }
+        let hw = self
+            .modal
+            .path_halfwidth
+            .ok_or_else(|| undefined(c, "path-halfwidth"))? as i64;
+        if info & 0x80 != 0 {
+            let scheme = c.uint()?;
+            // 0000 SS EE: 0 = modal, 1 = flush, 2 = half-width, 3 = explicit
+            for (shift, slot) in [(2, 0), (0, 1)] {
+                let ext = match (scheme >> shift) & 3 {
+                    0 => continue,
+                    1 => 0,
+                    2 => hw,
+                    _ => c.sint()?,
+                };
+                if slot == 0 {
+                    self.modal.path_start_ext = Some(ext);
+                } else {
+                    self.modal.path_end_ext = Some(ext);
+                }
+            }
+        }
+        if info & 0x20 != 0 {
+            self.modal.path_points = Some(c.point_list(false)?);
+        }
+        let pts = self
+            .modal
+            .path_points
+            .clone()
+            .ok_or_else(|| undefined(c, "path-point-list"))?;
+        let ext_start = self
+            .modal
+            .path_start_ext
+            .ok_or_else(|| undefined(c, "path-start-extension"))?;
+        let ext_end = self
+            .modal
+            .path_end_ext
+            .ok_or_else(|| undefined(c, "path-end-extension"))?;
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        let pathtype = if ext_start == 0 && ext_end == 0 {
+            0
+        } else if ext_start == hw && ext_end == hw {
+            2
+        } else {
+            4
+        };
+        let width = i32::try_from(2 * hw).map_err(|_| c.err("path width exceeds 32 bits"))?;
+        let ext = |v: i64| i32::try_from(v).map_err(|_| c.err("path extension exceeds 32 bits"));
+        let (bgnextn, endextn) = if pathtype == 4 {
+            (ext(ext_start)?, ext(ext_end)?)
+        } else {
+            (0, 0)
+        };
+        self.expand(c, &reps)?;
+        for (rx, ry) in reps.offsets() {
+            let xy = to_i32_points(c, offset(origin, (rx, ry)), &pts)?;
+            self.cur_cell(c)?.paths.push(Path {
+                layer,
+                datatype,
+                width,
+                pathtype,
+                bgnextn,
+                endextn,
+                xy,
+            });
+        }
+        Ok(())
+    }
+
+    fn trapezoid(&mut self, rid: u8, c: &mut Cursor) -> Result<(), IoError> {
+        // O W H X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(c.uint()?);
+        }
+        if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(c.uint()?);
+        }
+        let w = self
+            .modal
+            .geometry_w
+            .ok_or_else(|| undefined(c, "geometry-w"))? as i64;
+        let h = self
+            .modal
+            .geometry_h
+            .ok_or_else(|| undefined(c, "geometry-h"))? as i64;
+        let a = if rid != RID_TRAPEZOID_B { c.sint()? } else { 0 };
+        let b = if rid != RID_TRAPEZOID_A { c.sint()? } else { 0 };
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        let outline = if info & 0x80 != 0 {
+            // vertical: delta-a moves the bottom edge ends, delta-b the top edge ends
+            [
+                (0, a.max(0)),
+                (0, h + b.min(0)),
+                (w, h - b.max(0)),
+                (w, -a.min(0)),
+            ]
+        } else {
+            // horizontal: delta-a moves the left edge ends, delta-b the right edge ends
+            [
+                (-a.min(0), 0),
+                (a.max(0), h),
+                (w + b.min(0), h),
+                (w - b.max(0), 0),
+            ]
+        };
+        self.emit(c, layer, datatype, origin, &outline, &reps)
+    }
+
+    fn ctrapezoid(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // T W H X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x80 != 0 {
+            self.modal.ctrapezoid_type = Some(c.uint()?);
+        }
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(c.uint()?);
+        }
+        if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(c.uint()?);
+        }
+        let t = self
+            .modal
+            .ctrapezoid_type
+            .ok_or_else(|| undefined(c, "ctrapezoid-type"))?;
+        // some types derive one dimension from the other
+        let (w, h) = match t {
+            16..=19 | 25 => {
+                let w = self
+                    .modal
+                    .geometry_w
+                    .ok_or_else(|| undefined(c, "geometry-w"))?;
+                (w, w)
+            }
+            20 | 21 => {
+                let h = self
+                    .modal
+                    .geometry_h
+                    .ok_or_else(|| undefined(c, "geometry-h"))?;
+                (2 * h, h)
+            }
+            22 | 23 => {
+                let w = self
+                    .modal
+                    .geometry_w
+                    .ok_or_else(|| undefined(c, "geometry-w"))?;
+                (w, 2 * w)
+            }
+            _ => (
+                self.modal
+                    .geometry_w
+                    .ok_or_else(|| undefined(c, "geometry-w"))?,
+                self.modal
+                    .geometry_h
+                    .ok_or_else(|| undefined(c, "geometry-h"))?,
+            ),
+        };
+        let outline = ctrapezoid_outline(t, w as i64, h as i64)
+            .ok_or_else(|| c.err(format!("invalid ctrapezoid type {}", t)))?;
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        self.emit(c, layer, datatype, origin, &outline, &reps)
+    }
+
+    fn circle(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // 0 0 r X Y R D L
+        let info = c.byte()?;
+        let (layer, datatype) = self.layer_datatype(info, c)?;
+        if info & 0x20 != 0 {
+            self.modal.circle_radius = Some(c.uint()?);
+        }
+        let r = self
+            .modal
+            .circle_radius
+            .ok_or_else(|| undefined(c, "circle-radius"))? as f64;
+        let origin = self.geometry_xy(info, c)?;
+        let reps = self.repetition(info & 0x04 != 0, c)?;
+        let outline: Vec<(i64, i64)> = (0..CIRCLE_SEGMENTS)
+            .map(|i| {
+                let a = std::f64::consts::TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+                ((r * a.cos()).round() as i64, (r * a.sin()).round() as i64)
+            })
+            .collect();
+        self.emit(c, layer, datatype, origin, &outline, &reps)
+    }
+
+    fn property(&mut self, c: &mut Cursor) -> Result<(), IoError> {
+        // U U U U V C N S
+        let info = c.byte()?;
+        if info & 0x04 != 0 {
+            if info & 0x02 != 0 {
+                c.uint()?;
+            } else {
+                c.string()?;
+            }
+        }
+        if info & 0x08 != 0 {
+            return Ok(()); // reuses the modal value list
+        }
+        let n = match info >> 4 {
+            15 => c.uint()?,
+            n => n as u64,
+        };
+        for _ in 0..n {
+            match c.uint()? {
+                t @ 0..=7 => {
+                    c.real_body(t)?;
+                }
+                8 | 9 | 13..=15 => {
+                    c.uint()?;
+                }
+                10..=12 => {
+                    c.string()?;
+                }
+                t => return Err(c.err(format!("invalid property value type {}", t))),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Vertices of the 26 predefined CTRAPEZOID shapes (spec table 7-2) in a w×h box.
+fn ctrapezoid_outline(t: u64, w: i64, h: i64) -> Option<Vec<(i64, i64)>> {
+    Some(match t {
+        0 => vec![(0, 0), (0, h), (w - h, h), (w, 0)],
+        1 => vec![(0, 0), (0, h), (w, h), (w - h, 0)],
+        2 => vec![(0, 0), (h, h), (w, h), (w, 0)],
+        3 => vec![(h, 0), (0, h), (w, h), (w, 0)],
+        4 => vec![(0, 0), (h, h), (w - h, h), (w, 0)],
+        5 => vec![(h, 0), (0, h), (w, h), (w - h, 0)],
+        6 => vec![(0, 0), (h, h), (w, h), (w - h, 0)],
+        7 => vec![(h, 0), (0, h), (w - h, h), (w, 0)],
+        8 => vec![(0, 0), (0, h), (w, h - w), (w, 0)],
+        9 => vec![(0, 0), (0, h - w), (w, h), (w, 0)],
+        10 => vec![(0, 0), (0, h), (w, h), (w, w)],
+        11 => vec![(0, w), (0, h), (w, h), (w, 0)],
+        12 => vec![(0, 0), (0, h), (w, h - w), (w, w)],
+        13 => vec![(0, w), (0, h - w), (w, h), (w, 0)],
+        14 => vec![(0, 0), (0, h - w), (w, h), (w, w)],
+        15 => vec![(0, w), (0, h), (w, h - w), (w, 0)],
+        16 => vec![(0, 0), (0, w), (w, 0)],
+        17 => vec![(0, 0), (0, w), (w, w)],
+        18 => vec![(0, 0), (w, w), (w, 0)],
+        19 => vec![(0, w), (w, w), (w, 0)],
+        20 => vec![(0, 0), (h, h), (2 * h, 0)],
+        21 => vec![(0, h), (2 * h, h), (h, 0)],
+        22 => vec![(0, 0), (0, 2 * w), (w, w)],
+        23 => vec![(w, 0), (0, w), (w, 2 * w)],
+        24 | 25 => vec![(0, 0), (0, h), (w, h), (w, 0)],
+        _ => return None,
+    })
+}
+
+/// Translate relative points by `origin` and narrow them to DBU coordinates.
+/// `p` moved by `d`, saturating: a corrupt file ends in a range error, not a panic.
+fn offset(p: (i64, i64), d: (i64, i64)) -> (i64, i64) {
+    (p.0.saturating_add(d.0), p.1.saturating_add(d.1))
+}
+
+fn to_i32_points(
+    c: &Cursor,
+    origin: (i64, i64),
+    pts: &[(i64, i64)],
+) -> Result<Vec<(i32, i32)>, IoError> {
+    pts.iter()
+        .map(|&(x, y)| {
+            let (x, y) = offset(origin, (x, y));
+            match (i32::try_from(x), i32::try_from(y)) {
+                (Ok(x), Ok(y)) => Ok((x, y)),
+                _ => Err(c.err(format!("coordinate ({}, {}) exceeds 32 bits", x, y))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oasis::write_oas_to;
+
+    fn header(unit: f64) -> Vec<u8> {
+        let mut b = MAGIC.to_vec();
+        b.push(RID_START);
+        put_string(&mut b, b"1.0");
+        put_real(&mut b, unit);
+        put_uint(&mut b, 0);
+        for _ in 0..12 {
+            put_uint(&mut b, 0);
+        }
+        b
+    }
+
+    fn end(b: &mut Vec<u8>) {
+        b.push(RID_END);
+        put_string(b, &[]);
+        put_uint(b, 0);
+    }
+
+    #[test]
+    fn roundtrip_with_writer() {
+        let cells = vec![CellPolygons {
+            name: "TOP".into(),
+            polys: vec![
+                Polygon {
+                    layer: 1,
+                    datatype: 0,
+                    xy: vec![(0, 0), (10, 0), (10, 5), (0, 5)],
+                },
+                Polygon {
+                    layer: 2,
+                    datatype: 3,
+                    xy: vec![(-4, 0), (10, 0), (5, 7)],
+                },
+            ],
+            paths: vec![Path {
+                layer: 4,
+                datatype: 0,
+                width: 6,
+                pathtype: 2,
+                bgnextn: 0,
+                endextn: 0,
+                xy: vec![(0, 0), (0, 40), (25, 40)],
+            }],
+            ..Default::default()
+        }];
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &cells).unwrap();
+        let (s, back) = parse(&buf).unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].name, "TOP");
+        assert_eq!(back[0].polys[0].xy, vec![(0, 0), (10, 0), (10, 5), (0, 5)]);
+        assert_eq!(back[0].polys[1].layer, 2);
+        assert_eq!(back[0].polys[1].datatype, 3);
+        assert_eq!(back[0].polys[1].xy, vec![(-4, 0), (10, 0), (5, 7)]);
+        let p = &back[0].paths[0];
+        assert_eq!((p.layer, p.width, p.pathtype), (4, 6, 2));
+        assert_eq!(p.xy, vec![(0, 0), (0, 40), (25, 40)]);
+        assert!((s.units.unwrap().meters_per_dbu - 1e-9).abs() < 1e-18);
+        assert_eq!(s.cells[0].total_polys, 2);
+        assert_eq!(s.cells[0].layer_poly_counts.get(&(1, 0)), Some(&1));
+    }
+
+    #[test]
+    fn placements_roundtrip_with_writer() {
+        let rot = Strans {
+            reflect_x: true,
+            angle: 270.0,
+            ..Default::default()
+        };
+        let scaled = Strans {
+            mag: 2.5,
+            angle: 30.0,
+            ..Default::default()
+        };
+        let cells = vec![CellPolygons {
+            name: "TOP".into(),
+            refs: vec![
+                CellRef {
+                    name: "LEAF".into(),
+                    origin: (5, -7),
+                    strans: rot,
+                },
+                CellRef {
+                    name: "TOP2".into(),
+                    origin: (0, 0),
+                    strans: scaled,
+                },
+            ],
+            arefs: vec![
+                CellArrayRef {
+                    name: "LEAF".into(),
+                    cols: 3,
+                    rows: 2,
+                    origin: (10, 10),
+                    col_corner: (40, 10),
+                    row_corner: (10, 50),
+                    strans: Strans::default(),
+                },
+                CellArrayRef {
+                    name: "LEAF".into(),
+                    cols: 3,
+                    rows: 1,
+                    origin: (0, 0),
+                    col_corner: (10, 0),
+                    row_corner: (0, 1),
+                    strans: Strans::default(),
+                },
+            ],
+            ..Default::default()
+        }];
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &cells).unwrap();
+        let (_, back) = parse(&buf).unwrap();
+        let top = &back[0];
+        assert_eq!(top.refs.len(), 2 + 3);
+        assert_eq!(top.refs[0].name, "LEAF");
+        assert_eq!(top.refs[0].origin, (5, -7));
+        assert_eq!(top.refs[0].strans, rot);
+        assert_eq!(top.refs[1].name, "TOP2");
+        assert_eq!(top.refs[1].strans, scaled);
+        // a pitch that does not divide the corner falls back to explicit offsets
+        let expanded: Vec<_> = top.refs[2..].iter().map(|r| r.origin).collect();
+        assert_eq!(expanded, vec![(0, 0), (3, 0), (6, 0)]);
+        assert_eq!(top.arefs.len(), 1);
+        let a = &top.arefs[0];
+        assert_eq!((a.cols, a.rows), (3, 2));
+        assert_eq!(
+            (a.origin, a.col_corner, a.row_corner),
+            ((10, 10), (40, 10), (10, 50))
+        );
+    }
+
+    #[test]
+    fn text_with_inline_and_referenced_strings() {
+        let cells = vec![CellPolygons {
+            name: "TOP".into(),
+            labels: vec![Label {
+                layer: 7,
+                texttype: 1,
+                string: "VDD".into(),
+                position: (-5, 12),
+                anchor: Anchor::TopLeft,
+                reflect_x: false,
+                magnification: 1.0,
+                rotation: 0.0,
+            }],
+            ..Default::default()
+        }];
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &cells).unwrap();
+        let (_, back) = parse(&buf).unwrap();
+        assert_eq!(back[0].labels, cells[0].labels);
+
+        let mut b = header(1000.0);
+        b.push(RID_CELL_NAME);
+        put_string(&mut b, b"TOP");
+        // TEXT by TEXTSTRING refnum, defined after use; second TEXT reuses it modally
+        b.push(RID_TEXT);
+        b.push(0x40 | 0x20 | 0x10 | 0x08 | 0x02 | 0x01);
+        put_uint(&mut b, 0);
+        put_uint(&mut b, 3);
+        put_uint(&mut b, 0);
+        put_sint(&mut b, 1);
+        put_sint(&mut b, 2);
+        b.push(RID_TEXT);
+        b.push(0x10);
+        put_sint(&mut b, 9);
+        b.push(RID_TEXTSTRING);
+        put_string(&mut b, b"OUT");
+        end(&mut b);
+        let (_, back) = parse(&b).unwrap();
+        let l = &back[0].labels;
+        assert_eq!(l.len(), 2);
+        assert_eq!(
+            (l[0].string.as_str(), l[0].layer, l[0].position),
+            ("OUT", 3, (1, 2))
+        );
+        assert_eq!((l[1].string.as_str(), l[1].position), ("OUT", (9, 2)));
+
+        let mut b = header(1000.0);
+        b.push(RID_CELL_NAME);
+        put_string(&mut b, b"TOP");
+        b.push(RID_TEXT);
+        b.push(0x40 | 0x02 | 0x01);
+        put_string(&mut b, b"BIG");
+        put_uint(&mut b, 70000);
+        put_uint(&mut b, 0);
+        end(&mut b);
+        match parse(&b) {
+            Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("exceeds 16 bits")),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn modal_rectangle_with_repetition_and_forward_cellname() {
+        let mut b = header(1000.0);
+        // CELL by refnum before its CELLNAME
+        b.push(RID_CELL_REF);
+        put_uint(&mut b, 0);
+        // square 4x4 on 5/1 at (10,20), repeated 3 times along x with pitch 10
+        b.push(RID_RECTANGLE);
+        b.push(0x80 | 0x40 | 0x10 | 0x08 | 0x04 | 0x02 | 0x01);
+        put_uint(&mut b, 5);
+        put_uint(&mut b, 1);
+        put_uint(&mut b, 4);
+        put_sint(&mut b, 10);
+        put_sint(&mut b, 20);
+        put_uint(&mut b, 2);
+        put_uint(&mut b, 1);
+        put_uint(&mut b, 10);
+        // relative mode: next rectangle 100 further in x, reusing modal w/h/layer
+        b.push(RID_XYRELATIVE);
+        b.push(RID_RECTANGLE);
+        b.push(0x10);
+        put_sint(&mut b, 100);
+        b.push(RID_CELLNAME);
+        put_string(&mut b, b"A");
+        end(&mut b);
+        let (_, cells) = parse(&b).unwrap();
+        assert_eq!(cells[0].name, "A");
+        let p = &cells[0].polys;
+        assert_eq!(p.len(), 4);
+        assert_eq!(p[0].xy, vec![(10, 20), (14, 20), (14, 24), (10, 24)]);
+        assert_eq!(p[2].xy[0], (30, 20));
+        assert_eq!(p[3].xy[0], (110, 20));
+        assert_eq!((p[3].layer, p[3].datatype), (5, 1));
+    }
+
+    #[test]
+    fn xgeometry_is_skipped_and_sets_modal_layer_and_position() {
+        let mut b = header(1000.0);
+        b.push(RID_CELLNAME);
+        put_string(&mut b, b"A");
+        b.push(RID_CELL_REF);
+        put_uint(&mut b, 0);
+        // attribute 7 on 3/4 at (10, 20)
+        b.push(RID_XGEOMETRY);
+        b.push(0x10 | 0x08 | 0x02 | 0x01);
+        put_uint(&mut b, 7);
+        put_uint(&mut b, 3);
+        put_uint(&mut b, 4);
+        put_string(&mut b, b"xy");
+        put_sint(&mut b, 10);
+        put_sint(&mut b, 20);
+        // square taking layer, datatype and position from the modal variables
+        b.push(RID_RECTANGLE);
+        b.push(0x80 | 0x40);
+        put_uint(&mut b, 2);
+        end(&mut b);
+        let (_, cells) = parse(&b).unwrap();
+        let p = &cells[0].polys;
+        assert_eq!(p.len(), 1);
+        assert_eq!((p[0].layer, p[0].datatype), (3, 4));
+        assert_eq!(p[0].xy[0], (10, 20));
+    }
+
+    #[test]
+    fn manhattan_point_list_closes_implicitly() {
+        let mut b = header(1000.0);
+        b.push(RID_CELL_NAME);
+        put_string(&mut b, b"L");
+        // L-shape with type 0 (horizontal first): +20, +10, -10, +10 ; implied (0,20)
+        b.push(RID_POLYGON);
+        b.push(0x20 | 0x03);
+        put_uint(&mut b, 1);
+        put_uint(&mut b, 0);
+        put_uint(&mut b, 0);
+        put_uint(&mut b, 4);
+        for d in [20, 10, -10, 10] {
+            put_sint(&mut b, d);
+        }
+        end(&mut b);
+        let (_, cells) = parse(&b).unwrap();
+        assert_eq!(
+            cells[0].polys[0].xy,
+            vec![(0, 0), (20, 0), (20, 10), (10, 10), (10, 20), (0, 20)]
+        );
+    }
+
+    #[test]
+    fn path_trapezoid_and_cblock() {
+        let mut body = Vec::new();
+        body.push(RID_CELL_NAME);
+        put_string(&mut body, b"P");
+        // horizontal path (0,0)->(100,0), half-width 5, flush/half-width extensions
+        body.push(RID_PATH);
+        body.push(0x80 | 0x40 | 0x20 | 0x03);
+        put_uint(&mut body, 1);
+        put_uint(&mut body, 0);
+        put_uint(&mut body, 5);
+        put_uint(&mut body, (1 << 2) | 2);
+        put_uint(&mut body, 4);
+        put_uint(&mut body, 1);
+        put_gdelta(&mut body, 100, 0);
+        // trapezoid w=10 h=4, delta-a=2 only
+        body.push(RID_TRAPEZOID_A);
+        body.push(0x40 | 0x20);
+        put_uint(&mut body, 10);
+        put_uint(&mut body, 4);
+        put_sint(&mut body, 2);
+
+        let mut comp = Vec::new();
+        {
+            use std::io::Write;
+            let mut e =
+                flate2::write::DeflateEncoder::new(&mut comp, flate2::Compression::default());
+            e.write_all(&body).unwrap();
+            e.finish().unwrap();
+        }
+        let mut b = header(1000.0);
+        b.push(RID_CBLOCK);
+        put_uint(&mut b, 0);
+        put_uint(&mut b, body.len() as u64);
+        put_uint(&mut b, comp.len() as u64);
+        b.extend_from_slice(&comp);
+        end(&mut b);
+
+        let (s, cells) = parse(&b).unwrap();
+        let path = &cells[0].paths[0];
+        assert_eq!((path.width, path.pathtype), (10, 4));
+        assert_eq!((path.bgnextn, path.endextn), (0, 5));
+        assert_eq!(path.xy, vec![(0, 0), (100, 0)]);
+        let p = &cells[0].polys;
+        assert_eq!(p[0].xy, vec![(0, 0), (2, 4), (10, 4), (10, 0)]);
+        assert_eq!(s.cells[0].total_polys, 1);
+        assert_eq!(s.cells[0].total_paths, 1);
+        assert_eq!(
+            s.cells[0].bbox,
+            Some(crate::BBox {
+                xmin: 0,
+                ymin: -5,
+                xmax: 105,
+                ymax: 5
+            })
+        );
+    }
+
+    #[test]
+    fn hostile_counts_are_errors_not_panics() {
+        let rect_with = |rep: &dyn Fn(&mut Vec<u8>)| {
+            let mut b = header(1000.0);
+            b.push(RID_CELL_NAME);
+            put_string(&mut b, b"X");
+            b.push(RID_RECTANGLE);
+            b.push(0x40 | 0x20 | 0x04 | 0x02 | 0x01);
+            put_uint(&mut b, 1);
+            put_uint(&mut b, 0);
+            put_uint(&mut b, 4);
+            put_uint(&mut b, 4);
+            rep(&mut b);
+            end(&mut b);
+            parse(&b)
+        };
+        let out_of_range = |r: Result<_, IoError>| match r {
+            Err(IoError::Oasis { msg, .. }) => msg.contains("repetition count"),
+            _ => false,
+        };
+        // a count that overflows once the implied +2 is added
+        assert!(out_of_range(rect_with(&|b| {
+            put_uint(b, 2);
+            put_uint(b, u64::MAX);
+            put_uint(b, 10);
+        })));
+        // each side allowed, the product not
+        assert!(out_of_range(rect_with(&|b| {
+            put_uint(b, 1);
+            put_uint(b, MAX_REPETITION - 2);
+            put_uint(b, MAX_REPETITION - 2);
+            put_uint(b, 10);
+            put_uint(b, 10);
+        })));
+        assert!(out_of_range(rect_with(&|b| {
+            put_uint(b, 4);
+            put_uint(b, u64::MAX);
+        })));
+        // a lattice far off the coordinate range is a range error
+        match rect_with(&|b| {
+            put_uint(b, 2);
+            put_uint(b, 0);
+            put_uint(b, u64::MAX >> 1);
+        }) {
+            Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("exceeds 32 bits")),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        // a CBLOCK claiming more than it holds
+        let mut comp = Vec::new();
+        {
+            use std::io::Write;
+            let mut e =
+                flate2::write::DeflateEncoder::new(&mut comp, flate2::Compression::default());
+            e.write_all(&[RID_PAD]).unwrap();
+            e.finish().unwrap();
+        }
+        for claimed in [u64::MAX, 1 << 40, 0] {
+            let mut b = header(1000.0);
+            b.push(RID_CBLOCK);
+            put_uint(&mut b, 0);
+            put_uint(&mut b, claimed);
+            put_uint(&mut b, comp.len() as u64);
+            b.extend_from_slice(&comp);
+            end(&mut b);
+            match parse(&b) {
+                Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("CBLOCK size")),
+                other => panic!("unexpected {:?}", other.map(|_| ())),
+            }
+        }
+
+        // bits past 64 in the tenth byte of an unsigned-integer
+        let mut max = Vec::new();
+        put_uint(&mut max, u64::MAX);
+        let mut over = max.clone();
+        *over.last_mut().unwrap() |= 0x02;
+        let uint = |b: &[u8]| {
+            Cursor {
+                buf: b,
+                pos: 0,
+                base: 0,
+            }
+            .uint()
+        };
+        assert_eq!(uint(&max).unwrap(), u64::MAX);
+        match uint(&over) {
+            Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("overflows 64 bits")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn repetitions_past_the_file_budget_are_errors() {
+        // many rectangles, each repeated on an 8 x 8 lattice
+        let mut b = header(1000.0);
+        b.push(RID_CELL_NAME);
+        put_string(&mut b, b"X");
+        for _ in 0..100 {
+            b.push(RID_RECTANGLE);
+            b.push(0x40 | 0x20 | 0x04 | 0x02 | 0x01);
+            put_uint(&mut b, 1);
+            put_uint(&mut b, 0);
+            put_uint(&mut b, 4);
+            put_uint(&mut b, 4);
+            put_uint(&mut b, 1);
+            put_uint(&mut b, 6);
+            put_uint(&mut b, 6);
+            put_uint(&mut b, 10);
+            put_uint(&mut b, 10);
+        }
+        end(&mut b);
+        assert_eq!(parse(&b).unwrap().1[0].polys.len(), 6400);
+        match parse_with_budget(&b, 3 * 63) {
+            Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("element limit")),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn ctrapezoid_shapes_fit_their_box() {
+        for t in 0..26 {
+            let pts = ctrapezoid_outline(t, 20, 20).unwrap();
+            assert!(pts
+                .iter()
+                .all(|&(x, y)| (0..=40).contains(&x) && (0..=40).contains(&y)));
+        }
+        assert!(ctrapezoid_outline(26, 1, 1).is_none());
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_undefined_modal() {
+        assert!(matches!(
+            parse(b"GDS"),
+            Err(IoError::Oasis { offset: 0, .. })
+        ));
+        let mut b = header(1000.0);
+        b.push(RID_CELL_NAME);
+        put_string(&mut b, b"X");
+        b.push(RID_RECTANGLE);
+        b.push(0x40);
+        put_uint(&mut b, 3);
+        end(&mut b);
+        match parse(&b) {
+            Err(IoError::Oasis { msg, .. }) => assert!(msg.contains("layer")),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use super::*;
+use crate::{CellArrayRef, CellPolygons, IoError, Label, Path, Polygon, Strans};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Write cells as an OASIS file.
+/// `unit` is the number of database units per micron (e.g. 1000.0 for a 1 nm grid).
+pub fn write_oas<P: AsRef<std::path::Path>>(
+    path: P,
+    unit: f64,
+    cells: &[CellPolygons],
+) -> Result<(), IoError> {
+    let f = File::create(path)?;
+    let mut w = BufWriter::new(f);
+    write_oas_to(&mut w, unit, cells)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Write cells as an OASIS stream to any writer.
+///
+/// Cell names get implicit CELLNAME reference numbers in the order given (followed by
+/// any names that are only referenced), and every (layer, datatype) pair in use gets a
+/// LAYERNAME entry named `L<layer>D<datatype>`. Coordinates are emitted in absolute
+/// xy-mode; layer/datatype use the modal variables.
+///
+/// OASIS paths store a half-width, so odd GDS widths are rounded up by one DBU,
+/// absolute widths (negative WIDTH) become ordinary ones, and round-ended paths
+/// (type 1) are written with half-width extensions. Polygons with fewer than three
+/// vertices have no OASIS form and are skipped. Placements have
+/// no notion of absolute magnification/rotation, so those STRANS flags are dropped;
+/// TEXT has no presentation or transformation, so labels keep only layer, string and
+/// position.
+pub fn write_oas_to<W: Write>(w: &mut W, unit: f64, cells: &[CellPolygons]) -> Result<(), IoError> {
+    let mut out = Vec::with_capacity(1 << 16);
+    out.extend_from_slice(MAGIC);
+
+    // START: version, unit, offset-flag=0 followed by six empty table-offset pairs
+    out.push(RID_START);
+    put_string(&mut out, b"1.0");
+    put_real(&mut out, unit);
+    put_uint(&mut out, 0);
+    for _ in 0..12 {
+        put_uint(&mut out, 0);
+    }
+
+    // name tables: CELLNAME refnums are assigned implicitly 0, 1, 2, ...
+    let mut refnums: HashMap<&str, u64> = HashMap::new();
+    let referenced = cells.iter().flat_map(|c| {
+        c.refs
+            .iter()
+            .map(|r| r.name.as_str())
+            .chain(c.arefs.iter().map(|a| a.name.as_str()))
+    });
+    for name in cells.iter().map(|c| c.name.as_str()).chain(referenced) {
+        if refnums.contains_key(name) {
+            continue;
+        }
+        refnums.insert(name, refnums.len() as u64);
+        out.push(RID_CELLNAME);
+        put_string(&mut out, name.as_bytes());
+    }
+    let layers: BTreeSet<(u16, u16)> = cells
+        .iter()
+        .flat_map(|c| {
+            c.polys
+                .iter()
+                .map(|p| (p.layer, p.datatype))
+                .chain(c.paths.iter().map(|p| (p.layer, p.datatype)))
+        })
+        .collect();
+    for (layer, datatype) in layers {
+        out.push(RID_LAYERNAME);
+        put_string(&mut out, format!("L{}D{}", layer, datatype).as_bytes());
+        // interval type 3: exactly one value
+        put_uint(&mut out, 3);
+        put_uint(&mut out, layer as u64);
+        put_uint(&mut out, 3);
+        put_uint(&mut out, datatype as u64);
+    }
+    w.write_all(&out)?;
+    out.clear();
+
+    for c in cells {
+        out.push(RID_CELL_REF);
+        put_uint(&mut out, refnums[c.name.as_str()]);
+        // modal variables are reset at the start of every cell
+        let mut modal = Modal::default();
+        for p in &c.polys {
+            if let Some((x, y, wd, ht)) = as_rectangle(&p.xy) {
+                put_rectangle(&mut out, &mut modal, p, x, y, wd, ht);
+            } else {
+                put_polygon(&mut out, &mut modal, p);
+            }
+        }
+        for p in &c.paths {
+            put_path(&mut out, &mut modal, p);
+        }
+        for r in &c.refs {
+            put_placement(
+                &mut out,
+                refnums[r.name.as_str()],
+                r.origin,
+                &r.strans,
+                None,
+            );
+        }
+        for a in &c.arefs {
+            let rep = array_repetition(a);
+            put_placement(&mut out, refnums[a.name.as_str()], a.origin, &a.strans, rep);
+        }
+        for l in &c.labels {
+            put_text(&mut out, &mut modal, l);
+        }
+        w.write_all(&out)?;
+        out.clear();
+    }
+
+    // END: padding-string + validation-scheme 0 (none), padded to 256 bytes total
+    // record id (1) + padding length as a 2-byte uint (2) + validation scheme (1)
+    let pad = END_RECORD_LEN - 1 - 2 - 1;
+    out.push(RID_END);
+    put_string(&mut out, &vec![0u8; pad]);
+    put_uint(&mut out, 0);
+    debug_assert_eq!(out.len(), END_RECORD_LEN);
+    w.write_all(&out)?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Modal {
+    layer: Option<u16>,
+    datatype: Option<u16>,
+    textlayer: Option<u16>,
+    texttype: Option<u16>,
+}
+
+impl Modal {
+    /// Returns the L/D info bits that must be set (bit 0 = layer, bit 1 = datatype).
+    fn layer_bits(&mut self, layer: u16, datatype: u16) -> u8 {
+        let mut bits = 0;
+        if self.layer != Some(layer) {
+            self.layer = Some(layer);
+            bits |= 0x01;
+        }
+        if self.datatype != Some(datatype) {
+            self.datatype = Some(datatype);
+            bits |= 0x02;
+        }
+        bits
+    }
+}
+
+fn put_layer_fields(out: &mut Vec<u8>, bits: u8, layer: u16, datatype: u16) {
+    if bits & 0x01 != 0 {
+        put_uint(out, layer as u64);
+    }
+    if bits & 0x02 != 0 {
+        put_uint(out, datatype as u64);
+    }
+}
+
+/// Axis-aligned rectangles as (xmin, ymin, width, height).
+fn as_rectangle(xy: &[(i32, i32)]) -> Option<(i32, i32, u64, u64)> {
+    if xy.len() != 4 {
+        return None;
+    }
+    let horiz = |a: (i32, i32), b: (i32, i32)| a.1 == b.1 && a.0 != b.0;
+    let vert = |a: (i32, i32), b: (i32, i32)| a.0 == b.0 && a.1 != b.1;
+    let edges_ok = (0..4).all(|i| {
+        let (a, b) = (xy[i], xy[(i + 1) % 4]);
+        if i % 2 == 0 {
+            horiz(a, b)
+        } else {
+            vert(a, b)
+        }
+    }) || (0..4).all(|i| {
+        let (a, b) = (xy[i], xy[(i + 1) % 4]);
+        if i % 2 == 0 {
+            vert(a, b)
+        } else {
+            horiz(a, b)
+        }
+    });
+    if !edges_ok {
+        return None;
+    }
+    let xmin = xy.iter().map(|p| p.0).min()?;
+    let xmax = xy.iter().map(|p| p.0).max()?;
+    let ymin = xy.iter().map(|p| p.1).min()?;
+    let ymax = xy.iter().map(|p| p.1).max()?;
+    Some((
+        xmin,
+        ymin,
+        (xmax as i64 - xmin as i64) as u64,
+        (ymax as i64 - ymin as i64) as u64,
+    ))
+}
+
+fn put_rectangle(
+    out: &mut Vec<u8>,
+    modal: &mut Modal,
+    p: &Polygon,
+    x: i32,
+    y: i32,
+    w: u64,
+    h: u64,
+) {
+    // info byte: S W H X Y R D L
+    let ld = modal.layer_bits(p.layer, p.datatype);
+    let square = w == h;
+    let info = if square { 0x80 | 0x40 } else { 0x40 | 0x20 } | 0x10 | 0x08 | ld;
+    out.push(RID_RECTANGLE);
+    out.push(info);
+    put_layer_fields(out, ld, p.layer, p.datatype);
+    put_uint(out, w);
+    if !square {
+        put_uint(out, h);
+    }
+    put_sint(out, x as i64);
+    put_sint(out, y as i64);
+}
+
+fn put_polygon(out: &mut Vec<u8>, modal: &mut Modal, p: &Polygon) {
+    // POLYGON needs at least three vertices
+    let [(x0, y0), _, _, ..] = p.xy[..] else {
+        return;
+    };
+    // info byte: 0 0 P X Y R D L
+    let ld = modal.layer_bits(p.layer, p.datatype);
+    out.push(RID_POLYGON);
+    out.push(0x20 | 0x10 | 0x08 | ld);
+    put_layer_fields(out, ld, p.layer, p.datatype);
+    // closing edge implied
+    put_point_list(out, &p.xy);
+    put_sint(out, x0 as i64);
+    put_sint(out, y0 as i64);
+}
+
+/// Point-list type 4: g-deltas from the first vertex.
+fn put_point_list(out: &mut Vec<u8>, xy: &[(i32, i32)]) {
+    put_uint(out, 4);
+    put_uint(out, (xy.len() - 1) as u64);
+    for w in xy.windows(2) {
+        let dx = w[1].0 as i64 - w[0].0 as i64;
+        let dy = w[1].1 as i64 - w[0].1 as i64;
+        put_gdelta(out, dx, dy);
+    }
+}
+
+fn put_path(out: &mut Vec<u8>, modal: &mut Modal, p: &Path) {
+    let Some(&(x0, y0)) = p.xy.first() else {
+        return;
+    };
+    // info byte: E W P X Y R D L
+    let ld = modal.layer_bits(p.layer, p.datatype);
+    out.push(RID_PATH);
+    out.push(0x80 | 0x40 | 0x20 | 0x10 | 0x08 | ld);
+    put_layer_fields(out, ld, p.layer, p.datatype);
+    put_uint(out, p.width.unsigned_abs().div_ceil(2) as u64);
+    // extension scheme 0000SSEE: 1 = flush, 2 = half-width, 3 = explicit
+    match p.pathtype {
+        1 | 2 => put_uint(out, (2 << 2) | 2),
+        4 => {
+            put_uint(out, (3 << 2) | 3);
+            put_sint(out, p.bgnextn as i64);
+            put_sint(out, p.endextn as i64);
+        }
+        _ => put_uint(out, (1 << 2) | 1),
+    }
+    put_point_list(out, &p.xy);
+    put_sint(out, x0 as i64);
+    put_sint(out, y0 as i64);
+}
+
+fn put_text(out: &mut Vec<u8>, modal: &mut Modal, l: &Label) {
+    // info byte: 0 C N X Y R T L
+    let mut info = 0x40 | 0x10 | 0x08;
+    if modal.textlayer != Some(l.layer) {
+        modal.textlayer = Some(l.layer);
+        info |= 0x01;
+    }
+    if modal.texttype != Some(l.texttype) {
+        modal.texttype = Some(l.texttype);
+        info |= 0x02;
+    }
+    out.push(RID_TEXT);
+    out.push(info);
+    put_string(out, l.string.as_bytes());
+    put_layer_fields(out, info & 0x03, l.layer, l.texttype);
+    put_sint(out, l.position.0 as i64);
+    put_sint(out, l.position.1 as i64);
+}
+
+/// PLACEMENT (17) when the rotation is a multiple of 90° and there is no
+/// magnification, PLACEMENT (18) with explicit real values otherwise.
+fn put_placement(
+    out: &mut Vec<u8>,
+    refnum: u64,
+    (x, y): (i32, i32),
+    t: &Strans,
+    rep: Option<Vec<u8>>,
+) {
+    // info byte: C N X Y R A A F (17) / C N X Y R M A F (18)
+    let mut info = 0x80 | 0x40 | 0x20 | 0x10 | t.reflect_x as u8;
+    if rep.is_some() {
+        info |= 0x08;
+    }
+    let quarter = t.angle.rem_euclid(360.0) / 90.0;
+    if t.mag == 1.0 && quarter.fract() == 0.0 {
+        out.push(RID_PLACEMENT);
+        out.push(info | (quarter as u8) << 1);
+        put_uint(out, refnum);
+    } else {
+        if t.mag != 1.0 {
+            info |= 0x04;
+        }
+        if t.angle != 0.0 {
+            info |= 0x02;
+        }
+        out.push(RID_PLACEMENT_MAG);
+        out.push(info);
+        put_uint(out, refnum);
+        if t.mag != 1.0 {
+            put_real(out, t.mag);
+        }
+        if t.angle != 0.0 {
+            put_real(out, t.angle);
+        }
+    }
+    put_sint(out, x as i64);
+    put_sint(out, y as i64);
+    if let Some(rep) = rep {
+        out.extend_from_slice(&rep);
+    }
+}
+
+/// Encode an AREF lattice as a repetition: type 8/9 when the corners are exact multiples
+/// of the pitch, explicit displacements (type 10) otherwise. `None` for a 1×1 array.
+fn array_repetition(a: &CellArrayRef) -> Option<Vec<u8>> {
+    let (n, m) = (a.cols.max(1) as i64, a.rows.max(1) as i64);
+    if n * m <= 1 {
+        return None;
+    }
+    let span = |corner: (i32, i32), k: i64| {
+        let d = (
+            corner.0 as i64 - a.origin.0 as i64,
+            corner.1 as i64 - a.origin.1 as i64,
+        );
+        (d.0 % k == 0 && d.1 % k == 0).then_some((d.0 / k, d.1 / k))
+    };
+    let mut rep = Vec::new();
+    match (span(a.col_corner, n), span(a.row_corner, m)) {
+        (Some(cs), Some(rs)) if n >= 2 && m >= 2 => {
+            put_uint(&mut rep, 8);
+            put_uint(&mut rep, (n - 2) as u64);
+            put_uint(&mut rep, (m - 2) as u64);
+            put_gdelta(&mut rep, cs.0, cs.1);
+            put_gdelta(&mut rep, rs.0, rs.1);
+        }
+        (Some(s), _) if m == 1 => {
+            put_uint(&mut rep, 9);
+            put_uint(&mut rep, (n - 2) as u64);
+            put_gdelta(&mut rep, s.0, s.1);
+        }
+        (_, Some(s)) if n == 1 => {
+            put_uint(&mut rep, 9);
+            put_uint(&mut rep, (m - 2) as u64);
+            put_gdelta(&mut rep, s.0, s.1);
+        }
+        _ => {
+            let pos: Vec<(i32, i32)> = a.positions().collect();
+            put_uint(&mut rep, 10);
+            put_uint(&mut rep, (pos.len() - 2) as u64);
+            for w in pos.windows(2) {
+                put_gdelta(
+                    &mut rep,
+                    w[1].0 as i64 - w[0].0 as i64,
+                    w[1].1 as i64 - w[0].1 as i64,
+                );
+            }
+        }
+    }
+    Some(rep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect_cell() -> CellPolygons {
+        CellPolygons {
+            name: "TOP".into(),
+            polys: vec![
+                Polygon {
+                    layer: 1,
+                    datatype: 0,
+                    xy: vec![(0, 0), (10, 0), (10, 5), (0, 5)],
+                },
+                Polygon {
+                    layer: 1,
+                    datatype: 0,
+                    xy: vec![(0, 0), (10, 0), (5, 5)],
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn writes_magic_start_and_padded_end() {
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &[rect_cell()]).unwrap();
+        assert!(buf.starts_with(MAGIC));
+        assert_eq!(buf[MAGIC.len()], RID_START);
+        let end = &buf[buf.len() - END_RECORD_LEN..];
+        assert_eq!(end[0], RID_END);
+        assert_eq!(*end.last().unwrap(), 0);
+    }
+
+    #[test]
+    fn detects_rectangles() {
+        assert_eq!(
+            as_rectangle(&[(0, 0), (10, 0), (10, 5), (0, 5)]),
+            Some((0, 0, 10, 5))
+        );
+        assert_eq!(
+            as_rectangle(&[(0, 0), (0, 5), (10, 5), (10, 0)]),
+            Some((0, 0, 10, 5))
+        );
+        assert_eq!(as_rectangle(&[(0, 0), (10, 0), (12, 5), (0, 5)]), None);
+    }
+
+    #[test]
+    fn modal_layer_emitted_once() {
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &[rect_cell()]).unwrap();
+        let rect = buf.iter().position(|&b| b == RID_RECTANGLE).unwrap();
+        // first shape sets layer and datatype
+        assert_eq!(buf[rect + 1] & 0x03, 0x03);
+        let poly = rect + buf[rect..].iter().position(|&b| b == RID_POLYGON).unwrap();
+        // second shape on the same layer relies on the modal values
+        assert_eq!(buf[poly + 1] & 0x03, 0x00);
+    }
+
+    #[test]
+    fn degenerate_polygons_are_skipped() {
+        let mut cell = rect_cell();
+        for xy in [vec![(1, 1)], vec![(0, 0), (7, 7)]] {
+            cell.polys.push(Polygon {
+                layer: 2,
+                datatype: 0,
+                xy,
+            });
+        }
+        let mut buf = Vec::new();
+        write_oas_to(&mut buf, 1000.0, &[cell]).unwrap();
+        let (_, cells) = crate::read_oas_from(&buf[..]).unwrap();
+        assert_eq!(cells[0].polys.len(), 2);
+        assert!(cells[0].polys.iter().all(|p| p.layer == 1));
+    }
+}
@@ -0,0 +1,369 @@
+//! Outline polygons of PATH elements.
+//!
+//! Paths are read the way the common viewers draw them:
+//! - repeated points are dropped, and so are points in the middle of a straight run;
+//! - type 0 ends flush with its end points, type 2 half the width beyond them, type 4
+//!   BGNEXTN/ENDEXTN beyond them (negative values pull the end in) and type 1 in a
+//!   half circle;
+//! - turns of up to 90° are mitered; sharper turns are cut square half the width
+//!   beyond the vertex, so a path folding back on itself ends there like type 2;
+//! - a single point is drawn as if the path ran along x: a square for type 2, the
+//!   extensions for type 4, a circle for type 1 and nothing for type 0;
+//! - coordinates halfway between grid points round up, so an odd width keeps its
+//!   exact width on the grid.
+
+use crate::{Path, Polygon};
+use std::f64::consts::PI;
+
+/// Segments used for each semicircular cap of a round-ended (type 1) path.
+const ROUND_CAP_SEGMENTS: usize = 16;
+
+type Pt = (f64, f64);
+
+impl Path {
+    /// Outline of the path as a polygon on the DBU grid. `None` for paths without
+    /// area (zero width, no points, or a single point of type 0).
+    pub fn to_polygon(&self) -> Option<Polygon> {
+        let mut xy: Vec<(i32, i32)> = Vec::new();
+        for (x, y) in self.outline() {
+            let p = (snap(x), snap(y));
+            if xy.last() != Some(&p) {
+                xy.push(p);
+            }
+        }
+        while xy.len() > 1 && xy.first() == xy.last() {
+            xy.pop();
+        }
+        if xy.len() < 3 {
+            return None;
+        }
+        Some(Polygon {
+            layer: self.layer,
+            datatype: self.datatype,
+            xy,
+        })
+    }
+
+    /// Outline points before rounding: the left edge forward, the end cap, the right
+    /// edge backward and the begin cap. Where a sharp turn is cut off the outline
+    /// overlaps itself, covering every point once or more (the nonzero rule).
+    /// Empty where [`to_polygon`](Self::to_polygon) returns `None`.
+    pub fn outline(&self) -> Vec<Pt> {
+        let hw = self.half_width();
+        let mut pts = self.spine();
+        if hw == 0.0 || pts.is_empty() || (pts.len() == 1 && self.pathtype == 0) {
+            return Vec::new();
+        }
+        if pts.len() == 1 {
+            pts.push(pts[0]);
+        }
+        let n = pts.len();
+        let dirs: Vec<Pt> = if pts[0] == pts[1] {
+            vec![(1.0, 0.0)]
+        } else {
+            pts.windows(2).map(|w| unit(w[0], w[1])).collect()
+        };
+        // square ends: move the end points outward by the extensions
+        if self.pathtype != 1 {
+            let (bext, eext) = self.extensions();
+            let (d0, d1) = (dirs[0], dirs[n - 2]);
+            pts[0] = (pts[0].0 - d0.0 * bext, pts[0].1 - d0.1 * bext);
+            pts[n - 1] = (pts[n - 1].0 + d1.0 * eext, pts[n - 1].1 + d1.1 * eext);
+            if n == 2 && dot(sub(pts[1], pts[0]), d0) <= 0.0 {
+                // the extensions leave nothing of a single segment
+                return Vec::new();
+            }
+        }
+
+        // offsets of every vertex on either side, in path order
+        let (left, right): (Vec<Vec<Pt>>, Vec<Vec<Pt>>) = (0..n)
+            .map(|i| join(pts[i], dirs[i.saturating_sub(1)], dirs[i.min(n - 2)], hw))
+            .unzip();
+        let mut out: Vec<Pt> = left.into_iter().flatten().collect();
+        if self.pathtype == 1 {
+            out.extend(arc(pts[n - 1], dirs[n - 2], hw));
+        }
+        for side in right.into_iter().rev() {
+            out.extend(side.into_iter().rev());
+        }
+        if self.pathtype == 1 {
+            let back = (-dirs[0].0, -dirs[0].1);
+            out.extend(arc(pts[0], back, hw));
+        }
+        out.dedup();
+        out
+    }
+
+    /// The points that shape the path: repeated points and points in the middle of
+    /// a straight run left out, reversals kept.
+    fn spine(&self) -> Vec<Pt> {
+        let mut pts: Vec<(i64, i64)> = Vec::with_capacity(self.xy.len());
+        for &(x, y) in &self.xy {
+            let c = (x as i64, y as i64);
+            if pts.last() == Some(&c) {
+                continue;
+            }
+            if let [.., a, b] = pts[..] {
+                let (u, v) = ((b.0 - a.0, b.1 - a.1), (c.0 - b.0, c.1 - b.1));
+                // deltas reach 2^32, so their products need i128
+                let (u, v) = ((u.0 as i128, u.1 as i128), (v.0 as i128, v.1 as i128));
+                if u.0 * v.1 == u.1 * v.0 && u.0 * v.0 + u.1 * v.1 > 0 {
+                    pts.pop();
+                }
+            }
+            pts.push(c);
+        }
+        pts.into_iter().map(|(x, y)| (x as f64, y as f64)).collect()
+    }
+}
+
+/// Left and right offsets of vertex `p` between directions `a` (in) and `b` (out),
+/// both in path order. Turns of up to 90° meet in a single miter point per side.
+/// A sharper turn is split: the inner side runs through `p` between the two segment
+/// edges, the outer side cuts across the corner half the width beyond `p`. The two
+/// segment rectangles plus that corner are then all covered.
+fn join(p: Pt, a: Pt, b: Pt, hw: f64) -> (Vec<Pt>, Vec<Pt>) {
+    let (na, nb) = ((-a.1, a.0), (-b.1, b.0));
+    let at = |n: Pt, s: f64, d: Pt, e: f64| (p.0 + n.0 * s + d.0 * e, p.1 + n.1 * s + d.1 * e);
+    if dot(a, b) >= 0.0 {
+        let k = hw / (1.0 + dot(na, nb));
+        let miter = (na.0 + nb.0, na.1 + nb.1);
+        return (vec![at(miter, k, a, 0.0)], vec![at(miter, -k, a, 0.0)]);
+    }
+    // a reversal counts as a left turn
+    let side = if a.0 * b.1 - a.1 * b.0 >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    };
+    let inner = vec![at(na, side * hw, a, 0.0), p, at(nb, side * hw, b, 0.0)];
+    let outer = vec![at(na, -side * hw, a, hw), at(nb, -side * hw, b, -hw)];
+    if side > 0.0 {
+        (inner, outer)
+    } else {
+        (outer, inner)
+    }
+}
+
+/// Interior points of the half circle of radius `r` around `c`, from the left of
+/// direction `d` through its tip to the right.
+fn arc(c: Pt, d: Pt, r: f64) -> impl Iterator<Item = Pt> {
+    let start = d.1.atan2(d.0) + PI / 2.0;
+    (1..ROUND_CAP_SEGMENTS).map(move |k| {
+        let a = start - PI * k as f64 / ROUND_CAP_SEGMENTS as f64;
+        (c.0 + r * a.cos(), c.1 + r * a.sin())
+    })
+}
+
+fn unit(a: Pt, b: Pt) -> Pt {
+    let (dx, dy) = sub(b, a);
+    let l = (dx * dx + dy * dy).sqrt();
+    (dx / l, dy / l)
+}
+
+fn sub(a: Pt, b: Pt) -> Pt {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn dot(a: Pt, b: Pt) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+/// Nearest grid coordinate, halves rounding up.
+pub(crate) fn snap(v: f64) -> i32 {
+    (v + 0.5).floor().clamp(i32::MIN as f64, i32::MAX as f64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(pathtype: u16, xy: &[(i32, i32)]) -> Path {
+        Path {
+            layer: 3,
+            datatype: 1,
+            width: 10,
+            pathtype,
+            bgnextn: 2,
+            endextn: 4,
+            xy: xy.to_vec(),
+        }
+    }
+
+    fn outline(p: &Path) -> Vec<(i32, i32)> {
+        p.to_polygon().unwrap().xy
+    }
+
+    /// Twice the signed area, counting overlaps of a folded outline once per layer.
+    fn area2(xy: &[(i32, i32)]) -> i64 {
+        let n = xy.len();
+        (0..n)
+            .map(|i| {
+                let (a, b) = (xy[i], xy[(i + 1) % n]);
+                a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64
+            })
+            .sum()
+    }
+
+    #[test]
+    fn square_ends_and_mitered_joins() {
+        let p = path(0, &[(0, 0), (100, 0), (100, 50)]);
+        let poly = p.to_polygon().unwrap();
+        assert_eq!((poly.layer, poly.datatype), (3, 1));
+        assert_eq!(
+            poly.xy,
+            [(0, 5), (95, 5), (95, 50), (105, 50), (105, -5), (0, -5)]
+        );
+
+        let p = path(2, &[(0, 0), (100, 0)]);
+        assert_eq!(outline(&p), [(-5, 5), (105, 5), (105, -5), (-5, -5)]);
+        let p = path(4, &[(0, 0), (0, 100)]);
+        assert_eq!(outline(&p), [(-5, -2), (-5, 104), (5, 104), (5, -2)]);
+
+        // 45° joins miter too
+        let p = path(0, &[(0, 0), (100, 0), (200, 100)]);
+        let xy = outline(&p);
+        assert_eq!(xy.len(), 6);
+        assert!(xy.contains(&(98, 5)) && xy.contains(&(102, -5)));
+    }
+
+    #[test]
+    fn repeated_and_collinear_points_are_ignored() {
+        let plain = outline(&path(0, &[(0, 0), (100, 0), (100, 50)]));
+        let noisy = path(
+            0,
+            &[
+                (0, 0),
+                (0, 0),
+                (30, 0),
+                (60, 0),
+                (100, 0),
+                (100, 0),
+                (100, 50),
+            ],
+        );
+        assert_eq!(outline(&noisy), plain);
+        let diagonal = path(2, &[(0, 0), (10, 10), (20, 20), (30, 30)]);
+        assert_eq!(outline(&diagonal).len(), 4);
+
+        // spanning the whole coordinate range
+        let (lo, hi) = (i32::MIN, i32::MAX);
+        let wide = path(0, &[(lo, lo), (0, 0), (hi, hi), (lo, 10)]);
+        assert!(wide.to_polygon().is_some());
+        let straight = path(0, &[(lo, lo), (0, 0), (hi, hi)]);
+        assert_eq!(outline(&straight).len(), 4);
+    }
+
+    #[test]
+    fn extensions_per_pathtype() {
+        let ends = |pathtype, bgnextn, endextn| {
+            let mut p = path(pathtype, &[(0, 0), (100, 0)]);
+            (p.bgnextn, p.endextn) = (bgnextn, endextn);
+            let xy = outline(&p);
+            let xs = xy.iter().map(|pt| pt.0);
+            (xs.clone().min().unwrap(), xs.max().unwrap())
+        };
+        assert_eq!(ends(0, 7, 7), (0, 100));
+        assert_eq!(ends(2, 7, 7), (-5, 105));
+        assert_eq!(ends(4, 7, 20), (-7, 120));
+        assert_eq!(ends(4, -10, -20), (10, 80));
+        assert_eq!(ends(4, 0, 0), (0, 100));
+        assert!(path(4, &[(0, 0), (10, 0)])
+            .to_polygon()
+            .is_some_and(|p| p.xy.len() == 4));
+        let mut gone = path(4, &[(0, 0), (10, 0)]);
+        (gone.bgnextn, gone.endextn) = (-6, -4);
+        assert!(gone.to_polygon().is_none());
+    }
+
+    #[test]
+    fn sharp_turns_are_cut_half_the_width_beyond_the_vertex() {
+        // folding back: a type 2 end at the turn, both legs covered
+        let p = path(0, &[(0, 0), (100, 0), (50, 0)]);
+        let xy = outline(&p);
+        assert_eq!(
+            xy,
+            [
+                (0, 5),
+                (100, 5),
+                (100, 0),
+                (100, -5),
+                (50, -5),
+                (50, 5),
+                (105, 5),
+                (105, -5),
+                (0, -5)
+            ]
+        );
+        // both legs plus the cut-off corner, each counted once
+        assert_eq!(area2(&xy).abs(), 2 * (1000 + 500 + 50));
+
+        // 135°: the corner is cut at half the width beyond the vertex on each leg
+        let xy = outline(&path(0, &[(0, 0), (100, 0), (0, 100)]));
+        assert!(xy.contains(&(105, -5)) && xy.contains(&(107, 0)));
+        assert!(xy.contains(&(100, 0)));
+        assert!(xy.iter().all(|&(x, y)| x <= 107 && y >= -5));
+
+        // turning right mirrors it
+        let xy = outline(&path(0, &[(0, 0), (100, 0), (0, -100)]));
+        assert!(xy.contains(&(105, 5)) && xy.contains(&(107, 0)));
+    }
+
+    #[test]
+    fn odd_widths_keep_their_width() {
+        let mut p = path(0, &[(0, 0), (10, 0)]);
+        p.width = 5;
+        assert_eq!(outline(&p), [(0, 3), (10, 3), (10, -2), (0, -2)]);
+        p.xy = vec![(0, 0), (0, 10)];
+        p.pathtype = 2;
+        assert_eq!(outline(&p), [(-2, -2), (-2, 13), (3, 13), (3, -2)]);
+        assert_eq!(area2(&outline(&p)).abs(), 2 * 5 * 15);
+        // a negative (absolute) width draws the same outline
+        p.width = -5;
+        assert_eq!(area2(&outline(&p)).abs(), 2 * 5 * 15);
+    }
+
+    #[test]
+    fn round_ends_stay_within_the_radius() {
+        let p = path(1, &[(0, 0), (100, 0)]);
+        let poly = p.to_polygon().unwrap();
+        assert_eq!(poly.xy.len(), 4 + 2 * (ROUND_CAP_SEGMENTS - 1));
+        assert!(poly.xy.contains(&(105, 0)) && poly.xy.contains(&(-5, 0)));
+        let bb = p.bbox().unwrap();
+        assert!(poly
+            .xy
+            .iter()
+            .all(|&(x, y)| x >= bb.xmin && x <= bb.xmax && y >= bb.ymin && y <= bb.ymax));
+        // round ends ignore BGNEXTN/ENDEXTN
+        let mut q = p.clone();
+        (q.bgnextn, q.endextn) = (50, 50);
+        assert_eq!(outline(&q), poly.xy);
+    }
+
+    #[test]
+    fn single_points() {
+        assert!(path(0, &[(5, 5)]).to_polygon().is_none());
+        assert!(path(0, &[(5, 5), (5, 5)]).to_polygon().is_none());
+        assert_eq!(
+            outline(&path(2, &[(5, 5), (5, 5)])),
+            [(0, 10), (10, 10), (10, 0), (0, 0)]
+        );
+        assert_eq!(
+            outline(&path(4, &[(5, 5)])),
+            [(3, 10), (9, 10), (9, 0), (3, 0)]
+        );
+        let circle = outline(&path(1, &[(5, 5)]));
+        assert_eq!(circle.len(), 2 * ROUND_CAP_SEGMENTS);
+        assert!(circle.contains(&(10, 5)) && circle.contains(&(0, 5)));
+        assert!(circle.contains(&(5, 10)) && circle.contains(&(5, 0)));
+    }
+
+    #[test]
+    fn degenerate_paths_have_no_outline() {
+        let mut p = path(0, &[(0, 0), (10, 0)]);
+        p.width = 0;
+        assert!(p.to_polygon().is_none());
+        assert!(p.outline().is_empty());
+        assert!(path(2, &[]).to_polygon().is_none());
+    }
+}
@@ -0,0 +1,155 @@
+//! Memory-mapped, multi-threaded GDSII reading for very large libraries.
+//!
+//! A structural scan ([`GdsIndex::scan`]) finds the byte range of every structure;
+//! the ranges are then decoded concurrently by the same record parser
+//! [`read_gds`](crate::read_gds) uses, so cells, their order and any error come out
+//! exactly as in the sequential reader. A file the parser has to work around
+//! anything in (apart from a missing ENDLIB) is read again sequentially, so its
+//! diagnostics also match [`read_gds_with`] in content and order.
+//!
+//! `benches/parallel_read.rs` compares both readers; its header lists measured times.
+
+use crate::{
+    read_gds_with_from, CellPolygons, CellSummary, Diagnostic, DiagnosticKind, GdsImage, GdsIndex,
+    GdsSummary, IoError, ReadOptions,
+};
+use rayon::prelude::*;
+
+/// [`read_gds`](crate::read_gds) on a memory-mapped file, decoding cells on all
+/// threads of the rayon pool. Compressed files cannot be mapped and are read
+/// sequentially instead.
+pub fn read_gds_parallel<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    let (summary, cells, _) = read_gds_parallel_with(path, &ReadOptions::default())?;
+    Ok((summary, cells))
+}
+
+/// [`read_gds_with`](crate::read_gds_with) on a memory-mapped file, decoding cells
+/// on all threads of the rayon pool.
+pub fn read_gds_parallel_with<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: &ReadOptions,
+) -> Result<(GdsSummary, Vec<CellPolygons>, Vec<Diagnostic>), IoError> {
+    match GdsImage::open(path)? {
+        GdsImage::Mapped(map) => read_gds_slice_with(&map, opts),
+        GdsImage::Owned(data) => read_gds_with_from(&data[..], opts),
+    }
+}
+
+/// [`read_gds_polygons`](crate::read_gds_polygons) via [`read_gds_parallel`].
+pub fn read_gds_polygons_parallel<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Vec<CellPolygons>, IoError> {
+    Ok(read_gds_parallel(path)?.1)
+}
+
+/// Parallel read of an uncompressed GDSII stream already in memory.
+pub fn read_gds_slice(data: &[u8]) -> Result<(GdsSummary, Vec<CellPolygons>), IoError> {
+    let (summary, cells, _) = read_gds_slice_with(data, &ReadOptions::default())?;
+    Ok((summary, cells))
+}
+
+/// [`read_gds_slice`] with `opts`, also returning what the parser worked around.
+pub fn read_gds_slice_with(
+    data: &[u8],
+    opts: &ReadOptions,
+) -> Result<(GdsSummary, Vec<CellPolygons>, Vec<Diagnostic>), IoError> {
+    let index = GdsIndex::scan(data)?;
+    let parsed: Vec<_> = (0..index.entries.len())
+        .into_par_iter()
+        .map(|i| index.read_entry_with(data, i, opts))
+        .collect();
+    // the first failing entry holds the first error in stream order
+    let mut out = Vec::with_capacity(index.entries.len());
+    let mut damaged = index.has_duplicates();
+    for entry in parsed {
+        let (cells, diags) = entry?;
+        // every entry lacks the ENDLIB only the stream as a whole can have
+        damaged |= diags
+            .iter()
+            .any(|d| d.kind != DiagnosticKind::MissingEndlib);
+        out.extend(cells);
+    }
+    if damaged {
+        return read_gds_with_from(data, opts);
+    }
+    let mut diags = Vec::new();
+    if !index.endlib {
+        diags.push(Diagnostic::new(
+            DiagnosticKind::MissingEndlib,
+            data.len() as u64,
+            None,
+            "input ends without ENDLIB".to_string(),
+        ));
+    }
+    let cells = out.iter().map(CellSummary::from_polygons).collect();
+    let summary = GdsSummary {
+        libname: index.libname,
+        units: index.units,
+        cells,
+    };
+    Ok((summary, out, diags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_gds, read_gds_from, read_gds_with_from};
+
+    fn nand2() -> &'static str {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2")
+    }
+
+    #[test]
+    fn matches_sequential_reader() {
+        let (seq_summary, seq) = read_gds(nand2()).unwrap();
+        let (par_summary, par) = read_gds_parallel(nand2()).unwrap();
+        assert!(!par.is_empty());
+        assert_eq!(format!("{:?}", par), format!("{:?}", seq));
+        assert_eq!(par_summary.libname, seq_summary.libname);
+        assert_eq!(par_summary.units, seq_summary.units);
+        for (p, s) in par_summary.cells.iter().zip(&seq_summary.cells) {
+            assert_eq!((&p.name, p.bbox), (&s.name, s.bbox));
+            assert_eq!(p.layer_poly_counts, s.layer_poly_counts);
+            assert_eq!(p.layer_path_counts, s.layer_path_counts);
+        }
+    }
+
+    #[test]
+    fn errors_match_sequential_reader() {
+        let data = std::fs::read(nand2()).unwrap();
+        for cut in [3, data.len() / 3, data.len() / 2 + 1] {
+            let seq = read_gds_from(&data[..cut]).map(|_| ()).unwrap_err();
+            let par = read_gds_slice(&data[..cut]).map(|_| ()).unwrap_err();
+            assert_eq!(par.to_string(), seq.to_string(), "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn diagnostics_match_sequential_reader() {
+        let data = std::fs::read(nand2()).unwrap();
+        let lenient = ReadOptions { strict: false };
+        // no ENDLIB: read in parallel, warned about from the scan
+        let unended = &data[..data.len() - 4];
+        // an XY record of the wrong type: read again sequentially
+        let mut bad_xy = data.clone();
+        let xy = bad_xy.windows(2).position(|w| w == [0x10, 0x03]).unwrap();
+        bad_xy[xy + 1] = 0x02;
+        for (input, opts) in [
+            (&data[..], ReadOptions::default()),
+            (unended, ReadOptions::default()),
+            (unended, lenient),
+            (&bad_xy[..], lenient),
+        ] {
+            let (_, seq_cells, seq) = read_gds_with_from(input, &opts).unwrap();
+            let (_, par_cells, par) = read_gds_slice_with(input, &opts).unwrap();
+            assert_eq!(par, seq);
+            assert_eq!(format!("{:?}", par_cells), format!("{:?}", seq_cells));
+        }
+        let (.., diags) = read_gds_slice_with(&bad_xy, &lenient).unwrap();
+        assert!(diags
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::DroppedElement));
+    }
+}
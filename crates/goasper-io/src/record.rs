@@ -0,0 +1,442 @@
+//! GDSII record framing: a streaming tokenizer over any [`Read`] source.
+//!
+//! Every GDSII record is a big-endian `u16` length (header included), a record type,
+//! a data type and the payload. [`GdsRecordReader`] yields them one by one with the
+//! payload already decoded, so analyses only have to match on record types.
+
+use crate::{
+    gds_real8_to_f64, trim_gds_ascii, IoError, DT_ASCII, DT_BITARRAY, DT_INT2, DT_INT4, DT_REAL8,
+    RT_ENDLIB,
+};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+};
+
+/// Record payload decoded according to its data type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordData {
+    /// No data (data type 0), e.g. BOUNDARY or ENDEL.
+    None,
+    BitArray(u16),
+    Int2(Vec<i16>),
+    Int4(Vec<i32>),
+    Real8(Vec<f64>),
+    /// NUL/space padding stripped.
+    Ascii(String),
+    /// REAL4 payloads and payloads whose size does not fit the data type.
+    Raw(Vec<u8>),
+}
+
+impl RecordData {
+    fn decode(dtype: u8, buf: &[u8]) -> Self {
+        match dtype {
+            0 if buf.is_empty() => RecordData::None,
+            DT_BITARRAY if buf.len() == 2 => {
+                RecordData::BitArray(u16::from_be_bytes([buf[0], buf[1]]))
+            }
+            DT_INT2 if buf.len().is_multiple_of(2) => RecordData::Int2(
+                buf.chunks_exact(2)
+                    .map(|b| i16::from_be_bytes([b[0], b[1]]))
+                    .collect(),
+            ),
+            DT_INT4 if buf.len().is_multiple_of(4) => RecordData::Int4(
+                buf.chunks_exact(4)
+                    .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+            ),
+            DT_REAL8 if buf.len().is_multiple_of(8) => RecordData::Real8(
+                buf.chunks_exact(8)
+                    .map(|b| gds_real8_to_f64(b.try_into().unwrap()))
+                    .collect(),
+            ),
+            DT_ASCII => RecordData::Ascii(trim_gds_ascii(buf).to_string()),
+            _ => RecordData::Raw(buf.to_vec()),
+        }
+    }
+
+    /// First INT2 value.
+    pub fn int2(&self) -> Option<i16> {
+        match self {
+            RecordData::Int2(v) => v.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// First INT4 value.
+    pub fn int4(&self) -> Option<i32> {
+        match self {
+            RecordData::Int4(v) => v.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// First REAL8 value.
+    pub fn real8(&self) -> Option<f64> {
+        match self {
+            RecordData::Real8(v) => v.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn bits(&self) -> Option<u16> {
+        match self {
+            RecordData::BitArray(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn ascii(&self) -> Option<&str> {
+        match self {
+            RecordData::Ascii(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// INT4 payload as (x, y) pairs; `None` unless it holds a non-empty, even count.
+    pub fn points(&self) -> Option<impl Iterator<Item = (i32, i32)> + '_> {
+        match self {
+            RecordData::Int4(v) if !v.is_empty() && v.len().is_multiple_of(2) => {
+                Some(v.chunks_exact(2).map(|p| (p[0], p[1])))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One GDSII record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Byte offset of the record header in the stream.
+    pub offset: u64,
+    /// Total record length, header included.
+    pub len: u16,
+    pub rectype: u8,
+    pub dtype: u8,
+    pub payload: RecordData,
+}
+
+/// Iterator over the records of a GDSII stream.
+///
+/// Iteration ends after ENDLIB (anything after it, typically zero padding, is not
+/// read) or at a clean end of input. The first error is yielded once and ends the
+/// iteration, unless [`resynchronizing`](Self::resynchronizing) is enabled.
+pub struct GdsRecordReader<R> {
+    r: R,
+    offset: u64,
+    buf: Vec<u8>,
+    done: bool,
+    resync: bool,
+    /// Offset and bytes of a rejected header to resume scanning from.
+    lost: Option<(u64, [u8; 4])>,
+}
+
+impl GdsRecordReader<BufReader<File>> {
+    /// Open a file for buffered record reading.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, IoError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> GdsRecordReader<R> {
+    /// Records are read straight from `r`; wrap unbuffered sources in a `BufReader`.
+    pub fn new(r: R) -> Self {
+        GdsRecordReader {
+            r,
+            offset: 0,
+            buf: Vec::with_capacity(1 << 16),
+            done: false,
+            resync: false,
+            lost: None,
+        }
+    }
+
+    /// Report offsets relative to `offset`, for a reader positioned mid-stream.
+    pub fn starting_at(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Recover from corrupt headers: after yielding the error, scan forward two bytes
+    /// at a time to the next plausible record header and continue from there. In this
+    /// mode a header with an unknown record type, or a data type or length that does
+    /// not fit its record type, is reported as [`IoError::Malformed`] as well.
+    /// Truncation still ends the iteration.
+    pub fn resynchronizing(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record>, IoError> {
+        let (offset, hdr) = match self.lost.take() {
+            Some(lost) => match self.find_header(lost)? {
+                Some(found) => found,
+                None => return Ok(None),
+            },
+            None => {
+                let offset = self.offset;
+                let mut hdr = [0u8; 4];
+                match read_full(&mut self.r, &mut hdr)? {
+                    0 => return Ok(None),
+                    4 => {}
+                    n => {
+                        return Err(IoError::Truncated {
+                            offset,
+                            rectype: None,
+                            needed: 4,
+                            available: n as u64,
+                        })
+                    }
+                }
+                (offset, hdr)
+            }
+        };
+        let len = u16::from_be_bytes([hdr[0], hdr[1]]);
+        let rectype = hdr[2];
+        let dtype = hdr[3];
+        if self.resync && !plausible_header(&hdr) {
+            self.lost = Some((offset, hdr));
+            self.offset = offset + 4;
+        }
+        if len >= 4 && !len.is_multiple_of(2) {
+            return Err(IoError::OddLength {
+                offset,
+                len,
+                rectype,
+                dtype,
+            });
+        }
+        if len < 4 || self.lost.is_some() {
+            return Err(IoError::Malformed {
+                offset,
+                len,
+                rectype,
+                dtype,
+            });
+        }
+        let pay = (len - 4) as usize;
+        self.buf.resize(pay, 0);
+        let got = read_full(&mut self.r, &mut self.buf)?;
+        if got < pay {
+            return Err(IoError::Truncated {
+                offset,
+                rectype: Some(rectype),
+                needed: len as u64,
+                available: 4 + got as u64,
+            });
+        }
+        self.offset = offset + len as u64;
+        Ok(Some(Record {
+            offset,
+            len,
+            rectype,
+            dtype,
+            payload: RecordData::decode(dtype, &self.buf),
+        }))
+    }
+
+    /// Slide a 4-byte window forward from a rejected header until it holds a
+    /// plausible one; `None` at end of input.
+    fn find_header(
+        &mut self,
+        (mut offset, mut hdr): (u64, [u8; 4]),
+    ) -> Result<Option<(u64, [u8; 4])>, IoError> {
+        loop {
+            hdr.copy_within(2..4, 0);
+            let n = read_full(&mut self.r, &mut hdr[2..4])?;
+            if n < 2 {
+                self.offset = offset + 4 + n as u64;
+                return Ok(None);
+            }
+            offset += 2;
+            self.offset = offset + 4;
+            if plausible_header(&hdr) {
+                return Ok(Some((offset, hdr)));
+            }
+        }
+    }
+}
+
+/// Data type the GDSII spec assigns to each record type.
+fn expected_dtype(rectype: u8) -> Option<u8> {
+    const NONE: u8 = 0;
+    Some(match rectype {
+        0x04 | 0x07..=0x0C | 0x11 | 0x14 | 0x15 | 0x2D | 0x38 => NONE,
+        0x17 | 0x1A | 0x26 | 0x34 => DT_BITARRAY,
+        0x00 | 0x01 | 0x05 | 0x0D | 0x0E | 0x13 | 0x16 | 0x21 | 0x22 | 0x25 | 0x2A | 0x2B
+        | 0x2E | 0x32 | 0x33 | 0x36 | 0x39 | 0x3B => DT_INT2,
+        0x0F | 0x10 | 0x27 | 0x2F..=0x31 | 0x35 => DT_INT4,
+        0x03 | 0x1B | 0x1C => DT_REAL8,
+        0x02 | 0x06 | 0x12 | 0x19 | 0x1F | 0x20 | 0x23 | 0x24 | 0x2C | 0x37 | 0x3A => DT_ASCII,
+        _ => return None,
+    })
+}
+
+/// Whether a header could start a valid record: known record type, the data type
+/// the spec requires for it, and a payload size that fits that data type.
+fn plausible_header(hdr: &[u8; 4]) -> bool {
+    let len = u16::from_be_bytes([hdr[0], hdr[1]]);
+    if len < 4 || expected_dtype(hdr[2]) != Some(hdr[3]) {
+        return false;
+    }
+    let pay = len - 4;
+    match hdr[3] {
+        0 => pay == 0,
+        DT_BITARRAY => pay == 2,
+        DT_INT2 => pay.is_multiple_of(2),
+        DT_INT4 => pay.is_multiple_of(4),
+        DT_REAL8 => pay.is_multiple_of(8),
+        _ => pay.is_multiple_of(2),
+    }
+}
+
+/// Like `read_exact`, but reports how much was read when the input ends early.
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<usize, IoError> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(n)
+}
+
+impl<R: Read> Iterator for GdsRecordReader<R> {
+    type Item = Result<Record, IoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_record() {
+            Ok(Some(rec)) => {
+                self.done = rec.rectype == RT_ENDLIB;
+                Some(Ok(rec))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = self.lost.is_none();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_typed_payloads() {
+        let mut b = Vec::new();
+        // HEADER 600, LIBNAME "AB", UNITS, XY, ENDLIB, then padding that must not be read
+        b.extend_from_slice(&[0, 6, 0x00, DT_INT2, 0x02, 0x58]);
+        b.extend_from_slice(&[0, 8, 0x02, DT_ASCII, b'A', b'B', 0, 0]);
+        b.extend_from_slice(&[0, 12, 0x03, DT_REAL8, 0x41, 0x10, 0, 0, 0, 0, 0, 0]);
+        b.extend_from_slice(&[0, 12, 0x10, DT_INT4, 0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFE]);
+        b.extend_from_slice(&[0, 4, RT_ENDLIB, 0]);
+        b.extend_from_slice(&[0, 0, 0, 0]);
+
+        let recs: Vec<Record> = GdsRecordReader::new(&b[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(recs.len(), 5);
+        assert_eq!(recs[0].payload.int2(), Some(600));
+        assert_eq!(recs[1].offset, 6);
+        assert_eq!(recs[1].payload.ascii(), Some("AB"));
+        assert_eq!(recs[2].payload.real8(), Some(1.0));
+        let pts: Vec<_> = recs[3].payload.points().unwrap().collect();
+        assert_eq!(pts, vec![(1, -2)]);
+        assert_eq!(recs[4].payload, RecordData::None);
+        assert_eq!(recs[4].offset, 38);
+    }
+
+    #[test]
+    fn truncated_and_odd_records() {
+        // payload cut short: LIBNAME declares 8 bytes, 6 present
+        let b = [0u8, 4, 0x00, 0x00, 0, 8, 0x02, DT_ASCII, b'A', b'B'];
+        let errs: Vec<_> = GdsRecordReader::new(&b[..]).collect();
+        assert!(matches!(
+            errs[1],
+            Err(IoError::Truncated {
+                offset: 4,
+                rectype: Some(0x02),
+                needed: 8,
+                available: 6
+            })
+        ));
+        // header cut short
+        let b = [0u8, 4, 0x00, 0x00, 0, 8];
+        let last = GdsRecordReader::new(&b[..]).last().unwrap();
+        assert!(matches!(
+            last,
+            Err(IoError::Truncated {
+                offset: 4,
+                rectype: None,
+                ..
+            })
+        ));
+        let b = [0u8, 5, 0x02, DT_ASCII, b'A'];
+        let e = GdsRecordReader::new(&b[..]).next().unwrap().unwrap_err();
+        assert!(matches!(e, IoError::OddLength { len: 5, .. }));
+        assert_eq!(e.offset(), Some(0));
+    }
+
+    #[test]
+    fn short_length_is_malformed() {
+        let b = [0u8, 2, 0x05, 0x02];
+        let mut it = GdsRecordReader::new(&b[..]);
+        assert!(matches!(
+            it.next(),
+            Some(Err(IoError::Malformed {
+                offset: 0,
+                len: 2,
+                ..
+            }))
+        ));
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn resynchronizes_after_corrupt_header() {
+        let mut b = Vec::new();
+        b.extend_from_slice(&[0, 6, 0x00, DT_INT2, 0x02, 0x58]);
+        // unknown record type 0x70, then the rest of a garbled record
+        b.extend_from_slice(&[0, 8, 0x70, 0x00, 0xAB, 0xCD, 0xEF, 0x01]);
+        b.extend_from_slice(&[0, 8, 0x02, DT_ASCII, b'A', b'B', 0, 0]);
+        b.extend_from_slice(&[0, 4, RT_ENDLIB, 0]);
+
+        // the strict reader accepts the unknown record and reads on
+        assert_eq!(GdsRecordReader::new(&b[..]).count(), 4);
+
+        let items: Vec<_> = GdsRecordReader::new(&b[..]).resynchronizing().collect();
+        assert_eq!(items.len(), 4);
+        assert!(matches!(
+            items[1],
+            Err(IoError::Malformed {
+                offset: 6,
+                rectype: 0x70,
+                ..
+            })
+        ));
+        let name = items[2].as_ref().unwrap();
+        assert_eq!((name.offset, name.payload.ascii()), (14, Some("AB")));
+        assert_eq!(items[3].as_ref().unwrap().rectype, RT_ENDLIB);
+
+        // scanning off the end just stops
+        let b = [0u8, 2, 0x05, 0x02, 0xFF, 0xFF, 0xFF];
+        let items: Vec<_> = GdsRecordReader::new(&b[..]).resynchronizing().collect();
+        assert_eq!(items.len(), 1);
+    }
+}
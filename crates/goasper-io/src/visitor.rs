@@ -0,0 +1,237 @@
+//! Streaming access to a GDSII library, one element at a time.
+//!
+//! [`visit_gds`] drives a [`LayoutVisitor`] from the record parser without building
+//! any per-cell vectors, so memory stays bounded by the largest single element.
+//! [`read_gds`](crate::read_gds) itself is a visitor that collects everything;
+//! [`SummaryVisitor`] keeps only bounding boxes and counts.
+
+use crate::{
+    compress, parse_records, CellArrayRef, CellPolygons, CellRef, CellSummary, Diagnostic,
+    GdsRecordReader, GdsSummary, IoError, Label, Path, Polygon, ReadOptions, Units,
+};
+use std::{fs::File, io::Read};
+
+/// Callbacks for [`visit_gds`]. Every method defaults to doing nothing.
+///
+/// Calls arrive in stream order: `on_library` once (before the first cell, or at the
+/// end for a library without cells), then for each named structure `on_cell_begin`,
+/// its elements and `on_cell_end`.
+pub trait LayoutVisitor {
+    fn on_library(&mut self, _libname: Option<&str>, _units: Option<Units>) {}
+    fn on_cell_begin(&mut self, _name: &str) {}
+    /// A BOUNDARY.
+    fn on_polygon(&mut self, _polygon: Polygon) {}
+    fn on_path(&mut self, _path: Path) {}
+    /// An SREF.
+    fn on_ref(&mut self, _cell_ref: CellRef) {}
+    /// An AREF.
+    fn on_aref(&mut self, _array: CellArrayRef) {}
+    /// A TEXT element.
+    fn on_text(&mut self, _label: Label) {}
+    fn on_cell_end(&mut self) {}
+}
+
+/// Stream a GDS file through `visitor`, returning the warnings of the read.
+pub fn visit_gds<P: AsRef<std::path::Path>, V: LayoutVisitor>(
+    path: P,
+    opts: &ReadOptions,
+    visitor: &mut V,
+) -> Result<Vec<Diagnostic>, IoError> {
+    visit_gds_from(File::open(path)?, opts, visitor)
+}
+
+/// [`visit_gds`] over any reader; compressed streams are decoded on the fly.
+pub fn visit_gds_from<R: Read, V: LayoutVisitor>(
+    r: R,
+    opts: &ReadOptions,
+    visitor: &mut V,
+) -> Result<Vec<Diagnostic>, IoError> {
+    let mut records = GdsRecordReader::new(compress::decompress(r)?);
+    if !opts.strict {
+        records = records.resynchronizing();
+    }
+    parse_records(records, opts, visitor)
+}
+
+/// Builds a [`GdsSummary`]: bbox and per-layer counts per cell, nothing else kept.
+#[derive(Debug, Default)]
+pub struct SummaryVisitor {
+    summary: GdsSummary,
+    cur: Option<CellSummary>,
+}
+
+impl SummaryVisitor {
+    pub fn finish(self) -> GdsSummary {
+        self.summary
+    }
+}
+
+impl LayoutVisitor for SummaryVisitor {
+    fn on_library(&mut self, libname: Option<&str>, units: Option<Units>) {
+        self.summary.libname = libname.map(str::to_string);
+        self.summary.units = units;
+    }
+
+    fn on_cell_begin(&mut self, name: &str) {
+        self.cur = Some(CellSummary {
+            name: name.to_string(),
+            ..Default::default()
+        });
+    }
+
+    fn on_polygon(&mut self, polygon: Polygon) {
+        if let Some(c) = self.cur.as_mut() {
+            c.add_polygon(&polygon);
+        }
+    }
+
+    fn on_path(&mut self, path: Path) {
+        if let Some(c) = self.cur.as_mut() {
+            c.add_path(&path);
+        }
+    }
+
+    fn on_cell_end(&mut self) {
+        self.summary.cells.extend(self.cur.take());
+    }
+}
+
+/// Collects whole cells; what [`read_gds`](crate::read_gds) returns.
+#[derive(Default)]
+pub(crate) struct CellCollector {
+    pub(crate) library: GdsSummary,
+    pub(crate) cells: Vec<CellPolygons>,
+}
+
+impl CellCollector {
+    /// Library info plus a summary of every collected cell.
+    pub(crate) fn finish(mut self) -> (GdsSummary, Vec<CellPolygons>) {
+        self.library.cells = self.cells.iter().map(CellSummary::from_polygons).collect();
+        (self.library, self.cells)
+    }
+}
+
+impl LayoutVisitor for CellCollector {
+    fn on_library(&mut self, libname: Option<&str>, units: Option<Units>) {
+        self.library.libname = libname.map(str::to_string);
+        self.library.units = units;
+    }
+
+    fn on_cell_begin(&mut self, name: &str) {
+        self.cells.push(CellPolygons {
+            name: name.to_string(),
+            ..Default::default()
+        });
+    }
+
+    fn on_polygon(&mut self, polygon: Polygon) {
+        if let Some(c) = self.cells.last_mut() {
+            c.polys.push(polygon);
+        }
+    }
+
+    fn on_path(&mut self, path: Path) {
+        if let Some(c) = self.cells.last_mut() {
+            c.paths.push(path);
+        }
+    }
+
+    fn on_ref(&mut self, cell_ref: CellRef) {
+        if let Some(c) = self.cells.last_mut() {
+            c.refs.push(cell_ref);
+        }
+    }
+
+    fn on_aref(&mut self, array: CellArrayRef) {
+        if let Some(c) = self.cells.last_mut() {
+            c.arefs.push(array);
+        }
+    }
+
+    fn on_text(&mut self, label: Label) {
+        if let Some(c) = self.cells.last_mut() {
+            c.labels.push(label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_gds;
+
+    /// Counts elements and checks the call order.
+    #[derive(Default)]
+    struct Counter {
+        library: usize,
+        open: bool,
+        cells: Vec<String>,
+        elements: usize,
+        labels: usize,
+    }
+
+    impl LayoutVisitor for Counter {
+        fn on_library(&mut self, libname: Option<&str>, _units: Option<Units>) {
+            assert!(self.cells.is_empty() && libname.is_some());
+            self.library += 1;
+        }
+        fn on_cell_begin(&mut self, name: &str) {
+            assert!(!self.open);
+            self.open = true;
+            self.cells.push(name.to_string());
+        }
+        fn on_polygon(&mut self, _: Polygon) {
+            assert!(self.open);
+            self.elements += 1;
+        }
+        fn on_path(&mut self, _: Path) {
+            self.elements += 1;
+        }
+        fn on_ref(&mut self, _: CellRef) {
+            self.elements += 1;
+        }
+        fn on_aref(&mut self, _: CellArrayRef) {
+            self.elements += 1;
+        }
+        fn on_text(&mut self, _: Label) {
+            self.labels += 1;
+        }
+        fn on_cell_end(&mut self) {
+            assert!(self.open);
+            self.open = false;
+        }
+    }
+
+    #[test]
+    fn visits_every_element_in_order() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/nand2.gds2");
+        let (summary, cells) = read_gds(src).unwrap();
+        let mut v = Counter::default();
+        let diags = visit_gds(src, &ReadOptions::default(), &mut v).unwrap();
+        assert!(diags.is_empty());
+        assert_eq!(v.library, 1);
+        assert!(!v.open);
+        let names: Vec<_> = cells.iter().map(|c| c.name.clone()).collect();
+        assert_eq!(v.cells, names);
+        let elements: usize = cells
+            .iter()
+            .map(|c| c.polys.len() + c.paths.len() + c.refs.len() + c.arefs.len())
+            .sum();
+        assert_eq!(v.elements, elements);
+        assert_eq!(
+            v.labels,
+            cells.iter().map(|c| c.labels.len()).sum::<usize>()
+        );
+
+        let mut s = SummaryVisitor::default();
+        visit_gds(src, &ReadOptions::default(), &mut s).unwrap();
+        let streamed = s.finish();
+        assert_eq!(streamed.libname, summary.libname);
+        assert_eq!(streamed.cells.len(), summary.cells.len());
+        for (a, b) in streamed.cells.iter().zip(&summary.cells) {
+            assert_eq!((&a.name, a.bbox), (&b.name, b.bbox));
+            assert_eq!(a.layer_poly_counts, b.layer_poly_counts);
+            assert_eq!(a.total_paths, b.total_paths);
+        }
+    }
+}
@@ -1,12 +1,97 @@
 from ._lowlevel import PyLayout as _PyLayout
+from ._lowlevel import (
+    CellNotFoundError,
+    DuplicateCellError,
+    GoasperError,
+    GoasperIOError,
+    HierarchyError,
+    MalformedFileError,
+    UnsupportedFeatureError,
+    WriteError,
+)
 
 
 class Layout:
-    def __init__(self):
-        self._inner = _PyLayout()
+    def __init__(self, strict=True):
+        """With strict=False, damaged GDS input is loaded as far as possible instead of raising; see warnings()."""
+        self._inner = _PyLayout(strict)
 
-    def load_gds(self, path):
-        self._inner.load_gds(str(path))
+    @classmethod
+    def new_library(cls, name, user_units_per_dbu=1e-3, meters_per_dbu=1e-9):
+        """An empty library to build with add_cell, add_polygon, add_path, add_label and add_ref."""
+        layout = cls.__new__(cls)
+        layout._inner = _PyLayout.new_library(name, user_units_per_dbu, meters_per_dbu)
+        return layout
+
+    def add_cell(self, name):
+        """Add an empty cell; raises DuplicateCellError if the name is taken."""
+        self._inner.add_cell(name)
+
+    def remove_cell(self, name):
+        """Remove a cell; references to it elsewhere are kept (see undefined_references())."""
+        self._inner.remove_cell(name)
+
+    def rename_cell(self, name, new_name):
+        """Rename a cell and every reference placing it."""
+        self._inner.rename_cell(name, new_name)
+
+    def add_polygon(self, cell, layer, datatype, xy):
+        """Add a polygon given as [(x, y), ...] in DBU, last point not repeated."""
+        self._inner.add_polygon(cell, layer, datatype, xy)
+
+    def add_path(self, cell, layer, datatype, xy, width, pathtype=0, bgnextn=0, endextn=0):
+        self._inner.add_path(cell, layer, datatype, xy, width, pathtype, bgnextn, endextn)
+
+    def add_label(self, cell, layer, texttype, string, position, reflect=False, magnification=1.0, rotation=0.0):
+        self._inner.add_label(cell, layer, texttype, string, position, reflect, magnification, rotation)
+
+    def add_ref(self, cell, child, origin, reflect=False, mag=1.0, angle=0.0):
+        """Place `child` in `cell` at `origin`; `child` need not exist yet."""
+        self._inner.add_ref(cell, child, origin, reflect, mag, angle)
+
+    def remove_polygon(self, cell, index):
+        """Remove the index-th polygon of `cell` (as listed by polygons_for); False if there is none."""
+        return self._inner.remove_polygon(cell, index)
+
+    def remove_path(self, cell, index):
+        """Remove the index-th path of `cell`; False if there is none."""
+        return self._inner.remove_path(cell, index)
+
+    def boolean(self, cell, layer_a, layer_b, op, out_layer):
+        """Add `layer_a op layer_b` to `cell` on `out_layer`, using the cell's own polygons and paths; layers are (layer, datatype) tuples, op is "and", "or", "xor" or "not" (a and not b). Returns the number of polygons added."""
+        return self._inner.boolean(cell, tuple(layer_a), tuple(layer_b), op, tuple(out_layer))
+
+    def merge_layer(self, cell, layer):
+        """Replace the polygons of `cell` on `layer` ((layer, datatype)) by their union: non-overlapping polygons, holes joined to their outline by a keyhole cut. Paths are left alone. Returns the number of polygons now on the layer."""
+        return self._inner.merge_layer(cell, tuple(layer))
+
+    def merge(self, cell):
+        """merge_layer for every layer of `cell`; returns the cell's polygon count afterwards."""
+        return self._inner.merge(cell)
+
+    def size(self, cell, layer, amount, out_layer, corner="square", segments=32):
+        """Add `layer` of `cell` grown by `amount` DBU (negative shrinks) on `out_layer`, merged into non-overlapping polygons. `amount` is a number or a (dx, dy) pair; corner is "square", "octagonal" or "round" (with `segments` per full circle). Returns the number of polygons added."""
+        dx, dy = amount if isinstance(amount, (tuple, list)) else (amount, amount)
+        return self._inner.size(cell, tuple(layer), int(dx), int(dy), tuple(out_layer), corner, segments)
+
+    def load_gds(self, source):
+        """Load GDSII from a path, bytes/bytearray, or a binary file-like object (e.g. io.BytesIO)."""
+        self._inner.load_gds(source)
+
+    def open_gds(self, source):
+        """Index a GDSII file (same sources as load_gds) without decoding it; cells are decoded on first access, e.g. by polygons_for."""
+        self._inner.open_gds(source)
+
+    def load_gds_parallel(self, path):
+        """Load a large GDSII file from a path, memory-mapped and decoded on all cores."""
+        self._inner.load_gds_parallel(str(path))
+
+    def load_oas(self, source):
+        """Load OASIS from the same kinds of sources as load_gds."""
+        self._inner.load_oas(source)
+
+    def save_gds(self, path):
+        self._inner.save_gds(str(path))
 
     def save_oas(self, path):
         self._inner.save_oas(str(path))
@@ -15,9 +100,69 @@ class Layout:
         """Return list of cell (structure) names parsed from the GDS."""
         return self._inner.cell_names()
 
+    def dbu_in_meters(self):
+        """Size of one database unit in meters, None if no units were read."""
+        return self._inner.dbu_in_meters()
+
+    def user_units_per_dbu(self):
+        """Size of one database unit in user units, None if no units were read."""
+        return self._inner.user_units_per_dbu()
+
+    def bbox_um(self, cell):
+        """(xmin, ymin, xmax, ymax) of a cell in microns, None if unknown/empty."""
+        return self._inner.bbox_um(cell)
+
     def summary(self):
+        """Library name, units and per-cell bbox (DBU and "bbox_um"), per-layer counts and instances ("refs"/"arefs").
+
+        "local_bbox" (same as "bbox") covers the cell's own geometry; "full_bbox" and "full_bbox_um" also cover every placed child cell.
+        """
         return self._inner.summary()
 
     def polygons(self):
         """List of dicts: [{"name": str, "polys": [{"layer":int,"datatype":int,"xy":[(x,y),..]}]}]"""
         return self._inner.polygons()
+
+    def flatten(self, cell=None, depth=None):
+        """Polygons of `cell` (default: the single top cell) with all instances placed and paths outlined, in the shape of polygons(); depth limits the instance levels included (0 = the cell alone)."""
+        return self._inner.flatten(cell, depth)
+
+    def polygons_for(self, cell):
+        """Polygons of one cell: [{"layer","datatype","xy"}]; raises CellNotFoundError for unknown cells."""
+        return self._inner.polygons_for(cell)
+
+    def paths(self):
+        """List of dicts: [{"name": str, "paths": [{"layer","datatype","width","pathtype","bgnextn","endextn","xy","outline"}]}]; "outline" is the path's polygon on the DBU grid (None for paths without area)."""
+        return self._inner.paths()
+
+    def labels_for(self, cell):
+        """Labels of one cell: [{"layer","texttype","string","position","anchor","reflect","magnification","rotation"}]; raises CellNotFoundError for unknown cells."""
+        return self._inner.labels_for(cell)
+
+    def top_cells(self):
+        """Cells not placed by any other cell, in file order."""
+        return self._inner.top_cells()
+
+    def children(self, cell):
+        """Cells placed directly in `cell` (each once); raises CellNotFoundError for unknown cells."""
+        return self._inner.children(cell)
+
+    def parents(self, cell):
+        """Cells placing `cell` directly; raises CellNotFoundError for unknown cells."""
+        return self._inner.parents(cell)
+
+    def topological_order(self):
+        """All cells with children before parents, the order GDS writers need; raises HierarchyError on undefined references or cycles."""
+        return self._inner.topological_order()
+
+    def depth(self, cell):
+        """Instance levels below `cell` (0 for a leaf); raises HierarchyError if the hierarchy below it is broken."""
+        return self._inner.depth(cell)
+
+    def undefined_references(self):
+        """[(cell, name)] for every reference to a cell the library does not define."""
+        return self._inner.undefined_references()
+
+    def warnings(self):
+        """Problems the last GDS load worked around: [{"kind","offset","cell","message"}], e.g. kind "dropped_element" or "missing_endlib"; after open_gds, "dropped_cell" for cells listed empty because they did not decode."""
+        return self._inner.warnings()
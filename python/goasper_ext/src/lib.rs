@@ -1,9 +1,139 @@
-use goasper_core::{GoasperError, Layout};
+use goasper_core::{
+    BBox, BooleanOp, CellRef, Corner, GoasperError as CoreError, Label, Layout, Path, Polygon,
+    ReadOptions, Strans, Units,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::PyOSError;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyList};
+use std::path::PathBuf;
 
-fn to_pyerr(e: GoasperError) -> PyErr {
-    pyo3::exceptions::PyIOError::new_err(e.to_string())
+// an OSError, so callers catching OSError/IOError around loads (what every failure
+// used to raise) keep working
+create_exception!(
+    goasper,
+    GoasperError,
+    PyOSError,
+    "Base class of all goasper errors; a subclass of OSError."
+);
+create_exception!(
+    goasper,
+    GoasperIOError,
+    GoasperError,
+    "The file could not be opened or read."
+);
+create_exception!(
+    goasper,
+    MalformedFileError,
+    GoasperError,
+    "Corrupt or invalid GDSII/OASIS input; see the `offset` and `rectype` attributes."
+);
+create_exception!(
+    goasper,
+    CellNotFoundError,
+    GoasperError,
+    "No cell with that name."
+);
+create_exception!(
+    goasper,
+    UnsupportedFeatureError,
+    GoasperError,
+    "The input uses a feature goasper does not implement."
+);
+create_exception!(
+    goasper,
+    WriteError,
+    GoasperError,
+    "Writing the output failed."
+);
+create_exception!(
+    goasper,
+    DuplicateCellError,
+    GoasperError,
+    "A cell with that name already exists."
+);
+create_exception!(
+    goasper,
+    HierarchyError,
+    GoasperError,
+    "A reference to an undefined cell, or cells placing each other in a cycle."
+);
+
+fn to_pyerr(e: CoreError) -> PyErr {
+    let msg = e.to_string();
+    match &e {
+        CoreError::Io(_) => GoasperIOError::new_err(msg),
+        CoreError::Format(_) => Python::with_gil(|py| {
+            let err = MalformedFileError::new_err(msg);
+            let v = err.value(py);
+            // attribute setting on a fresh exception instance cannot fail in practice
+            let _ = v.setattr("offset", e.offset());
+            let _ = v.setattr("rectype", e.rectype());
+            err
+        }),
+        CoreError::CellNotFound(_) => CellNotFoundError::new_err(msg),
+        CoreError::Unsupported(_) | CoreError::UnsupportedRequest(_) => {
+            UnsupportedFeatureError::new_err(msg)
+        }
+        CoreError::Write(_) => WriteError::new_err(msg),
+        CoreError::DuplicateCell(_) => DuplicateCellError::new_err(msg),
+        CoreError::UndefinedCell { .. } | CoreError::ReferenceCycle(_) => {
+            HierarchyError::new_err(msg)
+        }
+    }
+}
+
+enum Source {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// Resolve a load source: bytes-like objects, file-like objects (anything with `read`)
+/// and finally paths.
+fn source(src: &Bound<'_, PyAny>) -> PyResult<Source> {
+    if let Ok(b) = src.downcast::<PyBytes>() {
+        return Ok(Source::Bytes(b.as_bytes().to_vec()));
+    }
+    if let Ok(b) = src.downcast::<PyByteArray>() {
+        return Ok(Source::Bytes(b.to_vec()));
+    }
+    if src.hasattr("read")? {
+        let data = src.call_method0("read")?;
+        return match data.downcast::<PyBytes>() {
+            Ok(b) => Ok(Source::Bytes(b.as_bytes().to_vec())),
+            Err(_) => Err(pyo3::exceptions::PyTypeError::new_err(
+                "file-like object must be opened in binary mode",
+            )),
+        };
+    }
+    Ok(Source::Path(src.extract()?))
+}
+
+/// [{"layer","datatype","xy":[(x,y),..]}]
+fn poly_list<'py>(py: Python<'py>, polys: &[Polygon]) -> PyResult<Bound<'py, PyList>> {
+    let out = PyList::empty(py);
+    for p in polys {
+        let pd = PyDict::new(py);
+        pd.set_item("layer", p.layer)?;
+        pd.set_item("datatype", p.datatype)?;
+        // Convert points to a Python list of (x,y) tuples
+        let pts = PyList::empty(py);
+        for (x, y) in &p.xy {
+            pts.append((*x, *y))?;
+        }
+        pd.set_item("xy", pts)?;
+        out.append(pd)?;
+    }
+    Ok(out)
+}
+
+fn set_strans(d: &Bound<'_, PyDict>, t: &Strans) -> PyResult<()> {
+    d.set_item("reflect", t.reflect_x)?;
+    d.set_item("abs_mag", t.abs_mag)?;
+    d.set_item("abs_angle", t.abs_angle)?;
+    d.set_item("mag", t.mag)?;
+    d.set_item("angle", t.angle)?;
+    Ok(())
 }
 
 #[pyclass]
@@ -13,15 +143,251 @@ struct PyLayout {
 
 #[pymethods]
 impl PyLayout {
+    /// `strict=False` loads damaged GDS files as far as possible; see `warnings()`.
     #[new]
-    fn new() -> Self {
+    #[pyo3(signature = (strict=true))]
+    fn new(strict: bool) -> Self {
+        Self {
+            inner: Layout::with_options(ReadOptions { strict }),
+        }
+    }
+
+    /// An empty library to build from scratch.
+    #[staticmethod]
+    #[pyo3(signature = (name, user_units_per_dbu=1e-3, meters_per_dbu=1e-9))]
+    fn new_library(name: &str, user_units_per_dbu: f64, meters_per_dbu: f64) -> Self {
+        let units = Units {
+            user_units_per_dbu,
+            meters_per_dbu,
+        };
         Self {
-            inner: Layout::new(),
+            inner: Layout::new_library(name, units),
+        }
+    }
+
+    fn add_cell(&mut self, name: &str) -> PyResult<()> {
+        self.inner.add_cell(name).map_err(to_pyerr)?;
+        Ok(())
+    }
+
+    fn remove_cell(&mut self, name: &str) -> PyResult<()> {
+        self.inner.remove_cell(name).map_err(to_pyerr)?;
+        Ok(())
+    }
+
+    fn rename_cell(&mut self, name: &str, new_name: &str) -> PyResult<()> {
+        self.inner.rename_cell(name, new_name).map_err(to_pyerr)
+    }
+
+    fn add_polygon(
+        &mut self,
+        cell: &str,
+        layer: u16,
+        datatype: u16,
+        xy: Vec<(i32, i32)>,
+    ) -> PyResult<()> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        c.add_polygon(Polygon {
+            layer,
+            datatype,
+            xy,
+        });
+        Ok(())
+    }
+
+    #[pyo3(signature = (cell, layer, datatype, xy, width, pathtype=0, bgnextn=0, endextn=0))]
+    #[allow(clippy::too_many_arguments)]
+    fn add_path(
+        &mut self,
+        cell: &str,
+        layer: u16,
+        datatype: u16,
+        xy: Vec<(i32, i32)>,
+        width: i32,
+        pathtype: u16,
+        bgnextn: i32,
+        endextn: i32,
+    ) -> PyResult<()> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        c.add_path(Path {
+            layer,
+            datatype,
+            width,
+            pathtype,
+            bgnextn,
+            endextn,
+            xy,
+        });
+        Ok(())
+    }
+
+    #[pyo3(signature = (cell, layer, texttype, string, position, reflect=false, magnification=1.0, rotation=0.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn add_label(
+        &mut self,
+        cell: &str,
+        layer: u16,
+        texttype: u16,
+        string: String,
+        position: (i32, i32),
+        reflect: bool,
+        magnification: f64,
+        rotation: f64,
+    ) -> PyResult<()> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        c.add_label(Label {
+            layer,
+            texttype,
+            string,
+            position,
+            anchor: Default::default(),
+            reflect_x: reflect,
+            magnification,
+            rotation,
+        });
+        Ok(())
+    }
+
+    #[pyo3(signature = (cell, child, origin, reflect=false, mag=1.0, angle=0.0))]
+    fn add_ref(
+        &mut self,
+        cell: &str,
+        child: &str,
+        origin: (i32, i32),
+        reflect: bool,
+        mag: f64,
+        angle: f64,
+    ) -> PyResult<()> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        c.add_ref(CellRef {
+            name: child.to_string(),
+            origin,
+            strans: Strans {
+                reflect_x: reflect,
+                mag,
+                angle,
+                ..Default::default()
+            },
+        });
+        Ok(())
+    }
+
+    /// Remove polygon `index` of `cell`; False if there is none.
+    fn remove_polygon(&mut self, cell: &str, index: usize) -> PyResult<bool> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        Ok(c.remove_polygon(index).is_some())
+    }
+
+    /// Remove path `index` of `cell`; False if there is none.
+    fn remove_path(&mut self, cell: &str, index: usize) -> PyResult<bool> {
+        let mut c = self.inner.cell_mut(cell).map_err(to_pyerr)?;
+        Ok(c.remove_path(index).is_some())
+    }
+
+    /// Add `layer_a op layer_b` to `cell` as `out_layer`; layers are (layer, datatype)
+    /// and `op` one of "and", "or", "xor", "not". Returns the number of polygons added.
+    fn boolean(
+        &mut self,
+        cell: &str,
+        layer_a: (u16, u16),
+        layer_b: (u16, u16),
+        op: &str,
+        out_layer: (u16, u16),
+    ) -> PyResult<usize> {
+        let op = match op.to_ascii_lowercase().as_str() {
+            "and" => BooleanOp::And,
+            "or" => BooleanOp::Or,
+            "xor" => BooleanOp::Xor,
+            "not" => BooleanOp::Not,
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown boolean op {op:?}; expected and, or, xor or not"
+                )))
+            }
+        };
+        self.inner
+            .boolean(cell, layer_a, layer_b, op, out_layer)
+            .map_err(to_pyerr)
+    }
+
+    /// Replace the polygons of `cell` on `layer` (layer, datatype) by their union.
+    /// Returns the number of polygons now on the layer.
+    fn merge_layer(&mut self, cell: &str, layer: (u16, u16)) -> PyResult<usize> {
+        self.inner.merge_layer(cell, layer).map_err(to_pyerr)
+    }
+
+    /// Merge every layer of `cell`; returns the cell's polygon count afterwards.
+    fn merge(&mut self, cell: &str) -> PyResult<usize> {
+        self.inner.merge(cell).map_err(to_pyerr)
+    }
+
+    /// Add `layer` of `cell` grown (or shrunk, for negative amounts) by `dx`, `dy` as
+    /// `out_layer`. `corner` is "square", "octagonal" or "round"; round corners use
+    /// `segments` per full circle. Returns the number of polygons added.
+    #[pyo3(signature = (cell, layer, dx, dy, out_layer, corner="square", segments=32))]
+    #[allow(clippy::too_many_arguments)]
+    fn size(
+        &mut self,
+        cell: &str,
+        layer: (u16, u16),
+        dx: i32,
+        dy: i32,
+        out_layer: (u16, u16),
+        corner: &str,
+        segments: u32,
+    ) -> PyResult<usize> {
+        let corner = match corner.to_ascii_lowercase().as_str() {
+            "square" => Corner::Square,
+            "octagonal" => Corner::Octagonal,
+            "round" => Corner::Round(segments),
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown corner {corner:?}; expected square, octagonal or round"
+                )))
+            }
+        };
+        self.inner
+            .size(cell, layer, (dx, dy), corner, out_layer)
+            .map_err(to_pyerr)
+    }
+
+    /// Accepts a path (str / os.PathLike), `bytes`/`bytearray`, or a binary file-like
+    /// object with a `read()` method (e.g. `io.BytesIO`).
+    fn load_gds(&mut self, src: &Bound<'_, PyAny>) -> PyResult<()> {
+        match source(src)? {
+            Source::Path(p) => self.inner.load_gds(p),
+            Source::Bytes(b) => self.inner.load_gds_bytes(&b),
+        }
+        .map_err(to_pyerr)
+    }
+
+    /// Index a GDS without decoding it; cells are decoded on first access. Same
+    /// sources as `load_gds`.
+    fn open_gds(&mut self, src: &Bound<'_, PyAny>) -> PyResult<()> {
+        match source(src)? {
+            Source::Path(p) => self.inner.open_gds(p),
+            Source::Bytes(b) => self.inner.open_gds_from(&b[..]),
         }
+        .map_err(to_pyerr)
     }
 
-    fn load_gds(&mut self, path: &str) -> PyResult<()> {
-        self.inner.load_gds(path).map_err(to_pyerr)?;
+    /// Memory-map a GDS file and decode its cells on all cores; releases the GIL.
+    fn load_gds_parallel(&mut self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        py.allow_threads(|| self.inner.load_gds_parallel(path))
+            .map_err(to_pyerr)
+    }
+
+    /// Same sources as `load_gds`.
+    fn load_oas(&mut self, src: &Bound<'_, PyAny>) -> PyResult<()> {
+        match source(src)? {
+            Source::Path(p) => self.inner.load_oas(p),
+            Source::Bytes(b) => self.inner.load_oas_bytes(&b),
+        }
+        .map_err(to_pyerr)
+    }
+
+    fn save_gds(&self, path: &str) -> PyResult<()> {
+        self.inner.save_gds(path).map_err(to_pyerr)?;
         Ok(())
     }
 
@@ -30,6 +396,18 @@ impl PyLayout {
         Ok(())
     }
 
+    fn dbu_in_meters(&self) -> Option<f64> {
+        self.inner.dbu_in_meters()
+    }
+
+    fn user_units_per_dbu(&self) -> Option<f64> {
+        self.inner.user_units_per_dbu()
+    }
+
+    fn bbox_um(&self, cell: &str) -> Option<(f64, f64, f64, f64)> {
+        self.inner.bbox_um(cell)
+    }
+
     fn cell_names(&self) -> Vec<String> {
         self.inner.cell_names()
     }
@@ -37,6 +415,9 @@ impl PyLayout {
     fn summary<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
         let out = PyDict::new(py);
         out.set_item("libname", self.inner.libname())?;
+        out.set_item("dbu_in_meters", self.inner.dbu_in_meters())?;
+        out.set_item("user_units_per_dbu", self.inner.user_units_per_dbu())?;
+        let units = self.inner.units();
 
         let cells = PyList::empty(py);
         for c in self.inner.cell_summaries() {
@@ -47,12 +428,47 @@ impl PyLayout {
             } else {
                 d.set_item("bbox", py.None())?;
             }
+            let bbox_um = units.zip(c.bbox).map(|(u, bb)| u.bbox_to_microns(&bb));
+            d.set_item("bbox_um", bbox_um)?;
+            // "bbox" is the cell's own geometry; "full_bbox" includes placed children
+            let as_tuple = |bb: BBox| (bb.xmin, bb.ymin, bb.xmax, bb.ymax);
+            d.set_item("local_bbox", c.bbox.map(as_tuple))?;
+            d.set_item("full_bbox", self.inner.full_bbox(&c.name).map(as_tuple))?;
+            d.set_item("full_bbox_um", self.inner.full_bbox_um(&c.name))?;
             d.set_item("total_polys", c.total_polys)?;
             let lp = PyDict::new(py);
             for ((lay, dt), cnt) in &c.layer_poly_counts {
                 lp.set_item(format!("{},{}", lay, dt), *cnt)?;
             }
             d.set_item("layer_poly_counts", lp)?;
+            d.set_item("total_paths", c.total_paths)?;
+            let lpa = PyDict::new(py);
+            for ((lay, dt), cnt) in &c.layer_path_counts {
+                lpa.set_item(format!("{},{}", lay, dt), *cnt)?;
+            }
+            d.set_item("layer_path_counts", lpa)?;
+            let refs = PyList::empty(py);
+            for r in self.inner.refs_for(&c.name).map_err(to_pyerr)? {
+                let rd = PyDict::new(py);
+                rd.set_item("cell", &r.name)?;
+                rd.set_item("origin", r.origin)?;
+                set_strans(&rd, &r.strans)?;
+                refs.append(rd)?;
+            }
+            d.set_item("refs", refs)?;
+            let arefs = PyList::empty(py);
+            for a in self.inner.arefs_for(&c.name).map_err(to_pyerr)? {
+                let ad = PyDict::new(py);
+                ad.set_item("cell", &a.name)?;
+                ad.set_item("cols", a.cols)?;
+                ad.set_item("rows", a.rows)?;
+                ad.set_item("origin", a.origin)?;
+                ad.set_item("col_corner", a.col_corner)?;
+                ad.set_item("row_corner", a.row_corner)?;
+                set_strans(&ad, &a.strans)?;
+                arefs.append(ad)?;
+            }
+            d.set_item("arefs", arefs)?;
             cells.append(d)?;
         }
         out.set_item("cells", cells)?;
@@ -63,23 +479,171 @@ impl PyLayout {
     /// [{"name": str, "polys": [{"layer":int,"datatype":int,"xy":[(x,y),..]}]}]
     fn polygons<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
         let cells_out = PyList::empty(py);
-        for c in self.inner.polygons() {
+        for c in self.inner.try_polygons().map_err(to_pyerr)? {
+            let d = PyDict::new(py);
+            d.set_item("name", &c.name)?;
+            d.set_item("polys", poly_list(py, &c.polys)?)?;
+            cells_out.append(d)?;
+        }
+        Ok(cells_out)
+    }
+
+    /// Flattened polygons of `cell` (the only top cell if None) down to `depth` levels,
+    /// shaped like `polygons()`: [{"name": cell, "polys": [...]}].
+    #[pyo3(signature = (cell=None, depth=None))]
+    fn flatten<'py>(
+        &self,
+        py: Python<'py>,
+        cell: Option<String>,
+        depth: Option<usize>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let cell = match cell {
+            Some(c) => c,
+            None => {
+                let mut tops = self.inner.top_cells().map_err(to_pyerr)?;
+                if tops.len() != 1 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "layout has {} top cells; pass the cell to flatten",
+                        tops.len()
+                    )));
+                }
+                tops.remove(0)
+            }
+        };
+        let polys = self.inner.flatten(&cell, depth).map_err(to_pyerr)?;
+        let d = PyDict::new(py);
+        d.set_item("name", &cell)?;
+        d.set_item("polys", poly_list(py, &polys)?)?;
+        PyList::new(py, [d])
+    }
+
+    /// Polygons of one cell as [{"layer","datatype","xy"}]; after `open_gds` only this
+    /// cell is decoded. Raises CellNotFoundError for unknown cells.
+    fn polygons_for<'py>(&self, py: Python<'py>, cell: &str) -> PyResult<Bound<'py, PyList>> {
+        let polys = &self.inner.cell(cell).map_err(to_pyerr)?.polys;
+        poly_list(py, polys)
+    }
+
+    /// Labels of one cell as [{"layer","texttype","string","position":(x,y),"anchor":str,
+    /// "reflect","magnification","rotation"}]; raises CellNotFoundError for unknown cells.
+    fn labels_for<'py>(&self, py: Python<'py>, cell: &str) -> PyResult<Bound<'py, PyList>> {
+        let labels = &self.inner.cell(cell).map_err(to_pyerr)?.labels;
+        let out = PyList::empty(py);
+        for l in labels {
+            let ld = PyDict::new(py);
+            ld.set_item("layer", l.layer)?;
+            ld.set_item("texttype", l.texttype)?;
+            ld.set_item("string", &l.string)?;
+            ld.set_item("position", l.position)?;
+            ld.set_item("anchor", l.anchor.as_str())?;
+            ld.set_item("reflect", l.reflect_x)?;
+            ld.set_item("magnification", l.magnification)?;
+            ld.set_item("rotation", l.rotation)?;
+            out.append(ld)?;
+        }
+        Ok(out)
+    }
+
+    fn top_cells(&self) -> PyResult<Vec<String>> {
+        self.inner.top_cells().map_err(to_pyerr)
+    }
+
+    /// Cells placed directly in `cell`; raises CellNotFoundError for unknown cells.
+    fn children(&self, cell: &str) -> PyResult<Vec<String>> {
+        let children = self
+            .inner
+            .cell_graph()
+            .map_err(to_pyerr)?
+            .children(cell)
+            .map_err(to_pyerr)?;
+        Ok(children.into_iter().map(str::to_string).collect())
+    }
+
+    /// Cells placing `cell` directly; raises CellNotFoundError for unknown cells.
+    fn parents(&self, cell: &str) -> PyResult<Vec<String>> {
+        let parents = self
+            .inner
+            .cell_graph()
+            .map_err(to_pyerr)?
+            .parents(cell)
+            .map_err(to_pyerr)?;
+        Ok(parents.into_iter().map(str::to_string).collect())
+    }
+
+    /// All cells, children before parents; raises HierarchyError for undefined
+    /// references and cycles.
+    fn topological_order(&self) -> PyResult<Vec<String>> {
+        let order = self
+            .inner
+            .cell_graph()
+            .map_err(to_pyerr)?
+            .topological_order()
+            .map_err(to_pyerr)?;
+        Ok(order.into_iter().map(str::to_string).collect())
+    }
+
+    fn depth(&self, cell: &str) -> PyResult<usize> {
+        self.inner
+            .cell_graph()
+            .map_err(to_pyerr)?
+            .depth(cell)
+            .map_err(to_pyerr)
+    }
+
+    /// [(cell, undefined referenced name)].
+    fn undefined_references(&self) -> PyResult<Vec<(String, String)>> {
+        let refs = self
+            .inner
+            .cell_graph()
+            .map_err(to_pyerr)?
+            .undefined_references();
+        Ok(refs
+            .into_iter()
+            .map(|(c, r)| (c.to_string(), r.to_string()))
+            .collect())
+    }
+
+    /// Warnings from the last GDS load as [{"kind": str, "offset": int,
+    /// "cell": str|None, "message": str}].
+    fn warnings<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let out = PyList::empty(py);
+        for w in self.inner.warnings() {
+            let wd = PyDict::new(py);
+            wd.set_item("kind", w.kind.as_str())?;
+            wd.set_item("offset", w.offset)?;
+            wd.set_item("cell", w.cell.as_deref())?;
+            wd.set_item("message", &w.message)?;
+            out.append(wd)?;
+        }
+        Ok(out)
+    }
+
+    /// Return paths grouped per cell as:
+    /// [{"name": str, "paths": [{"layer":int,"datatype":int,"width":int,"pathtype":int,
+    ///   "bgnextn":int,"endextn":int,"xy":[(x,y),..],"outline":[(x,y),..]|None}]}]
+    fn paths<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let cells_out = PyList::empty(py);
+        for c in self.inner.try_polygons().map_err(to_pyerr)? {
             let d = PyDict::new(py);
             d.set_item("name", &c.name)?;
-            let polys = PyList::empty(py);
-            for p in &c.polys {
+            let paths = PyList::empty(py);
+            for p in &c.paths {
                 let pd = PyDict::new(py);
                 pd.set_item("layer", p.layer)?;
                 pd.set_item("datatype", p.datatype)?;
-                // Convert points to a Python list of (x,y) tuples
+                pd.set_item("width", p.width)?;
+                pd.set_item("pathtype", p.pathtype)?;
+                pd.set_item("bgnextn", p.bgnextn)?;
+                pd.set_item("endextn", p.endextn)?;
                 let pts = PyList::empty(py);
                 for (x, y) in &p.xy {
                     pts.append((*x, *y))?;
                 }
                 pd.set_item("xy", pts)?;
-                polys.append(pd)?;
+                pd.set_item("outline", p.to_polygon().map(|o| o.xy))?;
+                paths.append(pd)?;
             }
-            d.set_item("polys", polys)?;
+            d.set_item("paths", paths)?;
             cells_out.append(d)?;
         }
         Ok(cells_out)
@@ -89,5 +653,17 @@ impl PyLayout {
 #[pymodule]
 fn _lowlevel(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyLayout>()?;
+    let py = m.py();
+    m.add("GoasperError", py.get_type::<GoasperError>())?;
+    m.add("GoasperIOError", py.get_type::<GoasperIOError>())?;
+    m.add("MalformedFileError", py.get_type::<MalformedFileError>())?;
+    m.add("CellNotFoundError", py.get_type::<CellNotFoundError>())?;
+    m.add(
+        "UnsupportedFeatureError",
+        py.get_type::<UnsupportedFeatureError>(),
+    )?;
+    m.add("WriteError", py.get_type::<WriteError>())?;
+    m.add("DuplicateCellError", py.get_type::<DuplicateCellError>())?;
+    m.add("HierarchyError", py.get_type::<HierarchyError>())?;
     Ok(())
 }
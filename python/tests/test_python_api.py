@@ -1,6 +1,278 @@
-from goasper import Layout
+import pytest
+
+from goasper import (
+    CellNotFoundError,
+    DuplicateCellError,
+    GoasperError,
+    GoasperIOError,
+    Layout,
+    MalformedFileError,
+)
 
 
 def test_smoke():
     layout = Layout()
     layout.load_gds("examples/nand2.gds2")
+
+
+def test_oas_roundtrip(tmp_path):
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    out = tmp_path / "nand2.oas"
+    layout.save_oas(out)
+
+    back = Layout()
+    back.load_oas(out)
+    assert back.cells() == layout.cells()
+    assert back.polygons() == layout.polygons()
+
+
+def test_gds_roundtrip(tmp_path):
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    out = tmp_path / "nand2.gds"
+    layout.save_gds(out)
+
+    back = Layout()
+    back.load_gds(out)
+    assert back.polygons() == layout.polygons()
+    assert back.summary()["libname"] == layout.summary()["libname"]
+
+
+def test_summary_lists_instances():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    for cell in layout.summary()["cells"]:
+        assert isinstance(cell["refs"], list)
+        assert isinstance(cell["arefs"], list)
+
+
+def test_labels_for():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    for name in layout.cells():
+        for label in layout.labels_for(name):
+            assert isinstance(label["string"], str)
+            assert len(label["position"]) == 2
+    with pytest.raises(CellNotFoundError):
+        layout.labels_for("__missing__")
+
+
+def test_units():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    assert layout.dbu_in_meters() > 0
+    assert layout.user_units_per_dbu() > 0
+    s = layout.summary()
+    assert s["dbu_in_meters"] == layout.dbu_in_meters()
+    for cell in s["cells"]:
+        if cell["bbox"] is not None:
+            assert layout.bbox_um(cell["name"]) == cell["bbox_um"]
+
+
+def test_load_gds_from_bytes_and_file_like():
+    import io
+
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    with open("examples/nand2.gds2", "rb") as f:
+        data = f.read()
+
+    from_bytes = Layout()
+    from_bytes.load_gds(data)
+    assert from_bytes.polygons() == layout.polygons()
+
+    from_buffer = Layout()
+    from_buffer.load_gds(io.BytesIO(data))
+    assert from_buffer.cells() == layout.cells()
+
+    with open("examples/nand2.gds2", "rb") as f:
+        from_file = Layout()
+        from_file.load_gds(f)
+    assert from_file.cells() == layout.cells()
+
+
+def test_compressed_gds_roundtrip(tmp_path):
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    out = tmp_path / "nand2.gds.gz"
+    layout.save_gds(out)
+    with open(out, "rb") as f:
+        assert f.read(2) == b"\x1f\x8b"
+
+    back = Layout()
+    back.load_gds(out)
+    assert back.polygons() == layout.polygons()
+
+
+def test_exception_hierarchy():
+    layout = Layout()
+    with pytest.raises(GoasperIOError):
+        layout.load_gds("/nonexistent/goasper.gds")
+    with pytest.raises(MalformedFileError) as info:
+        layout.load_gds(b"\x00\x02\x00\x00")
+    assert info.value.offset == 0
+    assert info.value.rectype == 0
+    assert issubclass(MalformedFileError, GoasperError)
+    assert issubclass(CellNotFoundError, GoasperError)
+    # load failures used to raise IOError; existing handlers must still catch them
+    assert issubclass(GoasperIOError, OSError)
+    assert issubclass(MalformedFileError, OSError)
+    with pytest.raises(OSError):
+        layout.load_gds("/nonexistent/goasper.gds")
+
+
+def test_lenient_load_reports_warnings():
+    with open("examples/nand2.gds2", "rb") as f:
+        data = f.read()
+    cut = data[: len(data) * 2 // 3 + 1]
+    with pytest.raises(MalformedFileError):
+        Layout().load_gds(cut)
+
+    layout = Layout(strict=False)
+    layout.load_gds(cut)
+    assert layout.cells()
+    kinds = [w["kind"] for w in layout.warnings()]
+    assert kinds[0] == "bad_record"
+    assert kinds[-1] == "missing_endlib"
+    assert all(isinstance(w["offset"], int) for w in layout.warnings())
+
+
+def test_parallel_load_matches_sequential():
+    seq = Layout()
+    seq.load_gds("examples/nand2.gds2")
+    par = Layout()
+    par.load_gds_parallel("examples/nand2.gds2")
+    assert par.cells() == seq.cells()
+    assert par.polygons() == seq.polygons()
+
+
+def test_open_gds_decodes_single_cell():
+    eager = Layout()
+    eager.load_gds("examples/nand2.gds2")
+    lazy = Layout()
+    lazy.open_gds("examples/nand2.gds2")
+    assert lazy.cells() == eager.cells()
+    expected = next(c["polys"] for c in eager.polygons() if c["name"] == "nand2")
+    assert lazy.polygons_for("nand2") == expected
+    with pytest.raises(CellNotFoundError):
+        lazy.polygons_for("missing")
+
+
+def test_summary_full_bbox_covers_local_bbox():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    for cell in layout.summary()["cells"]:
+        assert cell["local_bbox"] == cell["bbox"]
+        local, full = cell["local_bbox"], cell["full_bbox"]
+        if local is not None:
+            assert full[0] <= local[0] and full[1] <= local[1]
+            assert full[2] >= local[2] and full[3] >= local[3]
+
+
+def test_cell_graph():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    order = layout.topological_order()
+    assert sorted(order) == sorted(layout.cells())
+    for cell in order:
+        for child in layout.children(cell):
+            assert order.index(child) < order.index(cell)
+            assert cell in layout.parents(child)
+    for top in layout.top_cells():
+        assert layout.parents(top) == []
+        assert layout.depth(top) >= 0
+    assert layout.undefined_references() == []
+    with pytest.raises(CellNotFoundError):
+        layout.children("__missing__")
+
+
+def test_flatten_matches_polygons_shape():
+    layout = Layout()
+    layout.load_gds("examples/nand2.gds2")
+    top = layout.top_cells()[0]
+    flat = layout.flatten(top)
+    assert [c["name"] for c in flat] == [top]
+    for p in flat[0]["polys"]:
+        assert set(p) == {"layer", "datatype", "xy"}
+    own = layout.flatten(top, depth=0)[0]["polys"]
+    assert len(own) <= len(flat[0]["polys"])
+    with pytest.raises(CellNotFoundError):
+        layout.flatten("__missing__")
+
+
+def test_build_library_from_scratch(tmp_path):
+    layout = Layout.new_library("NEW")
+    layout.add_cell("LEAF")
+    layout.add_polygon("LEAF", 1, 0, [(0, 0), (10, 0), (10, 10), (0, 10)])
+    layout.add_path("LEAF", 2, 0, [(0, 0), (20, 0)], 4, pathtype=2)
+    layout.add_label("LEAF", 5, 0, "A", (1, 1))
+    layout.add_cell("TOP")
+    layout.add_ref("TOP", "LEAF", (100, 0))
+    with pytest.raises(DuplicateCellError):
+        layout.add_cell("TOP")
+    leaf = layout.summary()["cells"][0]
+    assert leaf["bbox"] == (-2, -2, 22, 10)
+    assert layout.top_cells() == ["TOP"]
+
+    layout.rename_cell("LEAF", "UNIT")
+    assert layout.children("TOP") == ["UNIT"]
+    assert layout.remove_polygon("UNIT", 0)
+    assert not layout.remove_polygon("UNIT", 0)
+
+    out = tmp_path / "new.gds"
+    layout.save_gds(out)
+    back = Layout()
+    back.load_gds(out)
+    assert back.cells() == ["UNIT", "TOP"]
+    layout.remove_cell("UNIT")
+    assert layout.undefined_references() == [("TOP", "UNIT")]
+
+
+def test_boolean_ops():
+    layout = Layout.new_library("LIB")
+    layout.add_cell("C")
+    layout.add_polygon("C", 1, 0, [(0, 0), (10, 0), (10, 10), (0, 10)])
+    layout.add_polygon("C", 2, 0, [(5, 5), (15, 5), (15, 15), (5, 15)])
+    assert layout.boolean("C", (1, 0), (2, 0), "and", (3, 0)) == 1
+    assert layout.polygons_for("C")[-1]["xy"] == [(5, 5), (10, 5), (10, 10), (5, 10)]
+    assert layout.boolean("C", (1, 0), (2, 0), "xor", (4, 0)) == 2
+    with pytest.raises(ValueError):
+        layout.boolean("C", (1, 0), (2, 0), "nand", (5, 0))
+
+
+def test_size_layers():
+    layout = Layout.new_library("LIB")
+    layout.add_cell("C")
+    layout.add_polygon("C", 1, 0, [(0, 0), (10, 0), (10, 10), (0, 10)])
+    layout.add_polygon("C", 1, 0, [(12, 0), (20, 0), (20, 10), (12, 10)])
+    assert layout.size("C", (1, 0), 1, (2, 0)) == 1
+    assert layout.polygons_for("C")[-1]["xy"] == [(-1, -1), (21, -1), (21, 11), (-1, 11)]
+    assert layout.size("C", (1, 0), (-2, 0), (3, 0)) == 2
+    assert layout.size("C", (1, 0), 100, (4, 0), corner="round", segments=16) == 1
+    with pytest.raises(ValueError):
+        layout.size("C", (1, 0), 1, (5, 0), corner="bevel")
+
+
+def test_merge_layers():
+    layout = Layout.new_library("LIB")
+    layout.add_cell("C")
+    for x in (0, 5, 10):
+        layout.add_polygon("C", 1, 0, [(x, 0), (x + 10, 0), (x + 10, 10), (x, 10)])
+    layout.add_polygon("C", 2, 0, [(0, 0), (1, 0), (1, 1), (0, 1)])
+    assert layout.summary()["cells"][0]["layer_poly_counts"]["1,0"] == 3
+    assert layout.merge_layer("C", (1, 0)) == 1
+    assert layout.polygons_for("C")[-1]["xy"] == [(0, 0), (20, 0), (20, 10), (0, 10)]
+    assert layout.merge("C") == 2
+
+
+def test_path_outlines():
+    layout = Layout.new_library("LIB")
+    layout.add_cell("C")
+    layout.add_path("C", 1, 0, [(0, 0), (0, 10)], 5, pathtype=2)
+    layout.add_path("C", 1, 0, [(0, 0), (100, 0), (50, 0)], 10)
+    layout.add_path("C", 1, 0, [(0, 0), (0, 0)], 10)
+    paths = layout.paths()[0]["paths"]
+    assert paths[0]["outline"] == [(-2, -2), (-2, 13), (3, 13), (3, -2)]
+    assert (105, 5) in paths[1]["outline"]
+    assert paths[2]["outline"] is None